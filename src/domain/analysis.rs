@@ -0,0 +1,276 @@
+use crate::domain::prompting::detect_category;
+use crate::domain::validation::{check_consistency, check_formatting, check_grammar_and_clarity};
+use serde::Serialize;
+
+/// Words that leave the model guessing what is meant
+const VAGUE_WORDS: &[&str] = &["something", "stuff", "things", "thing", "etc", "somehow", "whatever", "various", "nice", "good", "better", "some", "maybe"];
+const VAGUE_PHRASES: &[&str] = &["kind of", "sort of", "and so on"];
+/// References that need an antecedent when they open a prompt
+const DANGLING_REFERENCES: &[&str] = &["it", "this", "that", "these", "those", "them"];
+
+const FORMAT_MARKERS: &[&str] = &[
+    "format", "json", "yaml", "csv", "markdown", "table", "list", "bullet", "numbered", "paragraph", "sentence", "words",
+    "outline", "heading", "section", "code", "step by step", "step-by-step", "summary", "essay", "email", "report", "diagram",
+];
+const CONSTRAINT_MARKERS: &[&str] = &[
+    "must", "should", "only", "avoid", "don't", "do not", "without", "at most", "at least", "no more than", "limit",
+    "within", "under", "maximum", "minimum", "exactly", "never", "always", "require",
+];
+const CONTEXT_MARKERS: &[&str] = &[
+    "because", "so that", "in order to", "audience", "background", "context", "goal", "purpose", "i am", "i'm", "we are",
+    "we're", "my", "our", "currently", "given", "using", "as a",
+];
+/// Prompts this long are assumed to carry some context even without a marker
+const CONTEXT_WORD_COUNT: usize = 40;
+
+/// One aspect of a prompt that `analyze` looks at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Check {
+    Clarity,
+    Consistency,
+    Formatting,
+    Ambiguity,
+    OutputFormat,
+    Constraints,
+    Context,
+}
+
+impl Check {
+    /// The name used in reports, same as the serialized form
+    pub fn name(self) -> &'static str {
+        match self {
+            Check::Clarity => "clarity",
+            Check::Consistency => "consistency",
+            Check::Formatting => "formatting",
+            Check::Ambiguity => "ambiguity",
+            Check::OutputFormat => "output_format",
+            Check::Constraints => "constraints",
+            Check::Context => "context",
+        }
+    }
+
+    /// Share of the score; what the prompt asks for counts more than how it is typed
+    fn weight(self) -> f32 {
+        match self {
+            Check::Clarity | Check::Consistency => 1.0,
+            Check::Formatting => 0.5,
+            Check::Ambiguity | Check::OutputFormat | Check::Constraints | Check::Context => 1.5,
+        }
+    }
+
+    fn suggestion(self) -> &'static str {
+        match self {
+            Check::Clarity => "Split long sentences, merge fragments and clean up spacing.",
+            Check::Consistency => "Remove repeated sentences.",
+            Check::Formatting => "Fix the spacing around punctuation.",
+            Check::Ambiguity => "Replace vague words with concrete details and name what you refer to.",
+            Check::OutputFormat => "Say what the answer should look like, e.g. a bulleted list, a table or JSON with given fields.",
+            Check::Constraints => "Add limits such as length, scope, or what to include and avoid.",
+            Check::Context => "Explain who the result is for and why you need it.",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckResult {
+    pub check: Check,
+    pub passed: bool,
+    /// what the check found; empty when it passed
+    pub issues: Vec<String>,
+    /// how to fix the prompt, when the check failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<&'static str>,
+}
+
+impl CheckResult {
+    fn new(check: Check, issues: Vec<String>) -> Self {
+        let passed = issues.is_empty();
+        Self { check, passed, issues, suggestion: (!passed).then(|| check.suggestion()) }
+    }
+}
+
+/// Local diagnosis of a prompt, made without an LLM
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptAnalysis {
+    /// weighted share of passed checks, from 0 to 1
+    pub score: f32,
+    pub category: &'static str,
+    pub word_count: usize,
+    pub checks: Vec<CheckResult>,
+}
+
+impl PromptAnalysis {
+    pub fn failed(&self) -> impl Iterator<Item = &CheckResult> {
+        self.checks.iter().filter(|result| !result.passed)
+    }
+}
+
+pub fn analyze(text: &str) -> PromptAnalysis {
+    let lower = text.to_lowercase();
+    let words = words(&lower);
+
+    let checks = vec![
+        CheckResult::new(Check::Clarity, check_grammar_and_clarity(text)),
+        CheckResult::new(Check::Consistency, check_consistency(text)),
+        CheckResult::new(Check::Formatting, check_formatting(text)),
+        CheckResult::new(Check::Ambiguity, check_ambiguity(&lower, &words)),
+        CheckResult::new(Check::OutputFormat, check_output_format(&lower, &words)),
+        CheckResult::new(Check::Constraints, check_constraints(&lower, &words)),
+        CheckResult::new(Check::Context, check_context(&lower, &words)),
+    ];
+
+    let total: f32 = checks.iter().map(|result| result.check.weight()).sum();
+    let passed: f32 = checks.iter().filter(|result| result.passed).map(|result| result.check.weight()).sum();
+    PromptAnalysis { score: passed / total, category: detect_category(text), word_count: words.len(), checks }
+}
+
+fn check_ambiguity(lower: &str, words: &[&str]) -> Vec<String> {
+    let mut issues = Vec::new();
+    let mut vague: Vec<&str> = VAGUE_WORDS.iter().copied().filter(|term| words.contains(term)).collect();
+    vague.extend(VAGUE_PHRASES.iter().copied().filter(|phrase| contains_phrase(lower, phrase)));
+    if !vague.is_empty() {
+        issues.push(format!("Vague terms: {}", vague.join(", ")));
+    }
+    if let Some(first) = words.first().filter(|first| DANGLING_REFERENCES.contains(first)) {
+        issues.push(format!("Starts with \"{}\" without saying what it refers to", first));
+    }
+    issues
+}
+
+fn check_output_format(lower: &str, words: &[&str]) -> Vec<String> {
+    if mentions_any(lower, words, FORMAT_MARKERS) {
+        return Vec::new();
+    }
+    vec!["No output format requested".to_string()]
+}
+
+fn check_constraints(lower: &str, words: &[&str]) -> Vec<String> {
+    // Any number usually bounds the answer: "300 words", "3 examples", "Python 3.12"
+    if mentions_any(lower, words, CONSTRAINT_MARKERS) || lower.chars().any(|c| c.is_ascii_digit()) {
+        return Vec::new();
+    }
+    vec!["No constraints such as length, scope or exclusions".to_string()]
+}
+
+fn check_context(lower: &str, words: &[&str]) -> Vec<String> {
+    if words.len() >= CONTEXT_WORD_COUNT || mentions_any(lower, words, CONTEXT_MARKERS) {
+        return Vec::new();
+    }
+    vec!["No context about the purpose or audience".to_string()]
+}
+
+/// Lowercase words, split at anything but letters, digits, `'` and `-`
+fn words(lower: &str) -> Vec<&str> {
+    lower
+        .split(|c: char| !(c.is_alphanumeric() || c == '\'' || c == '-'))
+        .map(|word| word.trim_matches(['\'', '-']))
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Single-word markers match whole words, including a trailing plural `s`;
+/// longer markers match as phrases
+fn mentions_any(lower: &str, words: &[&str], markers: &[&str]) -> bool {
+    markers.iter().any(|marker| {
+        if marker.contains(' ') {
+            contains_phrase(lower, marker)
+        } else {
+            words.iter().any(|word| word == marker || word.strip_suffix('s') == Some(marker))
+        }
+    })
+}
+
+fn contains_phrase(lower: &str, phrase: &str) -> bool {
+    lower.match_indices(phrase).any(|(start, _)| {
+        let before = lower[..start].chars().next_back();
+        let after = lower[start + phrase.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failed(text: &str) -> Vec<Check> {
+        analyze(text).failed().map(|result| result.check).collect()
+    }
+
+    #[test]
+    fn test_bare_prompt_misses_what_it_asks_for() {
+        let analysis = analyze("write something about dogs");
+
+        assert_eq!(failed("write something about dogs"), vec![Check::Ambiguity, Check::OutputFormat, Check::Constraints, Check::Context]);
+        assert!(analysis.score < 0.4);
+        assert_eq!(analysis.word_count, 4);
+        let ambiguity = &analysis.checks[3];
+        assert_eq!(ambiguity.issues, vec!["Vague terms: something"]);
+        assert!(ambiguity.suggestion.is_some());
+    }
+
+    #[test]
+    fn test_complete_prompt_passes_every_check() {
+        let text = "Summarize the attached incident report for our on-call engineers. \
+                    Use a bulleted list of at most five items. Leave out customer names.";
+
+        let analysis = analyze(text);
+
+        assert!(analysis.failed().next().is_none(), "{:?}", analysis.failed().collect::<Vec<_>>());
+        assert_eq!(analysis.score, 1.0);
+        assert!(analysis.checks.iter().all(|result| result.suggestion.is_none()));
+    }
+
+    #[test]
+    fn test_existing_checks_are_included() {
+        let analysis = analyze("Write a haiku  about rain , please. Write a haiku  about rain , please.");
+
+        let issues: Vec<_> = analysis.checks[..3].iter().flat_map(|result| result.issues.clone()).collect();
+        assert!(issues.contains(&"Contains double spaces".to_string()));
+        assert!(issues.contains(&"Duplicate sentence found".to_string()));
+        assert!(issues.contains(&"Inconsistent spacing around punctuation".to_string()));
+    }
+
+    #[test]
+    fn test_names_match_serialized_form() {
+        for check in [Check::Clarity, Check::Consistency, Check::Formatting, Check::Ambiguity, Check::OutputFormat, Check::Constraints, Check::Context] {
+            assert_eq!(serde_json::to_value(check).unwrap(), check.name());
+        }
+    }
+
+    #[test]
+    fn test_ambiguity() {
+        let lower = "make it better, kind of like the old one";
+        assert_eq!(check_ambiguity(lower, &words(lower)), vec!["Vague terms: better, kind of"]);
+
+        let lower = "this crashes the parser, fix it";
+        assert_eq!(check_ambiguity(lower, &words(lower)), vec!["Starts with \"this\" without saying what it refers to"]);
+
+        // Markers only match whole words
+        let lower = "summarize the handbook";
+        assert!(check_ambiguity(lower, &words(lower)).is_empty());
+    }
+
+    #[test]
+    fn test_markers_match_whole_words_and_plurals() {
+        let lower = "give three examples in bullets";
+        assert!(check_output_format(lower, &words(lower)).is_empty());
+
+        let lower = "listen to the song";
+        assert!(!check_output_format(lower, &words(lower)).is_empty());
+    }
+
+    #[test]
+    fn test_numbers_count_as_constraints() {
+        let lower = "write a 300 word story";
+        assert!(check_constraints(lower, &words(lower)).is_empty());
+    }
+
+    #[test]
+    fn test_long_prompts_have_context() {
+        let lower = "describe ".repeat(CONTEXT_WORD_COUNT);
+        assert!(check_context(&lower, &words(&lower)).is_empty());
+    }
+}
@@ -1,4 +1,7 @@
 use itertools::Itertools;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
 pub struct FewShotExample {
     pub input: String,
     pub output: String,
@@ -45,6 +48,11 @@ pub fn select_examples(category: &str, limit: usize) -> Vec<FewShotExample> {
         .collect()
 }
 
+/// Every category that has at least one example, sorted
+pub fn categories() -> Vec<String> {
+    get_examples().into_iter().map(|ex| ex.category).sorted().dedup().collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -70,4 +78,9 @@ mod tests {
         assert_eq!(examples[0].input, "Write a simple hello world program"); // 0.95 quality
         assert_eq!(examples[1].input, "Write a function to calculate factorial"); // 0.9 quality
     }
+
+    #[test]
+    fn test_categories() {
+        assert_eq!(categories(), vec!["code", "definition", "explanation"]);
+    }
 }
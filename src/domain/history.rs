@@ -0,0 +1,124 @@
+use crate::domain::models::{EnhancedPrompt, EnhancementOptions};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How many enhancements are kept per client when no capacity is given
+pub const DEFAULT_HISTORY_CAPACITY: usize = 100;
+
+/// One completed enhancement
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryEntry {
+    pub id: u64,
+    /// the client that asked for it; entries are only shown to that client
+    #[serde(skip)]
+    pub owner: Option<String>,
+    /// seconds since the Unix epoch
+    pub created_at: u64,
+    pub prompt: String,
+    pub options: EnhancementOptions,
+    pub result: EnhancedPrompt,
+}
+
+/// The most recent enhancements of each client, oldest dropped first. Ids keep
+/// increasing for the life of the process, so an evicted id is never reused.
+#[derive(Debug)]
+pub struct EnhancementHistory {
+    capacity: usize,
+    inner: Mutex<Entries>,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    next_id: u64,
+    entries: VecDeque<HistoryEntry>,
+}
+
+impl Default for EnhancementHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_CAPACITY)
+    }
+}
+
+impl EnhancementHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity: capacity.max(1), inner: Mutex::default() }
+    }
+
+    /// Add an entry for `owner`, returning it and the entry of the same owner
+    /// dropped to make room, if any
+    pub fn record(&self, owner: Option<&str>, prompt: &str, options: &EnhancementOptions, result: &EnhancedPrompt) -> (HistoryEntry, Option<HistoryEntry>) {
+        let created_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let mut inner = self.inner.lock().unwrap();
+        inner.next_id += 1;
+        let entry = HistoryEntry { id: inner.next_id, owner: owner.map(str::to_string), created_at, prompt: prompt.to_string(), options: options.clone(), result: result.clone() };
+        let evicted = match inner.entries.iter().filter(|e| e.owner.as_deref() == owner).count() == self.capacity {
+            true => inner.entries.iter().position(|e| e.owner.as_deref() == owner).and_then(|oldest| inner.entries.remove(oldest)),
+            false => None,
+        };
+        inner.entries.push_back(entry.clone());
+        (entry, evicted)
+    }
+
+    /// The entries of `owner`, newest first
+    pub fn list(&self, owner: Option<&str>) -> Vec<HistoryEntry> {
+        self.inner.lock().unwrap().entries.iter().rev().filter(|entry| entry.owner.as_deref() == owner).cloned().collect()
+    }
+
+    pub fn get(&self, id: u64, owner: Option<&str>) -> Option<HistoryEntry> {
+        self.inner.lock().unwrap().entries.iter().find(|entry| entry.id == id && entry.owner.as_deref() == owner).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enhanced(text: &str) -> EnhancedPrompt {
+        EnhancedPrompt { text: text.to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn test_record_assigns_increasing_ids() {
+        let history = EnhancementHistory::default();
+
+        let (first, _) = history.record(None, "a", &EnhancementOptions::default(), &enhanced("A"));
+        let (second, _) = history.record(None, "b", &EnhancementOptions::default(), &enhanced("B"));
+
+        assert_eq!((first.id, second.id), (1, 2));
+        assert_eq!(history.get(2, None).unwrap().result.text, "B");
+        let prompts: Vec<_> = history.list(None).into_iter().map(|e| e.prompt).collect();
+        assert_eq!(prompts, vec!["b", "a"]);
+    }
+
+    #[test]
+    fn test_oldest_entry_is_evicted() {
+        let history = EnhancementHistory::new(2);
+
+        for prompt in ["a", "b"] {
+            assert!(history.record(None, prompt, &EnhancementOptions::default(), &enhanced(prompt)).1.is_none());
+        }
+        let (_, evicted) = history.record(None, "c", &EnhancementOptions::default(), &enhanced("c"));
+
+        assert_eq!(evicted.unwrap().id, 1);
+        assert!(history.get(1, None).is_none());
+        assert_eq!(history.list(None).len(), 2);
+        assert_eq!(history.get(3, None).unwrap().prompt, "c");
+    }
+
+    #[test]
+    fn test_clients_only_see_their_own_entries() {
+        let history = EnhancementHistory::new(1);
+
+        history.record(Some("alice"), "a", &EnhancementOptions::default(), &enhanced("A"));
+        let (_, evicted) = history.record(Some("bob"), "b", &EnhancementOptions::default(), &enhanced("B"));
+
+        assert!(evicted.is_none());
+        assert_eq!(history.list(Some("alice")).len(), 1);
+        assert!(history.get(1, Some("bob")).is_none());
+        assert!(history.get(1, None).is_none());
+        assert_eq!(history.get(2, Some("bob")).unwrap().prompt, "b");
+        assert!(history.list(None).is_empty());
+    }
+}
@@ -1,5 +1,9 @@
 use crate::domain::models::{EnhancedPrompt, EnhancementOptions, Prompt};
+use crate::domain::prompting::CompletionRequest;
 use async_trait::async_trait;
+use serde_json::Value;
+use std::fmt;
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -10,11 +14,95 @@ pub enum LLMError {
     RequestFailed(String),
     #[error("unexpected response: {0}")]
     UnexpectedResponse(String),
+    /// the provider rejected the credentials or the key lacks permission
+    #[error("unauthorized: {0}")]
+    Unauthorized(ProviderError),
+    #[error("rate limited: {error}")]
+    RateLimited { retry_after: Option<Duration>, error: ProviderError },
+    /// the prompt plus requested output does not fit the model's context window
+    #[error("context length exceeded: {0}")]
+    ContextLengthExceeded(ProviderError),
+    /// the provider's moderation refused the input or the output
+    #[error("content filtered: {0}")]
+    ContentFiltered(ProviderError),
+    #[error("timed out: {0}")]
+    Timeout(String),
+    #[error("model not found: {0}")]
+    ModelNotFound(ProviderError),
 }
 
+/// HTTP status, message and raw body of a failed provider response
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderError {
+    pub status: u16,
+    pub message: String,
+    /// the provider's error body, when it was valid JSON
+    pub body: Option<Value>,
+}
+
+impl ProviderError {
+    pub fn new(status: u16, message: impl Into<String>) -> Self {
+        Self { status, message: message.into(), body: None }
+    }
+
+    pub fn with_body(mut self, body: Option<Value>) -> Self {
+        self.body = body;
+        self
+    }
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "status {}: {}", self.status, self.message)
+    }
+}
+
+impl LLMError {
+    /// Whether the same request may succeed if sent again later
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, LLMError::RequestFailed(_) | LLMError::RateLimited { .. } | LLMError::Timeout(_))
+    }
+
+    /// Details of the provider response that caused the error, if any
+    pub fn provider_error(&self) -> Option<&ProviderError> {
+        match self {
+            LLMError::Unauthorized(error)
+            | LLMError::RateLimited { error, .. }
+            | LLMError::ContextLengthExceeded(error)
+            | LLMError::ContentFiltered(error)
+            | LLMError::ModelNotFound(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Receives chunks of generated text as they arrive
+pub type TokenSink<'a> = &'a (dyn Fn(&str) + Send + Sync);
+
 #[async_trait]
 pub trait LLMProvider: Send + Sync {
     async fn enhance(&self, prompt: Prompt, options: EnhancementOptions) -> Result<EnhancedPrompt, LLMError>;
+
+    /// Like `enhance`, but reports generated text through `on_token` while the response
+    /// is streamed. Providers without streaming support report the whole text at once.
+    async fn enhance_streaming(&self, prompt: Prompt, options: EnhancementOptions, on_token: TokenSink<'_>) -> Result<EnhancedPrompt, LLMError> {
+        let enhanced = self.enhance(prompt, options).await?;
+        on_token(&enhanced.text);
+        Ok(enhanced)
+    }
+
+    /// Answer any single-turn request, such as a critique of a draft. Providers
+    /// that only know how to enhance treat the user message as the prompt.
+    async fn complete(&self, request: CompletionRequest) -> Result<EnhancedPrompt, LLMError> {
+        self.enhance(Prompt { text: request.user }, EnhancementOptions::default()).await
+    }
+
+    /// `complete` with the generated text reported through `on_token`, like `enhance_streaming`
+    async fn complete_streaming(&self, request: CompletionRequest, on_token: TokenSink<'_>) -> Result<EnhancedPrompt, LLMError> {
+        let completed = self.complete(request).await?;
+        on_token(&completed.text);
+        Ok(completed)
+    }
 }
 
 #[cfg(test)]
@@ -31,6 +119,7 @@ mod tests {
                 text: format!("Mock enhanced: {} - this is a longer text with enough words to pass validation", prompt.text),
                 rationale: Some("Mock enhancement".to_string()),
                 confidence: None,
+                ..Default::default()
             })
         }
     }
@@ -106,4 +195,51 @@ mod tests {
         assert!(matches!(error2, LLMError::RequestFailed(_)));
         assert!(matches!(error3, LLMError::RequestFailed(_)));
     }
+
+    #[test]
+    fn test_typed_error_display() {
+        let error = LLMError::Unauthorized(ProviderError::new(401, "No auth credentials found"));
+        assert_eq!(error.to_string(), "unauthorized: status 401: No auth credentials found");
+
+        let error = LLMError::RateLimited { retry_after: Some(Duration::from_secs(3)), error: ProviderError::new(429, "slow down") };
+        assert_eq!(error.to_string(), "rate limited: status 429: slow down");
+
+        let error = LLMError::Timeout("no response after 30s".to_string());
+        assert_eq!(error.to_string(), "timed out: no response after 30s");
+    }
+
+    #[test]
+    fn test_llm_error_retryability() {
+        assert!(LLMError::RequestFailed("status 503".into()).is_retryable());
+        assert!(LLMError::Timeout("30s".into()).is_retryable());
+        assert!(LLMError::RateLimited { retry_after: None, error: ProviderError::new(429, "slow down") }.is_retryable());
+        assert!(!LLMError::Unauthorized(ProviderError::new(401, "bad key")).is_retryable());
+        assert!(!LLMError::ContextLengthExceeded(ProviderError::new(400, "too long")).is_retryable());
+        assert!(!LLMError::ContentFiltered(ProviderError::new(403, "flagged")).is_retryable());
+        assert!(!LLMError::ModelNotFound(ProviderError::new(404, "no such model")).is_retryable());
+        assert!(!LLMError::UnexpectedResponse("no choices".into()).is_retryable());
+    }
+
+    #[test]
+    fn test_provider_error_details() {
+        let body = serde_json::json!({ "error": { "code": 403, "message": "flagged" } });
+        let error = LLMError::ContentFiltered(ProviderError::new(403, "flagged").with_body(Some(body.clone())));
+        let detail = error.provider_error().unwrap();
+        assert_eq!(detail.status, 403);
+        assert_eq!(detail.body.as_ref(), Some(&body));
+        assert!(LLMError::Timeout("30s".into()).provider_error().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_default_streaming_reports_whole_text() {
+        let tokens = std::sync::Mutex::new(Vec::new());
+        let on_token = |token: &str| tokens.lock().unwrap().push(token.to_string());
+
+        let result = MockProvider
+            .enhance_streaming(Prompt { text: "Test prompt".to_string() }, EnhancementOptions::default(), &on_token)
+            .await
+            .unwrap();
+
+        assert_eq!(*tokens.lock().unwrap(), vec![result.text]);
+    }
 }
@@ -0,0 +1,86 @@
+use crate::domain::models::{EnhancedPrompt, EnhancementOptions, Prompt};
+use crate::domain::prompting::detect_category;
+
+/// Rule-based enhancement used when no LLM is available: restates the task and
+/// appends the requested context plus generic clarity requirements
+pub fn enhance_locally(prompt: &Prompt, options: &EnhancementOptions) -> EnhancedPrompt {
+    let mut sections = vec![normalize_task(&prompt.text)];
+
+    let mut context = Vec::new();
+    if let Some(goal) = &options.goal { context.push(format!("Goal: {}", goal)); }
+    if let Some(audience) = &options.audience { context.push(format!("Audience: {}", audience)); }
+    if let Some(style) = &options.style { context.push(format!("Style: {}", style)); }
+    if let Some(tone) = &options.tone { context.push(format!("Tone: {}", tone)); }
+    if let Some(language) = &options.language { context.push(format!("Write the entire response in this language: {}", language)); }
+    if !context.is_empty() {
+        sections.push(context.join("\n"));
+    }
+
+    let mut requirements = vec![
+        "- Address every part of the request specifically and completely.",
+        "- State any assumptions you make before answering.",
+        "- Organize the response into clear sections or numbered steps.",
+    ];
+    match detect_category(&prompt.text) {
+        "code" => requirements.push("- Provide complete, runnable code with comments and cover relevant edge cases."),
+        "explanation" => requirements.push("- Explain step by step and include a concrete example."),
+        "definition" => requirements.push("- Start with a concise definition, then give a short example."),
+        _ => {}
+    }
+    sections.push(format!("Requirements:\n{}", requirements.join("\n")));
+
+    EnhancedPrompt {
+        text: sections.join("\n\n"),
+        rationale: Some("Rule-based local enhancement (LLM provider unavailable)".to_string()),
+        ..Default::default()
+    }
+}
+
+/// Trim, capitalize the first letter and make sure the task ends with punctuation
+fn normalize_task(text: &str) -> String {
+    let trimmed = text.trim();
+    let mut chars = trimmed.chars();
+    let mut task = match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+        None => return "Complete the following task.".to_string(),
+    };
+    if !task.ends_with(['.', '!', '?', ':']) {
+        task.push('.');
+    }
+    format!("Task: {}", task)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::validation::validate_enhanced_prompt;
+
+    #[test]
+    fn test_local_enhancement_passes_validation() {
+        let enhanced = enhance_locally(&Prompt { text: "fix bug".into() }, &EnhancementOptions::default());
+        assert!(validate_enhanced_prompt(&enhanced).is_ok());
+        assert!(enhanced.text.starts_with("Task: Fix bug."));
+        assert!(enhanced.rationale.is_some());
+    }
+
+    #[test]
+    fn test_local_enhancement_includes_options() {
+        let options = EnhancementOptions {
+            goal: Some("ship a hotfix".into()),
+            audience: Some("on-call engineers".into()),
+            language: Some("id".into()),
+            ..Default::default()
+        };
+        let enhanced = enhance_locally(&Prompt { text: "write code to parse logs".into() }, &options);
+        assert!(enhanced.text.contains("Goal: ship a hotfix"));
+        assert!(enhanced.text.contains("Audience: on-call engineers"));
+        assert!(enhanced.text.contains("language: id"));
+        assert!(enhanced.text.contains("runnable code"));
+    }
+
+    #[test]
+    fn test_normalize_task() {
+        assert_eq!(normalize_task("  explain rust?  "), "Task: Explain rust?");
+        assert_eq!(normalize_task(""), "Complete the following task.");
+    }
+}
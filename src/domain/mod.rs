@@ -3,3 +3,9 @@ pub mod llm;
 pub mod validation;
 pub mod fewshot;
 pub mod sequential_thinking;
+pub mod prompting;
+pub mod local_enhancer;
+pub mod templates;
+pub mod history;
+pub mod analysis;
+pub mod thinking_store;
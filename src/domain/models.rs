@@ -1,4 +1,6 @@
+use crate::domain::sequential_thinking::ThoughtData;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Prompt {
@@ -28,17 +30,94 @@ pub struct EnhancementOptions {
     /// enable sequential thinking for step-by-step reasoning
     #[serde(default)]
     pub enable_sequential_thinking: Option<bool>,
-    /// number of thoughts to generate (default: 3)
+    /// number of thoughts to generate (default: 3, at most 10)
     #[serde(default)]
     pub thought_count: Option<u32>,
+    /// alternative revisions to try at each sequential thinking step, keeping
+    /// the best one (default: 1, no branching)
+    #[serde(default)]
+    pub branch_count: Option<u32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct EnhancedPrompt {
     pub text: String,
     pub rationale: Option<String>,
     #[serde(default)]
     pub confidence: Option<f32>,
+    /// backend that produced the result, e.g. `openrouter:openai/gpt-4o-mini`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    /// set when the result was not produced by a successful LLM enhancement
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub degraded: Option<Degradation>,
+    /// tokens of every provider call that went into the result, when the provider reports them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenUsage>,
+    /// clarity, consistency and formatting findings that do not fail validation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub issues: Vec<String>,
+    /// the drafts sequential thinking went through, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub thoughts: Vec<ThoughtData>,
+    /// alternative revisions tried when branching, by branch id; the selected
+    /// one of each fork is also the next entry of `thoughts`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub branches: BTreeMap<String, Vec<ThoughtData>>,
+}
+
+/// Tokens a provider billed for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+impl TokenUsage {
+    pub fn total(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+
+    /// Usage of two calls together; unknown only if neither reported any
+    pub fn combine(a: Option<Self>, b: Option<Self>) -> Option<Self> {
+        match (a, b) {
+            (Some(a), Some(b)) => Some(Self { input_tokens: a.input_tokens + b.input_tokens, output_tokens: a.output_tokens + b.output_tokens }),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+/// What to do when the LLM provider cannot produce an enhancement
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DegradationPolicy {
+    /// surface the provider error to the caller
+    #[default]
+    Fail,
+    /// return the prompt unchanged
+    Original,
+    /// return a rule-based enhancement computed without an LLM
+    Local,
+}
+
+/// Why and how a result was degraded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Degradation {
+    pub policy: DegradationPolicy,
+    pub reason: String,
+}
+
+impl std::str::FromStr for DegradationPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "fail" => Ok(DegradationPolicy::Fail),
+            "original" => Ok(DegradationPolicy::Original),
+            "local" => Ok(DegradationPolicy::Local),
+            other => Err(format!("unknown degradation policy: {}", other)),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -88,6 +167,7 @@ mod tests {
             language: Some("en".to_string()),
             enable_sequential_thinking: Some(false),
             thought_count: Some(1),
+            branch_count: None,
         };
 
         assert_eq!(options.goal.as_deref(), Some("Create a clear instruction"));
@@ -109,10 +189,11 @@ mod tests {
             language: None,
             enable_sequential_thinking: Some(false),
             thought_count: Some(1),
+            branch_count: None,
         };
 
         let json = serde_json::to_string(&options).unwrap();
-        let expected = r#"{"goal":"Test goal","style":null,"tone":null,"level":2,"audience":null,"language":null,"enable_sequential_thinking":false,"thought_count":1}"#;
+        let expected = r#"{"goal":"Test goal","style":null,"tone":null,"level":2,"audience":null,"language":null,"enable_sequential_thinking":false,"thought_count":1,"branch_count":null}"#;
         assert_eq!(json, expected);
 
         let deserialized: EnhancementOptions = serde_json::from_str(&json).unwrap();
@@ -127,6 +208,7 @@ mod tests {
             text: "Enhanced prompt text".to_string(),
             rationale: Some("Made it clearer".to_string()),
             confidence: None,
+            ..Default::default()
         };
         assert_eq!(enhanced.text, "Enhanced prompt text");
         assert_eq!(enhanced.rationale.as_deref(), Some("Made it clearer"));
@@ -138,6 +220,7 @@ mod tests {
             text: "Enhanced prompt text".to_string(),
             rationale: None,
             confidence: None,
+            ..Default::default()
         };
         assert_eq!(enhanced.text, "Enhanced prompt text");
         assert!(enhanced.rationale.is_none());
@@ -149,6 +232,7 @@ mod tests {
             text: "Enhanced text".to_string(),
             rationale: Some("Test rationale".to_string()),
             confidence: None,
+            ..Default::default()
         };
         let json = serde_json::to_string(&enhanced).unwrap();
         assert_eq!(json, r#"{"text":"Enhanced text","rationale":"Test rationale","confidence":null}"#);
@@ -164,8 +248,39 @@ mod tests {
             text: "Enhanced text".to_string(),
             rationale: None,
             confidence: None,
+            ..Default::default()
         };
         let json = serde_json::to_string(&enhanced).unwrap();
         assert_eq!(json, r#"{"text":"Enhanced text","rationale":null,"confidence":null}"#);
     }
+
+    #[test]
+    fn test_degradation_policy_from_str() {
+        assert_eq!("fail".parse::<DegradationPolicy>().unwrap(), DegradationPolicy::Fail);
+        assert_eq!("Original".parse::<DegradationPolicy>().unwrap(), DegradationPolicy::Original);
+        assert_eq!(" local ".parse::<DegradationPolicy>().unwrap(), DegradationPolicy::Local);
+        assert!("sometimes".parse::<DegradationPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_degraded_prompt_serialization() {
+        let enhanced = EnhancedPrompt {
+            text: "Original text".to_string(),
+            degraded: Some(Degradation { policy: DegradationPolicy::Original, reason: "request failed: timeout".to_string() }),
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&enhanced).unwrap();
+        assert_eq!(json, r#"{"text":"Original text","rationale":null,"confidence":null,"degraded":{"policy":"original","reason":"request failed: timeout"}}"#);
+    }
+
+    #[test]
+    fn test_token_usage_combine() {
+        let a = TokenUsage { input_tokens: 10, output_tokens: 5 };
+        let b = TokenUsage { input_tokens: 3, output_tokens: 2 };
+
+        assert_eq!(TokenUsage::combine(Some(a), Some(b)), Some(TokenUsage { input_tokens: 13, output_tokens: 7 }));
+        assert_eq!(TokenUsage::combine(None, Some(b)), Some(b));
+        assert_eq!(TokenUsage::combine(None, None), None);
+        assert_eq!(a.total(), 15);
+    }
 }
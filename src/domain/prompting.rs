@@ -0,0 +1,206 @@
+use crate::domain::fewshot;
+use crate::domain::models::{EnhancementOptions, Prompt};
+
+pub const ENHANCEMENT_SYSTEM_PROMPT: &str = "You are an expert prompt engineering assistant. Your ONLY task is to refine and enhance user prompts for Large Language Models. You must return ONLY the enhanced prompt text - no introductions, no explanations, no additional commentary of any kind. Simply output the improved prompt directly.
+
+CRITICAL: Your response must contain ONLY the enhanced prompt. No prefixes like 'Enhanced prompt:' or 'Here is the enhanced version:'. No meta-commentary. No acknowledgments. Just the enhanced prompt text itself.
+
+Guidelines for enhancement:
+- Maximize clarity and specificity
+- Specify clear goals and constraints
+- Resolve ambiguities while staying faithful to original intent
+- Structure the prompt for optimal LLM performance
+- If a specific language is requested, write the entire enhanced prompt in that language
+
+Remember: Output ONLY the enhanced prompt. Nothing else.";
+
+/// The whole reply of a critique that found nothing left to fix
+pub const NO_ISSUES: &str = "NO ISSUES";
+
+pub const CRITIQUE_SYSTEM_PROMPT: &str = "You are an expert prompt engineering reviewer. You review a draft prompt for a Large Language Model against the user's original request and requirements. Do NOT rewrite the prompt.
+
+List the problems that remain in the draft as short bullet points, most important first. Look for:
+- Goals, constraints, context or output format that are missing or unclear
+- Ambiguities the model would have to guess about
+- Anything that does not suit the requested goal, audience, style, tone or language
+- Anything unfaithful to the original request
+
+If the draft has no remaining problems, reply with exactly: NO ISSUES";
+
+pub const REVISION_SYSTEM_PROMPT: &str = "You are an expert prompt engineering assistant. You revise a draft prompt for a Large Language Model so that it resolves every point of a review, while staying faithful to the user's original request. Change only what the review asks for.
+
+CRITICAL: Your response must contain ONLY the revised prompt. No prefixes like 'Revised prompt:'. No explanations of the changes. No meta-commentary.";
+
+/// Provider-agnostic description of a single-turn completion: every backend
+/// maps this onto its own wire format so they all receive equivalent requests
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompletionRequest {
+    pub system: String,
+    pub user: String,
+    pub temperature: f32,
+}
+
+/// Build the completion request used to enhance `prompt`
+pub fn enhancement_request(prompt: &Prompt, options: &EnhancementOptions) -> CompletionRequest {
+    CompletionRequest {
+        system: ENHANCEMENT_SYSTEM_PROMPT.to_string(),
+        user: build_enhancement_message(prompt, options),
+        temperature: 0.2,
+    }
+}
+
+/// Build the user message for an enhancement request: the enhancement
+/// instructions derived from `options`, the original prompt, and few-shot
+/// examples for the detected category.
+pub fn build_enhancement_message(prompt: &Prompt, options: &EnhancementOptions) -> String {
+    let instruction = option_instructions(options);
+
+    let mut user = if instruction.is_empty() {
+        prompt.text.clone()
+    } else {
+        format!("{}\n\n---\nOriginal prompt:\n{}", instruction, prompt.text)
+    };
+
+    let examples = fewshot::select_examples(detect_category(&prompt.text), 2);
+    if !examples.is_empty() {
+        let examples_text = examples.iter().map(|ex| format!("Example Input: {}\nExample Output: {}", ex.input, ex.output)).collect::<Vec<_>>().join("\n\n");
+        user = format!("Here are some examples to guide your response:\n\n{}\n\n{}", examples_text, user);
+    }
+
+    user
+}
+
+/// Build the completion request that reviews `draft` against the original
+/// `prompt` and `options`; the answer is a list of problems or `NO_ISSUES`
+pub fn critique_request(prompt: &Prompt, options: &EnhancementOptions, draft: &str) -> CompletionRequest {
+    CompletionRequest {
+        system: CRITIQUE_SYSTEM_PROMPT.to_string(),
+        user: format!("{}Original request:\n{}\n\n---\nDraft prompt:\n{}", option_instructions(options), prompt.text, draft),
+        temperature: 0.0,
+    }
+}
+
+/// Build the completion request that rewrites `draft` to resolve `critique`
+pub fn revision_request(prompt: &Prompt, options: &EnhancementOptions, draft: &str, critique: &str) -> CompletionRequest {
+    CompletionRequest {
+        system: REVISION_SYSTEM_PROMPT.to_string(),
+        user: format!(
+            "{}Original request:\n{}\n\n---\nDraft prompt:\n{}\n\n---\nReview to address:\n{}",
+            option_instructions(options),
+            prompt.text,
+            draft,
+            critique
+        ),
+        temperature: 0.2,
+    }
+}
+
+/// Temperatures of the alternative revisions tried when sequential thinking
+/// branches; the first is the one `revision_request` uses
+pub const BRANCH_TEMPERATURES: [f32; 4] = [0.2, 0.5, 0.8, 1.0];
+
+/// Whether a critique reply says the draft needs no further revision; tolerates
+/// the markup and punctuation models like to add around it
+pub fn critique_is_clean(critique: &str) -> bool {
+    critique.trim_matches(|c: char| !c.is_alphanumeric()).eq_ignore_ascii_case(NO_ISSUES)
+}
+
+/// One `Name: value` line per option that was set
+fn option_instructions(options: &EnhancementOptions) -> String {
+    let mut instruction = String::new();
+    if let Some(goal) = &options.goal { instruction.push_str(&format!("Goal: {}\n", goal)); }
+    if let Some(style) = &options.style { instruction.push_str(&format!("Style: {}\n", style)); }
+    if let Some(tone) = &options.tone { instruction.push_str(&format!("Tone: {}\n", tone)); }
+    if let Some(level) = options.level { instruction.push_str(&format!("Enhancement level: {} (1-5)\n", level)); }
+    if let Some(audience) = &options.audience { instruction.push_str(&format!("Audience: {}\n", audience)); }
+    if let Some(language) = &options.language { instruction.push_str(&format!("Language: {}\n", language)); }
+    instruction
+}
+
+/// Pick the few-shot category that best matches the prompt text
+pub fn detect_category(text: &str) -> &'static str {
+    let lower = text.to_lowercase();
+    if lower.contains("code") || lower.contains("function") || lower.contains("program") {
+        "code"
+    } else if lower.contains("explain") || lower.contains("what is") {
+        "explanation"
+    } else if lower.contains("define") || lower.contains("definition") {
+        "definition"
+    } else {
+        "general"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_category() {
+        assert_eq!(detect_category("Write a function that sorts"), "code");
+        assert_eq!(detect_category("Explain ownership"), "explanation");
+        assert_eq!(detect_category("Give me a definition of entropy"), "definition");
+        assert_eq!(detect_category("Plan a trip to Bali"), "general");
+    }
+
+    #[test]
+    fn test_enhancement_request() {
+        let prompt = Prompt { text: "Plan a trip to Bali".to_string() };
+        let request = enhancement_request(&prompt, &EnhancementOptions::default());
+        assert_eq!(request.system, ENHANCEMENT_SYSTEM_PROMPT);
+        assert_eq!(request.user, "Plan a trip to Bali");
+        assert_eq!(request.temperature, 0.2);
+    }
+
+    #[test]
+    fn test_build_message_without_options() {
+        let prompt = Prompt { text: "Plan a trip to Bali".to_string() };
+        let message = build_enhancement_message(&prompt, &EnhancementOptions::default());
+        assert_eq!(message, "Plan a trip to Bali");
+    }
+
+    #[test]
+    fn test_build_message_with_options_and_examples() {
+        let prompt = Prompt { text: "write code for fibonacci".to_string() };
+        let options = EnhancementOptions {
+            goal: Some("teach recursion".to_string()),
+            language: Some("en".to_string()),
+            ..Default::default()
+        };
+        let message = build_enhancement_message(&prompt, &options);
+        assert!(message.starts_with("Here are some examples to guide your response:"));
+        assert!(message.contains("Goal: teach recursion\n"));
+        assert!(message.contains("Language: en\n"));
+        assert!(message.ends_with("---\nOriginal prompt:\nwrite code for fibonacci"));
+    }
+
+    #[test]
+    fn test_critique_request_carries_goal_and_draft() {
+        let prompt = Prompt { text: "plan a trip".to_string() };
+        let options = EnhancementOptions { audience: Some("retirees".to_string()), ..Default::default() };
+
+        let request = critique_request(&prompt, &options, "Plan a two-week trip to Bali.");
+
+        assert_eq!(request.system, CRITIQUE_SYSTEM_PROMPT);
+        assert_eq!(request.user, "Audience: retirees\nOriginal request:\nplan a trip\n\n---\nDraft prompt:\nPlan a two-week trip to Bali.");
+    }
+
+    #[test]
+    fn test_revision_request_includes_critique() {
+        let prompt = Prompt { text: "plan a trip".to_string() };
+
+        let request = revision_request(&prompt, &EnhancementOptions::default(), "Plan a trip.", "- No destination");
+
+        assert_eq!(request.system, REVISION_SYSTEM_PROMPT);
+        assert!(request.user.starts_with("Original request:\nplan a trip\n"));
+        assert!(request.user.ends_with("Draft prompt:\nPlan a trip.\n\n---\nReview to address:\n- No destination"));
+    }
+
+    #[test]
+    fn test_critique_is_clean() {
+        assert!(critique_is_clean("NO ISSUES"));
+        assert!(critique_is_clean(" **No issues.**\n"));
+        assert!(!critique_is_clean("- No issues with tone, but the budget is missing"));
+        assert!(!critique_is_clean("- The output format is missing"));
+    }
+}
@@ -1,18 +1,37 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use anyhow::{Result, Error};
+use itertools::Itertools;
+use tracing::debug;
 
+/// One step of sequential thinking, serialized with the camelCase field names
+/// of the reference sequential-thinking server; unset fields are left out
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ThoughtData {
     pub thought: String,
     pub thought_number: u32,
     pub total_thoughts: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub is_revision: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub revises_thought: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub branch_from_thought: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub branch_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub needs_more_thoughts: Option<bool>,
     pub next_thought_needed: bool,
+    /// review of this thought's draft that the next thought revises against
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub critique: Option<String>,
+    /// how the draft of a branch scored against the other branches of its fork
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<f32>,
+    /// whether this branch won its fork and was carried on as the next thought
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selected: Option<bool>,
 }
 
 impl ThoughtData {
@@ -32,6 +51,9 @@ impl ThoughtData {
             branch_id: None,
             needs_more_thoughts: None,
             next_thought_needed,
+            critique: None,
+            score: None,
+            selected: None,
         }
     }
 
@@ -46,8 +68,50 @@ impl ThoughtData {
         self.branch_id = Some(branch_id);
         self
     }
+
+    pub fn with_critique(mut self, critique: String) -> Self {
+        self.critique = Some(critique);
+        self
+    }
+
+    pub fn with_score(mut self, score: f32, selected: bool) -> Self {
+        self.score = Some(score);
+        self.selected = Some(selected);
+        self
+    }
+
+    /// The first line of `format_thought`, e.g. `🔄 Revision 2/3 (revising thought 1)`
+    pub fn header(&self) -> String {
+        let prefix = if self.is_revision.unwrap_or(false) {
+            "🔄 Revision"
+        } else if self.branch_from_thought.is_some() {
+            "🌿 Branch"
+        } else {
+            "💭 Thought"
+        };
+
+        let context = if let Some(revises) = self.revises_thought {
+            format!(" (revising thought {})", revises)
+        } else if let Some(branch_from) = self.branch_from_thought {
+            format!(" (from thought {}, ID: {})", branch_from, self.branch_id.as_deref().unwrap_or("none"))
+        } else {
+            String::new()
+        };
+
+        let score = match (self.score, self.selected) {
+            (Some(score), Some(true)) => format!(" [score {:.2}, selected]", score),
+            (Some(score), _) => format!(" [score {:.2}]", score),
+            (None, _) => String::new(),
+        };
+
+        format!("{} {}/{}{}{}", prefix, self.thought_number, self.total_thoughts, context, score)
+    }
 }
 
+/// The thoughts and branches of one line of thinking, serialized as
+/// `thoughtHistory` and `branches` so it can be saved and resumed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct SequentialThinking {
     thought_history: Vec<ThoughtData>,
     branches: HashMap<String, Vec<ThoughtData>>,
@@ -90,40 +154,54 @@ impl SequentialThinking {
             .and_then(|v| v.as_str())
             .map(|s| s.to_string());
 
+        let revises_thought = data.get("revisesThought")
+            .and_then(|v| v.as_u64())
+            .map(|v| v as u32);
+
+        let is_revision = data.get("isRevision")
+            .and_then(|v| v.as_bool());
+
+        let needs_more_thoughts = data.get("needsMoreThoughts")
+            .and_then(|v| v.as_bool());
+
+        let critique = data.get("critique")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+
         let mut thought_data = ThoughtData::new(thought, thought_number, total_thoughts, next_thought_needed);
 
         if let (Some(branch_from), Some(branch_id)) = (branch_from_thought, branch_id) {
             thought_data = thought_data.with_branch(branch_from, branch_id);
         }
 
+        // Like the reference server, a revision need not say which thought it revises
+        thought_data.is_revision = is_revision;
+        thought_data.revises_thought = revises_thought;
+        thought_data.needs_more_thoughts = needs_more_thoughts;
+
+        if let Some(critique) = critique {
+            thought_data = thought_data.with_critique(critique);
+        }
+
         Ok(thought_data)
     }
 
     pub fn format_thought(&self, thought_data: &ThoughtData) -> String {
-        let prefix = if thought_data.is_revision.unwrap_or(false) {
-            "🔄 Revision"
-        } else if thought_data.branch_from_thought.is_some() {
-            "🌿 Branch"
-        } else {
-            "💭 Thought"
-        };
+        let header = thought_data.header();
 
-        let context = if let Some(revises) = thought_data.revises_thought {
-            format!(" (revising thought {})", revises)
-        } else if let Some(branch_from) = thought_data.branch_from_thought {
-            format!(" (from thought {}, ID: {:?})", branch_from, thought_data.branch_id)
-        } else {
-            String::new()
-        };
-
-        let header = format!("{} {}/{}{}", prefix, thought_data.thought_number, thought_data.total_thoughts, context);
-
-        format!("{}\n{}", header, thought_data.thought)
+        match &thought_data.critique {
+            Some(critique) => format!("{}\n{}\n📝 Critique\n{}", header, thought_data.thought, critique),
+            None => format!("{}\n{}", header, thought_data.thought),
+        }
     }
 
     pub fn process_thought(&mut self, input: serde_json::Value) -> Result<serde_json::Value> {
-        let mut thought_data = self.validate_thought_data(&input)?;
+        let thought_data = self.validate_thought_data(&input)?;
+        Ok(self.record_thought(thought_data))
+    }
 
+    /// Add an already validated thought to the history, and to its branch if it has one
+    pub fn record_thought(&mut self, mut thought_data: ThoughtData) -> serde_json::Value {
         if thought_data.thought_number > thought_data.total_thoughts {
             thought_data.total_thoughts = thought_data.thought_number;
         }
@@ -131,21 +209,18 @@ impl SequentialThinking {
         self.thought_history.push(thought_data.clone());
 
         if let (Some(_branch_from), Some(branch_id)) = (thought_data.branch_from_thought, &thought_data.branch_id) {
-            self.branches.entry(branch_id.clone()).or_insert_with(Vec::new).push(thought_data.clone());
+            self.branches.entry(branch_id.clone()).or_default().push(thought_data.clone());
         }
 
-        let formatted_thought = self.format_thought(&thought_data);
-        eprintln!("{}", formatted_thought);
+        debug!(thought = %self.format_thought(&thought_data), "processed thought");
 
-        let response = serde_json::json!({
+        serde_json::json!({
             "thoughtNumber": thought_data.thought_number,
             "totalThoughts": thought_data.total_thoughts,
             "nextThoughtNeeded": thought_data.next_thought_needed,
-            "branches": self.branches.keys().collect::<Vec<_>>(),
+            "branches": self.branches.keys().sorted().collect::<Vec<_>>(),
             "thoughtHistoryLength": self.thought_history.len()
-        });
-
-        Ok(response)
+        })
     }
 
     pub fn get_thought_history(&self) -> &[ThoughtData] {
@@ -157,6 +232,26 @@ impl SequentialThinking {
     }
 }
 
+/// Markdown rendering of a thought trace: one section per thought, headed like
+/// `format_thought`, with the branches forked from it right after it
+pub fn render_markdown(thoughts: &[ThoughtData], branches: &BTreeMap<String, Vec<ThoughtData>>) -> String {
+    let mut sections = Vec::new();
+    for thought in thoughts {
+        sections.push(markdown_section("###", thought));
+        let forked = branches.values().flatten().filter(|branch| branch.branch_from_thought == Some(thought.thought_number));
+        sections.extend(forked.map(|branch| markdown_section("####", branch)));
+    }
+    sections.join("\n\n")
+}
+
+fn markdown_section(heading: &str, thought_data: &ThoughtData) -> String {
+    let mut section = format!("{} {}\n\n{}", heading, thought_data.header(), thought_data.thought);
+    if let Some(critique) = &thought_data.critique {
+        section.push_str(&format!("\n\n**📝 Critique**\n\n{}", critique));
+    }
+    section
+}
+
 impl Default for SequentialThinking {
     fn default() -> Self {
         Self::new()
@@ -257,6 +352,114 @@ mod tests {
         assert!(formatted.contains("(revising thought 1)"));
     }
 
+    #[test]
+    fn test_validate_thought_data_with_revision_and_critique() {
+        let st = SequentialThinking::new();
+        let input = json!({
+            "thought": "Second draft",
+            "thoughtNumber": 2,
+            "totalThoughts": 3,
+            "nextThoughtNeeded": true,
+            "isRevision": true,
+            "revisesThought": 1,
+            "critique": "- No output format"
+        });
+
+        let thought = st.validate_thought_data(&input).unwrap();
+        assert_eq!(thought.is_revision, Some(true));
+        assert_eq!(thought.revises_thought, Some(1));
+        assert_eq!(thought.critique.as_deref(), Some("- No output format"));
+    }
+
+    #[test]
+    fn test_validate_thought_data_needs_more_thoughts() {
+        let st = SequentialThinking::new();
+        let input = json!({
+            "thought": "Not done yet",
+            "thoughtNumber": 3,
+            "totalThoughts": 3,
+            "nextThoughtNeeded": true,
+            "needsMoreThoughts": true,
+            "isRevision": false
+        });
+
+        let thought = st.validate_thought_data(&input).unwrap();
+        assert_eq!(thought.needs_more_thoughts, Some(true));
+        assert_eq!(thought.is_revision, Some(false));
+        assert!(thought.revises_thought.is_none());
+    }
+
+    #[test]
+    fn test_format_thought_with_critique() {
+        let st = SequentialThinking::new();
+        let thought = ThoughtData::new("Draft".to_string(), 1, 3, true)
+            .with_critique("- Too vague".to_string());
+
+        let formatted = st.format_thought(&thought);
+        assert!(formatted.ends_with("Draft\n📝 Critique\n- Too vague"));
+    }
+
+    #[test]
+    fn test_format_scored_branch() {
+        let st = SequentialThinking::new();
+        let thought = ThoughtData::new("Alternative".to_string(), 2, 3, true)
+            .with_branch(1, "1b".to_string())
+            .with_score(0.714, true);
+
+        let formatted = st.format_thought(&thought);
+        assert!(formatted.starts_with("🌿 Branch 2/3 (from thought 1, ID: 1b) [score 0.71, selected]\n"));
+    }
+
+    #[test]
+    fn test_record_thought_returns_status() {
+        let mut st = SequentialThinking::new();
+        st.record_thought(ThoughtData::new("Draft".to_string(), 1, 2, true));
+
+        let status = st.record_thought(ThoughtData::new("Alternative".to_string(), 2, 2, false).with_branch(1, "1a".to_string()));
+        assert_eq!(status["thoughtHistoryLength"], 2);
+        assert_eq!(status["branches"], json!(["1a"]));
+        assert_eq!(st.get_branches()["1a"][0].thought, "Alternative");
+    }
+
+    #[test]
+    fn test_render_markdown_places_branches_after_their_fork() {
+        let thoughts = vec![
+            ThoughtData::new("First".to_string(), 1, 2, true).with_critique("- Vague".to_string()),
+            ThoughtData::new("Second".to_string(), 2, 2, false).with_revision(1),
+        ];
+        let mut branches = BTreeMap::new();
+        for (id, selected) in [("1a", false), ("1b", true)] {
+            let branch = ThoughtData::new(id.to_string(), 2, 2, false).with_branch(1, id.to_string()).with_score(0.5, selected);
+            branches.insert(id.to_string(), vec![branch]);
+        }
+
+        let markdown = render_markdown(&thoughts, &branches);
+
+        let headings: Vec<_> = markdown.lines().filter(|line| line.starts_with('#')).collect();
+        assert_eq!(headings, vec![
+            "### 💭 Thought 1/2",
+            "#### 🌿 Branch 2/2 (from thought 1, ID: 1a) [score 0.50]",
+            "#### 🌿 Branch 2/2 (from thought 1, ID: 1b) [score 0.50, selected]",
+            "### 🔄 Revision 2/2 (revising thought 1)",
+        ]);
+        assert!(markdown.starts_with("### 💭 Thought 1/2\n\nFirst\n\n**📝 Critique**\n\n- Vague\n\n####"));
+    }
+
+    #[test]
+    fn test_thought_data_serializes_like_reference_server() {
+        let thought = ThoughtData::new("Draft".to_string(), 2, 3, true).with_revision(1);
+
+        let value = serde_json::to_value(&thought).unwrap();
+        assert_eq!(value, json!({
+            "thought": "Draft",
+            "thoughtNumber": 2,
+            "totalThoughts": 3,
+            "isRevision": true,
+            "revisesThought": 1,
+            "nextThoughtNeeded": true
+        }));
+    }
+
     #[test]
     fn test_process_thought() {
         let mut st = SequentialThinking::new();
@@ -306,4 +509,19 @@ mod tests {
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("Invalid thoughtNumber"));
     }
+
+    #[test]
+    fn test_serialization_round_trip() {
+        let mut st = SequentialThinking::new();
+        st.record_thought(ThoughtData::new("first".to_string(), 1, 2, true));
+        st.record_thought(ThoughtData::new("alt".to_string(), 2, 2, false).with_branch(1, "b".to_string()));
+
+        let value = serde_json::to_value(&st).unwrap();
+        assert_eq!(value["thoughtHistory"][0]["thought"], "first");
+        assert_eq!(value["branches"]["b"][0]["branchFromThought"], 1);
+
+        let restored: SequentialThinking = serde_json::from_value(value).unwrap();
+        assert_eq!(restored.thought_history.len(), 2);
+        assert_eq!(restored.branches["b"][0].thought, "alt");
+    }
 }
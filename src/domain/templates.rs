@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use thiserror::Error;
+
+/// A reusable prompt with named `{{placeholders}}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub arguments: Vec<TemplateArgument>,
+    pub template: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplateArgument {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TemplateError {
+    #[error("unknown prompt: {0}")]
+    UnknownTemplate(String),
+    #[error("missing required argument: {0}")]
+    MissingArgument(String),
+}
+
+impl PromptTemplate {
+    /// A template whose arguments are the placeholders found in `template`, all required
+    pub fn from_text(name: impl Into<String>, template: impl Into<String>) -> Self {
+        let template = template.into();
+        let mut arguments: Vec<TemplateArgument> = Vec::new();
+        for placeholder in placeholders(&template) {
+            if !arguments.iter().any(|a| a.name == placeholder) {
+                arguments.push(TemplateArgument { name: placeholder.to_string(), description: None, required: true });
+            }
+        }
+        Self { name: name.into(), description: None, arguments, template }
+    }
+
+    /// Substitute every `{{name}}`; optional arguments that were not given become empty
+    pub fn render(&self, values: &HashMap<String, String>) -> Result<String, TemplateError> {
+        if let Some(missing) = self.arguments.iter().find(|a| a.required && values.get(&a.name).filter(|v| !v.trim().is_empty()).is_none()) {
+            return Err(TemplateError::MissingArgument(missing.name.clone()));
+        }
+
+        let mut rendered = String::with_capacity(self.template.len());
+        let mut rest = self.template.as_str();
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start + 2..].find("}}") else { break };
+            let name = rest[start + 2..start + 2 + len].trim();
+            rendered.push_str(&rest[..start]);
+            rendered.push_str(values.get(name).map(String::as_str).unwrap_or(""));
+            rest = &rest[start + 2 + len + 2..];
+        }
+        rendered.push_str(rest);
+        Ok(rendered.trim().to_string())
+    }
+}
+
+fn placeholders(template: &str) -> impl Iterator<Item = &str> {
+    template.split("{{").skip(1).filter_map(|part| part.split_once("}}")).map(|(name, _)| name.trim()).filter(|name| !name.is_empty())
+}
+
+/// Templates by name; later additions replace earlier ones with the same name
+#[derive(Debug, Clone, Default)]
+pub struct PromptCatalog {
+    templates: BTreeMap<String, PromptTemplate>,
+}
+
+impl PromptCatalog {
+    /// The templates that ship with anytra
+    pub fn builtin() -> Self {
+        let mut catalog = Self::default();
+        catalog.extend(builtin_templates());
+        catalog
+    }
+
+    pub fn extend(&mut self, templates: impl IntoIterator<Item = PromptTemplate>) {
+        for template in templates {
+            self.templates.insert(template.name.clone(), template);
+        }
+    }
+
+    pub fn list(&self) -> impl Iterator<Item = &PromptTemplate> {
+        self.templates.values()
+    }
+
+    pub fn render(&self, name: &str, values: &HashMap<String, String>) -> Result<(&PromptTemplate, String), TemplateError> {
+        let template = self.templates.get(name).ok_or_else(|| TemplateError::UnknownTemplate(name.to_string()))?;
+        Ok((template, template.render(values)?))
+    }
+}
+
+fn argument(name: &str, description: &str, required: bool) -> TemplateArgument {
+    TemplateArgument { name: name.to_string(), description: Some(description.to_string()), required }
+}
+
+fn builtin_templates() -> Vec<PromptTemplate> {
+    vec![
+        PromptTemplate {
+            name: "code-review-request".to_string(),
+            description: Some("Ask for a focused review of a code change".to_string()),
+            arguments: vec![
+                argument("code", "The code or diff to review", true),
+                argument("language", "Programming language of the code", false),
+                argument("focus", "What to pay most attention to, e.g. security or performance", false),
+            ],
+            template: "Review the code below. Point out bugs, risky patterns and unclear naming, ordered by severity, \
+                       and suggest a concrete fix for each finding.\n\nLanguage: {{language}}\nFocus: {{focus}}\n\n\
+                       ```\n{{code}}\n```"
+                .to_string(),
+        },
+        PromptTemplate {
+            name: "bug-report".to_string(),
+            description: Some("Turn a rough problem description into a reproducible bug report".to_string()),
+            arguments: vec![
+                argument("summary", "What went wrong", true),
+                argument("steps", "How to reproduce it", false),
+                argument("expected", "What should have happened", false),
+                argument("environment", "Versions, OS, configuration", false),
+            ],
+            template: "Write a bug report with the sections Summary, Steps to Reproduce, Expected Behavior, Actual Behavior \
+                       and Environment. Keep each section factual and mark anything unknown as such.\n\n\
+                       Summary: {{summary}}\nSteps: {{steps}}\nExpected: {{expected}}\nEnvironment: {{environment}}"
+                .to_string(),
+        },
+        PromptTemplate {
+            name: "data-analysis".to_string(),
+            description: Some("Request an analysis of a dataset against a question".to_string()),
+            arguments: vec![
+                argument("question", "What the analysis should answer", true),
+                argument("data", "The data, or a description of its columns", true),
+                argument("audience", "Who will read the results", false),
+            ],
+            template: "Answer the question below using the data provided. State your assumptions, show the calculations \
+                       or queries you used, and summarise the findings in plain language for the audience. Flag any data \
+                       quality issues that limit the conclusions.\n\nQuestion: {{question}}\nAudience: {{audience}}\n\nData:\n{{data}}"
+                .to_string(),
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_render_substitutes_arguments() {
+        let template = PromptTemplate::from_text("greet", "Hello {{ name }}, welcome to {{place}}. Bye {{name}}.");
+
+        let rendered = template.render(&values(&[("name", "Ana"), ("place", "Jakarta")])).unwrap();
+
+        assert_eq!(rendered, "Hello Ana, welcome to Jakarta. Bye Ana.");
+    }
+
+    #[test]
+    fn test_from_text_infers_required_arguments() {
+        let template = PromptTemplate::from_text("greet", "{{a}} {{b}} {{a}} {{}}");
+
+        let names: Vec<_> = template.arguments.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["a", "b"]);
+        assert!(template.arguments.iter().all(|a| a.required));
+    }
+
+    #[test]
+    fn test_render_requires_required_arguments() {
+        let catalog = PromptCatalog::builtin();
+
+        assert_eq!(catalog.render("bug-report", &values(&[])).unwrap_err(), TemplateError::MissingArgument("summary".into()));
+        assert_eq!(catalog.render("bug-report", &values(&[("summary", "  ")])).unwrap_err(), TemplateError::MissingArgument("summary".into()));
+    }
+
+    #[test]
+    fn test_optional_arguments_render_empty() {
+        let catalog = PromptCatalog::builtin();
+
+        let (_, rendered) = catalog.render("bug-report", &values(&[("summary", "Crash on start")])).unwrap();
+
+        assert!(rendered.contains("Summary: Crash on start\nSteps: \n"));
+        assert!(!rendered.contains("{{"));
+    }
+
+    #[test]
+    fn test_unknown_template() {
+        let catalog = PromptCatalog::builtin();
+
+        assert_eq!(catalog.render("nope", &values(&[])).unwrap_err(), TemplateError::UnknownTemplate("nope".into()));
+    }
+
+    #[test]
+    fn test_extend_replaces_by_name() {
+        let mut catalog = PromptCatalog::builtin();
+
+        catalog.extend([PromptTemplate::from_text("bug-report", "Bug: {{what}}")]);
+
+        assert_eq!(catalog.list().count(), 3);
+        let (_, rendered) = catalog.render("bug-report", &values(&[("what", "x")])).unwrap();
+        assert_eq!(rendered, "Bug: x");
+    }
+
+    #[test]
+    fn test_unclosed_placeholder_is_kept() {
+        let template = PromptTemplate::from_text("t", "a {{b");
+
+        assert_eq!(template.render(&values(&[])).unwrap(), "a {{b");
+    }
+}
@@ -0,0 +1,151 @@
+use crate::domain::sequential_thinking::SequentialThinking;
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Longest session id or name accepted
+pub const MAX_SESSION_ID_LEN: usize = 64;
+
+/// The sequential thinking of one session, as a store keeps it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThinkingSession {
+    /// what the store knows it by, derived from `owner` and `name`
+    pub id: String,
+    /// the client it belongs to; `None` when the server has no authentication
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// the name its client chose
+    pub name: String,
+    /// seconds since the Unix epoch of the last recorded thought
+    pub updated_at: u64,
+    #[serde(flatten)]
+    pub thinking: SequentialThinking,
+}
+
+impl ThinkingSession {
+    pub fn new(owner: Option<&str>, name: &str) -> Self {
+        Self { id: session_key(owner, name), owner: owner.map(str::to_string), name: name.to_string(), updated_at: now(), thinking: SequentialThinking::new() }
+    }
+
+    /// Mark the session as changed just now
+    pub fn touch(&mut self) {
+        self.updated_at = now();
+    }
+}
+
+/// Seconds since the Unix epoch
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Store id of the session `name` of `owner`: a hex SHA-256, so any client name
+/// makes a valid id and sessions of different clients never collide
+pub fn session_key(owner: Option<&str>, name: &str) -> String {
+    let key = serde_json::to_string(&(owner, name)).expect("strings serialize");
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+/// Session ids name files, so only ASCII letters, digits, `-` and `_` are allowed
+pub fn check_session_id(id: &str) -> anyhow::Result<()> {
+    if id.is_empty() || id.len() > MAX_SESSION_ID_LEN {
+        bail!("session id must be 1 to {} characters", MAX_SESSION_ID_LEN);
+    }
+    if !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        bail!("session id may only contain letters, digits, '-' and '_': {}", id);
+    }
+    Ok(())
+}
+
+/// Where thinking sessions are kept between tool calls
+pub trait ThinkingStore: Send + Sync {
+    fn load(&self, id: &str) -> anyhow::Result<Option<ThinkingSession>>;
+
+    /// Create or replace the session with the same id
+    fn save(&self, session: &ThinkingSession) -> anyhow::Result<()>;
+
+    /// Delete a session, returning what it held
+    fn remove(&self, id: &str) -> anyhow::Result<Option<ThinkingSession>>;
+
+    /// Every stored session, in no particular order
+    fn list(&self) -> anyhow::Result<Vec<ThinkingSession>>;
+}
+
+/// Sessions kept for the life of the process only
+#[derive(Debug, Default)]
+pub struct MemoryThinkingStore {
+    sessions: Mutex<HashMap<String, ThinkingSession>>,
+}
+
+impl ThinkingStore for MemoryThinkingStore {
+    fn load(&self, id: &str) -> anyhow::Result<Option<ThinkingSession>> {
+        Ok(self.sessions.lock().unwrap().get(id).cloned())
+    }
+
+    fn save(&self, session: &ThinkingSession) -> anyhow::Result<()> {
+        self.sessions.lock().unwrap().insert(session.id.clone(), session.clone());
+        Ok(())
+    }
+
+    fn remove(&self, id: &str) -> anyhow::Result<Option<ThinkingSession>> {
+        Ok(self.sessions.lock().unwrap().remove(id))
+    }
+
+    fn list(&self) -> anyhow::Result<Vec<ThinkingSession>> {
+        Ok(self.sessions.lock().unwrap().values().cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::sequential_thinking::ThoughtData;
+
+    #[test]
+    fn test_session_ids() {
+        assert!(check_session_id("abc-123_XYZ").is_ok());
+        assert!(check_session_id(&"a".repeat(MAX_SESSION_ID_LEN)).is_ok());
+        assert!(check_session_id("").is_err());
+        assert!(check_session_id(&"a".repeat(MAX_SESSION_ID_LEN + 1)).is_err());
+        assert!(check_session_id("../etc").is_err());
+        assert!(check_session_id("a b").is_err());
+    }
+
+    #[test]
+    fn test_memory_store_saves_loads_and_removes() {
+        let store = MemoryThinkingStore::default();
+        let mut session = ThinkingSession::new(None, "a");
+        session.thinking.record_thought(ThoughtData::new("step".to_string(), 1, 1, false));
+
+        store.save(&session).unwrap();
+
+        assert_eq!(store.load(&session.id).unwrap().unwrap().thinking.get_thought_history().len(), 1);
+        assert_eq!(store.list().unwrap().len(), 1);
+        assert!(store.remove(&session.id).unwrap().is_some());
+        assert!(store.load(&session.id).unwrap().is_none());
+        assert!(store.remove(&session.id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_session_serializes_flat() {
+        let session = ThinkingSession { id: "a".to_string(), updated_at: 42, ..ThinkingSession::new(Some("alice"), "plan") };
+
+        let value = serde_json::to_value(&session).unwrap();
+
+        assert_eq!(value, serde_json::json!({ "id": "a", "owner": "alice", "name": "plan", "updatedAt": 42, "thoughtHistory": [], "branches": {} }));
+    }
+
+    #[test]
+    fn test_session_keys_keep_clients_apart() {
+        let key = session_key(Some("alice"), "plan");
+
+        assert!(check_session_id(&key).is_ok());
+        assert_eq!(key, ThinkingSession::new(Some("alice"), "plan").id);
+        assert_ne!(key, session_key(Some("bob"), "plan"));
+        assert_ne!(key, session_key(None, "plan"));
+        assert_ne!(session_key(Some("a"), "b-c"), session_key(Some("a-b"), "c"));
+    }
+}
@@ -1,6 +1,7 @@
 use anyhow::{self, Result};
 use crate::domain::models::EnhancedPrompt;
 use std::collections::HashSet;
+use tracing::debug;
 
 #[derive(Debug, Clone)]
 pub enum ValidationError {
@@ -61,7 +62,14 @@ pub fn compute_confidence(prompt: &EnhancedPrompt) -> f32 {
     (len_score + word_score) / 2.0
 }
 
-#[allow(dead_code)]
+/// Every non-fatal finding on an enhanced prompt
+pub fn find_issues(text: &str) -> Vec<String> {
+    let mut issues = check_grammar_and_clarity(text);
+    issues.extend(check_consistency(text));
+    issues.extend(check_formatting(text));
+    issues
+}
+
 pub fn check_grammar_and_clarity(text: &str) -> Vec<String> {
     let mut issues = Vec::new();
     // Check for double spaces
@@ -73,7 +81,7 @@ pub fn check_grammar_and_clarity(text: &str) -> Vec<String> {
         issues.push("Some lines end with space".to_string());
     }
     // Check sentence length for clarity
-    let sentences: Vec<&str> = text.split(|c| c == '.' || c == '!' || c == '?').collect();
+    let sentences: Vec<&str> = text.split(['.', '!', '?']).collect();
     let avg_length = sentences.iter().map(|s| s.len()).sum::<usize>() / sentences.len().max(1);
     if avg_length > 100 {
         issues.push("Average sentence length is too long (>100 chars), may affect clarity".to_string());
@@ -84,11 +92,10 @@ pub fn check_grammar_and_clarity(text: &str) -> Vec<String> {
     issues
 }
 
-#[allow(dead_code)]
 pub fn check_consistency(text: &str) -> Vec<String> {
     let mut issues = Vec::new();
     // Check for duplicate sentences
-    let sentences: Vec<&str> = text.split(|c| c == '.' || c == '!' || c == '?').collect();
+    let sentences: Vec<&str> = text.split(['.', '!', '?']).collect();
     let mut seen = HashSet::new();
     for sentence in sentences {
         let trimmed = sentence.trim();
@@ -100,7 +107,6 @@ pub fn check_consistency(text: &str) -> Vec<String> {
     issues
 }
 
-#[allow(dead_code)]
 pub fn check_formatting(text: &str) -> Vec<String> {
     let mut issues = Vec::new();
     // Check for inconsistent spacing around punctuation
@@ -116,8 +122,7 @@ pub fn check_formatting(text: &str) -> Vec<String> {
 
 #[allow(dead_code)]
 pub fn track_quality_metrics(text: &str, confidence: f32, issues: &[String]) {
-    // Simple logging
-    println!("Quality metrics - text length: {}, confidence: {}, issues: {}", text.len(), confidence, issues.len());
+    debug!(text_length = text.len(), confidence, issues = issues.len(), "quality metrics");
 }
 
 fn word_count(text: &str) -> usize {
@@ -135,6 +140,7 @@ mod tests {
             text: "This is a valid enhanced prompt with enough length and words to pass validation.".to_string(),
             rationale: None,
             confidence: None,
+            ..Default::default()
         };
         assert!(validate_enhanced_prompt(&prompt).is_ok());
     }
@@ -145,6 +151,7 @@ mod tests {
             text: "".to_string(),
             rationale: None,
             confidence: None,
+            ..Default::default()
         };
         assert!(matches!(validate_enhanced_prompt(&prompt), Err(ValidationError::EmptyPrompt)));
     }
@@ -155,6 +162,7 @@ mod tests {
             text: "Short".to_string(),
             rationale: None,
             confidence: None,
+            ..Default::default()
         };
         assert!(matches!(validate_enhanced_prompt(&prompt), Err(ValidationError::TooShort)));
     }
@@ -166,6 +174,7 @@ mod tests {
             text,
             rationale: None,
             confidence: None,
+            ..Default::default()
         };
         assert!(matches!(validate_enhanced_prompt(&prompt), Err(ValidationError::TooLong)));
     }
@@ -176,6 +185,7 @@ mod tests {
             text: "Short text".to_string(),
             rationale: None,
             confidence: None,
+            ..Default::default()
         };
         assert!(matches!(validate_enhanced_prompt(&prompt), Err(ValidationError::TooSimple)));
     }
@@ -186,6 +196,7 @@ mod tests {
             text: "This is a long prompt that contains inappropriate content and has enough words.".to_string(),
             rationale: None,
             confidence: None,
+            ..Default::default()
         };
         assert!(matches!(validate_enhanced_prompt(&prompt), Err(ValidationError::InappropriateContent(_))));
     }
@@ -196,6 +207,7 @@ mod tests {
             text: "This is a test prompt with some words to compute confidence score.".to_string(),
             rationale: None,
             confidence: None,
+            ..Default::default()
         };
         let score = compute_confidence(&prompt);
         assert!(score > 0.0 && score <= 1.0);
@@ -256,4 +268,13 @@ mod tests {
         let issues = check_formatting(text);
         assert!(issues.contains(&"Inconsistent spacing around punctuation".to_string()));
     }
+
+    #[test]
+    fn test_find_issues_collects_every_check() {
+        let issues = find_issues("Write a test  for the parser , please. Write a test  for the parser , please.");
+
+        assert!(issues.contains(&"Contains double spaces".to_string()));
+        assert!(issues.contains(&"Duplicate sentence found".to_string()));
+        assert!(issues.contains(&"Inconsistent spacing around punctuation".to_string()));
+    }
 }
@@ -1,10 +1,27 @@
+use crate::domain::models::DegradationPolicy;
 use std::env;
+use std::fmt;
+use std::time::Duration;
 
 /// Centralized application configuration
 #[derive(Debug, Clone)]
 pub struct Config {
-    /// OpenRouter API configuration
-    pub openrouter: OpenRouterConfig,
+    /// Which LLM backend to use
+    pub provider: ProviderKind,
+    /// OpenRouter API configuration, set when that provider is selected
+    pub openrouter: Option<OpenRouterConfig>,
+    /// Generic OpenAI-compatible endpoint configuration, set when that provider is selected
+    pub openai_compatible: Option<OpenAICompatibleConfig>,
+    /// Anthropic Messages API configuration, set when that provider is selected
+    pub anthropic: Option<AnthropicConfig>,
+    /// Ollama configuration, set when that provider is selected
+    pub ollama: Option<OllamaConfig>,
+    /// Ordered failover chain; when non-empty it replaces `provider`
+    pub fallback: FallbackConfig,
+    /// What to return when no backend can produce an enhancement
+    pub degradation: DegradationPolicy,
+    /// Retry behaviour for transient HTTP failures, shared by all providers
+    pub retry: RetryConfig,
     /// Sequential thinking configuration
     pub sequential_thinking: SequentialThinkingConfig,
     /// Logging configuration
@@ -16,10 +33,84 @@ pub struct Config {
 pub struct OpenRouterConfig {
     pub api_key: String,
     pub model: String,
+    /// API base URL, e.g. `https://openrouter.ai/api/v1`
+    pub base_url: String,
     pub referer: Option<String>,
     pub title: Option<String>,
 }
 
+/// Configuration for any server speaking the OpenAI chat-completions API
+#[derive(Debug, Clone)]
+pub struct OpenAICompatibleConfig {
+    /// Base URL up to and including the API version, e.g. `http://localhost:8000/v1`
+    pub base_url: String,
+    pub api_key: Option<String>,
+    pub model: String,
+}
+
+/// Anthropic Messages API configuration
+#[derive(Debug, Clone)]
+pub struct AnthropicConfig {
+    pub api_key: String,
+    pub model: String,
+    pub base_url: String,
+    /// Required by the Messages API: upper bound on generated tokens
+    pub max_tokens: u32,
+    /// Value of the `anthropic-version` header
+    pub version: String,
+}
+
+/// Local Ollama server configuration
+#[derive(Debug, Clone)]
+pub struct OllamaConfig {
+    pub host: String,
+    pub model: String,
+    /// Context window size passed as `options.num_ctx`
+    pub num_ctx: Option<u32>,
+    /// Sampling temperature; overrides the per-request default when set
+    pub temperature: Option<f32>,
+    /// Read the response as a stream of NDJSON chunks
+    pub stream: bool,
+}
+
+/// Supported LLM backends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderKind {
+    OpenRouter,
+    OpenAICompatible,
+    Anthropic,
+    Ollama,
+}
+
+/// A backend plus an optional model override, e.g. `openrouter:openai/gpt-4o-mini`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProviderTarget {
+    pub kind: ProviderKind,
+    pub model: Option<String>,
+}
+
+/// Provider failover configuration
+#[derive(Debug, Clone, Default)]
+pub struct FallbackConfig {
+    /// Backends to try in order
+    pub chain: Vec<ProviderTarget>,
+    /// Give up on a backend and move to the next one after this long
+    pub timeout: Option<Duration>,
+}
+
+/// Retry policy for transport errors and retryable HTTP statuses (408, 429, 5xx)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryConfig {
+    /// Total number of attempts, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled on every further attempt
+    pub base_delay: Duration,
+    /// Upper bound for any single delay, including one requested via `Retry-After`
+    pub max_delay: Duration,
+    /// Randomize each backoff delay between half and the full value
+    pub jitter: bool,
+}
+
 /// Sequential thinking configuration
 #[derive(Debug, Clone)]
 pub struct SequentialThinkingConfig {
@@ -35,12 +126,35 @@ pub struct LoggingConfig {
 impl Config {
     /// Load configuration from environment variables
     pub fn from_env() -> Result<Self, String> {
-        let openrouter = OpenRouterConfig::from_env()?;
+        let provider = ProviderKind::from_env()?;
+        let fallback = FallbackConfig::from_env()?;
+
+        // Only the settings of backends that will actually be used are required
+        let uses = |kind: ProviderKind| {
+            if fallback.chain.is_empty() {
+                provider == kind
+            } else {
+                fallback.chain.iter().any(|target| target.kind == kind)
+            }
+        };
+        let openrouter = if uses(ProviderKind::OpenRouter) { Some(OpenRouterConfig::from_env()?) } else { None };
+        let openai_compatible = if uses(ProviderKind::OpenAICompatible) { Some(OpenAICompatibleConfig::from_env()?) } else { None };
+        let anthropic = if uses(ProviderKind::Anthropic) { Some(AnthropicConfig::from_env()?) } else { None };
+        let ollama = if uses(ProviderKind::Ollama) { Some(OllamaConfig::from_env()?) } else { None };
+        let degradation = parse_env("DEGRADATION_POLICY")?.unwrap_or_default();
+        let retry = RetryConfig::from_env()?;
         let sequential_thinking = SequentialThinkingConfig::from_env();
         let logging = LoggingConfig::from_env();
 
         Ok(Self {
+            provider,
             openrouter,
+            openai_compatible,
+            anthropic,
+            ollama,
+            fallback,
+            degradation,
+            retry,
             sequential_thinking,
             logging,
         })
@@ -59,18 +173,172 @@ impl OpenRouterConfig {
             .map_err(|_| "OPENROUTER_API_KEY environment variable is required")?;
 
         let model = env::var("OPENROUTER_MODEL").unwrap_or_else(|_| "openrouter/auto".to_string());
+        let base_url = env::var("OPENROUTER_BASE_URL").unwrap_or_else(|_| "https://openrouter.ai/api/v1".to_string());
         let referer = env::var("OPENROUTER_REFERER").ok();
         let title = env::var("OPENROUTER_TITLE").ok();
 
         Ok(Self {
             api_key,
             model,
+            base_url,
             referer,
             title,
         })
     }
 }
 
+impl OpenAICompatibleConfig {
+    /// Load OpenAI-compatible endpoint configuration from environment variables
+    pub fn from_env() -> Result<Self, String> {
+        let base_url = env::var("OPENAI_COMPATIBLE_BASE_URL")
+            .map_err(|_| "OPENAI_COMPATIBLE_BASE_URL environment variable is required")?;
+        let model = env::var("OPENAI_COMPATIBLE_MODEL")
+            .map_err(|_| "OPENAI_COMPATIBLE_MODEL environment variable is required")?;
+        let api_key = env::var("OPENAI_COMPATIBLE_API_KEY").ok();
+
+        Ok(Self {
+            base_url,
+            api_key,
+            model,
+        })
+    }
+}
+
+impl AnthropicConfig {
+    /// Load Anthropic configuration from environment variables
+    pub fn from_env() -> Result<Self, String> {
+        let api_key = env::var("ANTHROPIC_API_KEY")
+            .map_err(|_| "ANTHROPIC_API_KEY environment variable is required")?;
+
+        let model = env::var("ANTHROPIC_MODEL").unwrap_or_else(|_| "claude-3-5-sonnet-latest".to_string());
+        let base_url = env::var("ANTHROPIC_BASE_URL").unwrap_or_else(|_| "https://api.anthropic.com".to_string());
+        let max_tokens = parse_env("ANTHROPIC_MAX_TOKENS")?.unwrap_or(1024);
+        let version = env::var("ANTHROPIC_VERSION").unwrap_or_else(|_| "2023-06-01".to_string());
+
+        Ok(Self {
+            api_key,
+            model,
+            base_url,
+            max_tokens,
+            version,
+        })
+    }
+}
+
+impl OllamaConfig {
+    /// Load Ollama configuration from environment variables
+    pub fn from_env() -> Result<Self, String> {
+        let host = env::var("OLLAMA_HOST").unwrap_or_else(|_| "http://localhost:11434".to_string());
+        let model = env::var("OLLAMA_MODEL").unwrap_or_else(|_| "llama3.2".to_string());
+        let num_ctx = parse_env("OLLAMA_NUM_CTX")?;
+        let temperature = parse_env("OLLAMA_TEMPERATURE")?;
+        let stream = env::var("OLLAMA_STREAM")
+            .map(|val| matches!(val.to_lowercase().as_str(), "true" | "1" | "yes" | "on"))
+            .unwrap_or(false);
+
+        Ok(Self {
+            host,
+            model,
+            num_ctx,
+            temperature,
+            stream,
+        })
+    }
+}
+
+impl ProviderKind {
+    /// Read the selected provider from `LLM_PROVIDER` (default: openrouter)
+    pub fn from_env() -> Result<Self, String> {
+        match env::var("LLM_PROVIDER") {
+            Ok(val) => Self::parse(&val),
+            Err(_) => Ok(ProviderKind::OpenRouter),
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().replace('-', "_").as_str() {
+            "openrouter" => Ok(ProviderKind::OpenRouter),
+            "openai_compatible" | "openai" => Ok(ProviderKind::OpenAICompatible),
+            "anthropic" => Ok(ProviderKind::Anthropic),
+            "ollama" => Ok(ProviderKind::Ollama),
+            other => Err(format!("unknown LLM_PROVIDER: {}", other)),
+        }
+    }
+}
+
+impl fmt::Display for ProviderKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ProviderKind::OpenRouter => "openrouter",
+            ProviderKind::OpenAICompatible => "openai_compatible",
+            ProviderKind::Anthropic => "anthropic",
+            ProviderKind::Ollama => "ollama",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl ProviderTarget {
+    /// Parse `kind` or `kind:model`; everything after the first `:` is the model,
+    /// so Ollama tags such as `ollama:qwen2.5:7b` are kept intact
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (kind, model) = match value.trim().split_once(':') {
+            Some((kind, model)) => (kind, Some(model.trim().to_string()).filter(|m| !m.is_empty())),
+            None => (value.trim(), None),
+        };
+        Ok(Self { kind: ProviderKind::parse(kind)?, model })
+    }
+}
+
+impl FallbackConfig {
+    /// Load the failover chain from `LLM_FALLBACK_CHAIN` (comma separated targets)
+    /// and the per-backend timeout from `LLM_FALLBACK_TIMEOUT_SECS`
+    pub fn from_env() -> Result<Self, String> {
+        let chain = match env::var("LLM_FALLBACK_CHAIN") {
+            Ok(val) => val
+                .split(',')
+                .filter(|entry| !entry.trim().is_empty())
+                .map(ProviderTarget::parse)
+                .collect::<Result<Vec<_>, _>>()?,
+            Err(_) => Vec::new(),
+        };
+        let timeout = parse_env("LLM_FALLBACK_TIMEOUT_SECS")?.map(Duration::from_secs);
+
+        Ok(Self { chain, timeout })
+    }
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Load the retry policy from `LLM_RETRY_*` environment variables
+    pub fn from_env() -> Result<Self, String> {
+        let defaults = Self::default();
+        let max_attempts = parse_env("LLM_RETRY_MAX_ATTEMPTS")?.unwrap_or(defaults.max_attempts).max(1);
+        let base_delay = parse_env("LLM_RETRY_BASE_DELAY_MS")?.map(Duration::from_millis).unwrap_or(defaults.base_delay);
+        let max_delay = parse_env("LLM_RETRY_MAX_DELAY_MS")?.map(Duration::from_millis).unwrap_or(defaults.max_delay);
+        let jitter = env::var("LLM_RETRY_JITTER")
+            .map(|val| !matches!(val.to_lowercase().as_str(), "false" | "0" | "no" | "off"))
+            .unwrap_or(defaults.jitter);
+
+        Ok(Self {
+            max_attempts,
+            base_delay,
+            max_delay,
+            jitter,
+        })
+    }
+}
+
 impl SequentialThinkingConfig {
     /// Load sequential thinking configuration from environment variables
     pub fn from_env() -> Self {
@@ -93,6 +361,18 @@ impl LoggingConfig {
     }
 }
 
+/// Parse an optional environment variable, failing on malformed values
+fn parse_env<T: std::str::FromStr>(name: &str) -> Result<Option<T>, String> {
+    match env::var(name) {
+        Ok(val) => val
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("{} has an invalid value: {}", name, val)),
+        Err(_) => Ok(None),
+    }
+}
+
 /// Get the default value for enable_sequential_thinking from environment variables
 /// Returns true if ENABLE_SEQUENTIAL_THINKING is set to "true", "1", "yes", or "on" (case-insensitive)
 /// Returns false if set to "false", "0", "no", or "off" (case-insensitive)
@@ -116,13 +396,69 @@ pub fn get_enable_sequential_thinking_default() -> bool {
 }
 
 #[cfg(test)]
+static ENV_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Serializes tests that mutate process environment variables
+#[cfg(test)]
+pub(crate) fn lock_env() -> std::sync::MutexGuard<'static, ()> {
+    ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// OpenRouter with a dummy key, no fallback chain and sequential thinking off
+/// unless a test turns it on
+#[cfg(test)]
+pub(crate) fn create_test_config() -> Config {
+    Config {
+        provider: ProviderKind::OpenRouter,
+        openrouter: Some(OpenRouterConfig {
+            api_key: "test-key".to_string(),
+            model: "test-model".to_string(),
+            base_url: "https://openrouter.ai/api/v1".to_string(),
+            referer: None,
+            title: None,
+        }),
+        openai_compatible: None,
+        anthropic: None,
+        ollama: None,
+        fallback: FallbackConfig::default(),
+        degradation: DegradationPolicy::Fail,
+        retry: RetryConfig::default(),
+        sequential_thinking: SequentialThinkingConfig { default_enabled: false },
+        logging: LoggingConfig { level: "info".to_string() },
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
     use std::env;
 
     fn clear_env() {
+        env::remove_var("LLM_PROVIDER");
+        env::remove_var("OPENAI_COMPATIBLE_BASE_URL");
+        env::remove_var("OPENAI_COMPATIBLE_MODEL");
+        env::remove_var("OPENAI_COMPATIBLE_API_KEY");
+        env::remove_var("ANTHROPIC_API_KEY");
+        env::remove_var("ANTHROPIC_MODEL");
+        env::remove_var("ANTHROPIC_BASE_URL");
+        env::remove_var("ANTHROPIC_MAX_TOKENS");
+        env::remove_var("ANTHROPIC_VERSION");
+        env::remove_var("OLLAMA_HOST");
+        env::remove_var("OLLAMA_MODEL");
+        env::remove_var("OLLAMA_NUM_CTX");
+        env::remove_var("OLLAMA_TEMPERATURE");
+        env::remove_var("OLLAMA_STREAM");
+        env::remove_var("LLM_FALLBACK_CHAIN");
+        env::remove_var("LLM_FALLBACK_TIMEOUT_SECS");
+        env::remove_var("DEGRADATION_POLICY");
+        env::remove_var("LLM_RETRY_MAX_ATTEMPTS");
+        env::remove_var("LLM_RETRY_BASE_DELAY_MS");
+        env::remove_var("LLM_RETRY_MAX_DELAY_MS");
+        env::remove_var("LLM_RETRY_JITTER");
         env::remove_var("OPENROUTER_API_KEY");
         env::remove_var("OPENROUTER_MODEL");
+        env::remove_var("OPENROUTER_BASE_URL");
         env::remove_var("OPENROUTER_REFERER");
         env::remove_var("OPENROUTER_TITLE");
         env::remove_var("ENABLE_SEQUENTIAL_THINKING");
@@ -137,6 +473,7 @@ mod tests {
 
     #[test]
     fn test_config_from_env_complete() {
+        let _guard = lock_env();
         clear_env();
         set_env(&[
             ("OPENROUTER_API_KEY", "test-api-key"),
@@ -149,30 +486,35 @@ mod tests {
 
         let config = Config::from_env().unwrap();
 
-        assert_eq!(config.openrouter.api_key, "test-api-key");
-        assert_eq!(config.openrouter.model, "test-model");
-        assert_eq!(config.openrouter.referer.as_deref(), Some("test-referer"));
-        assert_eq!(config.openrouter.title.as_deref(), Some("test-title"));
+        let openrouter = config.openrouter.unwrap();
+        assert_eq!(openrouter.api_key, "test-api-key");
+        assert_eq!(openrouter.model, "test-model");
+        assert_eq!(openrouter.referer.as_deref(), Some("test-referer"));
+        assert_eq!(openrouter.title.as_deref(), Some("test-title"));
         assert_eq!(config.sequential_thinking.default_enabled, false);
         assert_eq!(config.logging.level, "debug");
     }
 
     #[test]
     fn test_config_from_env_minimal() {
+        let _guard = lock_env();
         clear_env();
         set_env(&[("OPENROUTER_API_KEY", "test-api-key")]);
 
         let config = Config::from_env().unwrap();
 
-        assert_eq!(config.openrouter.api_key, "test-api-key");
-        assert_eq!(config.openrouter.model, "openrouter/auto");
-        assert!(config.openrouter.referer.is_none());
-        assert!(config.openrouter.title.is_none());
+        let openrouter = config.openrouter.as_ref().unwrap();
+        assert_eq!(openrouter.api_key, "test-api-key");
+        assert_eq!(openrouter.model, "openrouter/auto");
+        assert_eq!(openrouter.base_url, "https://openrouter.ai/api/v1");
+        assert!(openrouter.referer.is_none());
+        assert!(openrouter.title.is_none());
         assert_eq!(config.logging.level, "info"); // default
     }
 
     #[test]
     fn test_config_from_env_missing_api_key() {
+        let _guard = lock_env();
         clear_env();
 
         let result = Config::from_env();
@@ -182,6 +524,7 @@ mod tests {
 
     #[test]
     fn test_openrouter_config_from_env() {
+        let _guard = lock_env();
         clear_env();
         set_env(&[
             ("OPENROUTER_API_KEY", "test-key"),
@@ -200,6 +543,7 @@ mod tests {
 
     #[test]
     fn test_sequential_thinking_config_from_env() {
+        let _guard = lock_env();
         clear_env();
 
         // Test default (true)
@@ -219,6 +563,7 @@ mod tests {
 
     #[test]
     fn test_logging_config_from_env() {
+        let _guard = lock_env();
         clear_env();
 
         // Test default
@@ -233,6 +578,7 @@ mod tests {
 
     #[test]
     fn test_get_enable_sequential_thinking_default_true_values() {
+        let _guard = lock_env();
         clear_env();
         let true_values = ["true", "TRUE", "True", "1", "yes", "YES", "on", "ON"];
         for val in &true_values {
@@ -243,6 +589,7 @@ mod tests {
 
     #[test]
     fn test_get_enable_sequential_thinking_default_false_values() {
+        let _guard = lock_env();
         clear_env();
         let false_values = ["false", "FALSE", "False", "0", "no", "NO", "off", "OFF"];
         for val in &false_values {
@@ -253,6 +600,7 @@ mod tests {
 
     #[test]
     fn test_get_enable_sequential_thinking_default_unrecognized() {
+        let _guard = lock_env();
         clear_env();
         env::set_var("ENABLE_SEQUENTIAL_THINKING", "maybe");
         assert_eq!(get_enable_sequential_thinking_default(), true, "Unrecognized value should default to true");
@@ -260,7 +608,211 @@ mod tests {
 
     #[test]
     fn test_get_enable_sequential_thinking_default_not_set() {
+        let _guard = lock_env();
         clear_env();
         assert_eq!(get_enable_sequential_thinking_default(), true, "Should default to true when not set");
     }
+
+    #[test]
+    fn test_provider_kind_parse() {
+        assert_eq!(ProviderKind::parse("openrouter").unwrap(), ProviderKind::OpenRouter);
+        assert_eq!(ProviderKind::parse("OpenAI-Compatible").unwrap(), ProviderKind::OpenAICompatible);
+        assert_eq!(ProviderKind::parse("openai").unwrap(), ProviderKind::OpenAICompatible);
+        assert_eq!(ProviderKind::parse("Anthropic").unwrap(), ProviderKind::Anthropic);
+        assert_eq!(ProviderKind::parse("ollama").unwrap(), ProviderKind::Ollama);
+        assert!(ProviderKind::parse("bogus").unwrap_err().contains("bogus"));
+    }
+
+    #[test]
+    fn test_config_from_env_openai_compatible() {
+        let _guard = lock_env();
+        clear_env();
+        set_env(&[
+            ("LLM_PROVIDER", "openai_compatible"),
+            ("OPENAI_COMPATIBLE_BASE_URL", "http://localhost:8000/v1"),
+            ("OPENAI_COMPATIBLE_MODEL", "llama-3-8b"),
+        ]);
+
+        let config = Config::from_env().unwrap();
+
+        assert_eq!(config.provider, ProviderKind::OpenAICompatible);
+        let openai = config.openai_compatible.unwrap();
+        assert_eq!(openai.base_url, "http://localhost:8000/v1");
+        assert_eq!(openai.model, "llama-3-8b");
+        assert!(openai.api_key.is_none());
+    }
+
+    #[test]
+    fn test_config_from_env_openai_compatible_missing_base_url() {
+        let _guard = lock_env();
+        clear_env();
+        set_env(&[
+            ("LLM_PROVIDER", "openai_compatible"),
+            ("OPENAI_COMPATIBLE_MODEL", "llama-3-8b"),
+        ]);
+
+        let result = Config::from_env();
+        assert!(result.unwrap_err().contains("OPENAI_COMPATIBLE_BASE_URL"));
+    }
+
+    #[test]
+    fn test_config_from_env_anthropic() {
+        let _guard = lock_env();
+        clear_env();
+        set_env(&[
+            ("LLM_PROVIDER", "anthropic"),
+            ("ANTHROPIC_API_KEY", "sk-ant-test"),
+            ("ANTHROPIC_MAX_TOKENS", "2048"),
+        ]);
+
+        let config = Config::from_env().unwrap();
+
+        assert_eq!(config.provider, ProviderKind::Anthropic);
+        let anthropic = config.anthropic.unwrap();
+        assert_eq!(anthropic.api_key, "sk-ant-test");
+        assert_eq!(anthropic.model, "claude-3-5-sonnet-latest");
+        assert_eq!(anthropic.base_url, "https://api.anthropic.com");
+        assert_eq!(anthropic.max_tokens, 2048);
+        assert_eq!(anthropic.version, "2023-06-01");
+    }
+
+    #[test]
+    fn test_anthropic_config_invalid_max_tokens() {
+        let _guard = lock_env();
+        clear_env();
+        set_env(&[("ANTHROPIC_API_KEY", "sk-ant-test"), ("ANTHROPIC_MAX_TOKENS", "lots")]);
+
+        let result = AnthropicConfig::from_env();
+        assert!(result.unwrap_err().contains("ANTHROPIC_MAX_TOKENS"));
+    }
+
+    #[test]
+    fn test_config_from_env_ollama_without_openrouter_key() {
+        let _guard = lock_env();
+        clear_env();
+        set_env(&[
+            ("LLM_PROVIDER", "ollama"),
+            ("OLLAMA_MODEL", "qwen2.5:7b"),
+            ("OLLAMA_NUM_CTX", "8192"),
+            ("OLLAMA_TEMPERATURE", "0.4"),
+            ("OLLAMA_STREAM", "true"),
+        ]);
+
+        let config = Config::from_env().unwrap();
+
+        assert_eq!(config.provider, ProviderKind::Ollama);
+        assert!(config.openrouter.is_none());
+        let ollama = config.ollama.unwrap();
+        assert_eq!(ollama.host, "http://localhost:11434");
+        assert_eq!(ollama.model, "qwen2.5:7b");
+        assert_eq!(ollama.num_ctx, Some(8192));
+        assert_eq!(ollama.temperature, Some(0.4));
+        assert!(ollama.stream);
+    }
+
+    #[test]
+    fn test_ollama_config_defaults() {
+        let _guard = lock_env();
+        clear_env();
+
+        let config = OllamaConfig::from_env().unwrap();
+
+        assert_eq!(config.model, "llama3.2");
+        assert!(config.num_ctx.is_none());
+        assert!(config.temperature.is_none());
+        assert!(!config.stream);
+    }
+
+    #[test]
+    fn test_ollama_config_invalid_num_ctx() {
+        let _guard = lock_env();
+        clear_env();
+        set_env(&[("OLLAMA_NUM_CTX", "big")]);
+
+        assert!(OllamaConfig::from_env().unwrap_err().contains("OLLAMA_NUM_CTX"));
+    }
+
+    #[test]
+    fn test_provider_target_parse() {
+        assert_eq!(
+            ProviderTarget::parse("openrouter:openai/gpt-4o-mini").unwrap(),
+            ProviderTarget { kind: ProviderKind::OpenRouter, model: Some("openai/gpt-4o-mini".into()) }
+        );
+        assert_eq!(
+            ProviderTarget::parse(" ollama:qwen2.5:7b ").unwrap(),
+            ProviderTarget { kind: ProviderKind::Ollama, model: Some("qwen2.5:7b".into()) }
+        );
+        assert_eq!(
+            ProviderTarget::parse("anthropic").unwrap(),
+            ProviderTarget { kind: ProviderKind::Anthropic, model: None }
+        );
+        assert!(ProviderTarget::parse("nope:model").is_err());
+    }
+
+    #[test]
+    fn test_config_from_env_fallback_chain() {
+        let _guard = lock_env();
+        clear_env();
+        set_env(&[
+            ("OPENROUTER_API_KEY", "test-api-key"),
+            ("LLM_FALLBACK_CHAIN", "openrouter:model-a, openrouter:model-b,ollama"),
+            ("LLM_FALLBACK_TIMEOUT_SECS", "30"),
+        ]);
+
+        let config = Config::from_env().unwrap();
+
+        assert_eq!(config.fallback.chain.len(), 3);
+        assert_eq!(config.fallback.chain[1].model.as_deref(), Some("model-b"));
+        assert_eq!(config.fallback.chain[2].kind, ProviderKind::Ollama);
+        assert_eq!(config.fallback.timeout, Some(Duration::from_secs(30)));
+        assert!(config.openrouter.is_some());
+        assert!(config.ollama.is_some());
+        assert!(config.anthropic.is_none());
+    }
+
+    #[test]
+    fn test_config_from_env_fallback_chain_requires_backend_settings() {
+        let _guard = lock_env();
+        clear_env();
+        set_env(&[("LLM_PROVIDER", "ollama"), ("LLM_FALLBACK_CHAIN", "ollama,anthropic")]);
+
+        let result = Config::from_env();
+        assert!(result.unwrap_err().contains("ANTHROPIC_API_KEY"));
+    }
+
+    #[test]
+    fn test_config_from_env_degradation_policy() {
+        let _guard = lock_env();
+        clear_env();
+        set_env(&[("OPENROUTER_API_KEY", "test-api-key")]);
+        assert_eq!(Config::from_env().unwrap().degradation, DegradationPolicy::Fail);
+
+        set_env(&[("DEGRADATION_POLICY", "local")]);
+        assert_eq!(Config::from_env().unwrap().degradation, DegradationPolicy::Local);
+
+        set_env(&[("DEGRADATION_POLICY", "whatever")]);
+        assert!(Config::from_env().unwrap_err().contains("DEGRADATION_POLICY"));
+    }
+
+    #[test]
+    fn test_retry_config_from_env() {
+        let _guard = lock_env();
+        clear_env();
+        assert_eq!(RetryConfig::from_env().unwrap(), RetryConfig::default());
+
+        set_env(&[
+            ("LLM_RETRY_MAX_ATTEMPTS", "5"),
+            ("LLM_RETRY_BASE_DELAY_MS", "100"),
+            ("LLM_RETRY_MAX_DELAY_MS", "2000"),
+            ("LLM_RETRY_JITTER", "off"),
+        ]);
+        let config = RetryConfig::from_env().unwrap();
+        assert_eq!(config.max_attempts, 5);
+        assert_eq!(config.base_delay, Duration::from_millis(100));
+        assert_eq!(config.max_delay, Duration::from_secs(2));
+        assert!(!config.jitter);
+
+        set_env(&[("LLM_RETRY_MAX_ATTEMPTS", "many")]);
+        assert!(RetryConfig::from_env().unwrap_err().contains("LLM_RETRY_MAX_ATTEMPTS"));
+    }
 }
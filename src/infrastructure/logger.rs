@@ -1,13 +1,191 @@
+use serde_json::{Map, Value};
+use std::fmt::Debug;
+use std::sync::Arc;
+use tracing::field::{Field, Visit};
+use tracing::{span, Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::{fmt, EnvFilter};
 
-pub fn init_tracing(level: &str) {
+/// Only events of this crate are forwarded; dependencies stay on stderr
+const FORWARDED_TARGET: &str = env!("CARGO_CRATE_NAME");
+
+/// A tracing event on its way to a `LogSink`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogRecord {
+    pub level: Level,
+    /// the module that emitted the event
+    pub target: String,
+    /// the message and every other field of the event
+    pub fields: Map<String, Value>,
+    /// the `session` field of the innermost enclosing span that has one
+    pub session: Option<String>,
+}
+
+/// Receives tracing events besides stderr, e.g. to pass them on to MCP clients
+pub trait LogSink: Send + Sync {
+    /// The most verbose level anyone listens for; `None` while nobody does
+    fn max_level(&self) -> Option<Level>;
+    fn log(&self, record: LogRecord);
+}
+
+/// Log to stderr at `level` (or `RUST_LOG`) and forward events to `sink` at
+/// whatever level it asks for
+pub fn init_tracing(level: &str, sink: Arc<dyn LogSink>) {
     let filter = EnvFilter::try_from_default_env()
         .or_else(|_| EnvFilter::try_new(level))
         .unwrap_or_else(|_| EnvFilter::new("info"));
 
-    fmt()
-        .with_env_filter(filter)
-        .with_target(false)
-        .compact()
-        .init();
+    // stdout carries the stdio transport, so logs must never go there
+    let stderr = fmt::layer().with_writer(std::io::stderr).with_target(false).compact().with_filter(filter);
+
+    tracing_subscriber::registry().with(stderr).with(ForwardingLayer { sink }).init();
+}
+
+/// Hands events to a `LogSink`, tagged with the session they happened in
+struct ForwardingLayer {
+    sink: Arc<dyn LogSink>,
+}
+
+/// Stored on spans that carry a `session` field
+struct SessionScope(String);
+
+impl<S> Layer<S> for ForwardingLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let mut fields = FieldVisitor::default();
+        attrs.record(&mut fields);
+        let Some(session) = fields.0.remove("session") else { return };
+        if let Some(span) = ctx.span(id) {
+            let session = match session {
+                Value::String(session) => session,
+                other => other.to_string(),
+            };
+            span.extensions_mut().insert(SessionScope(session));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if !metadata.target().starts_with(FORWARDED_TARGET) {
+            return;
+        }
+        match self.sink.max_level() {
+            // More verbose levels compare greater
+            Some(max) if *metadata.level() <= max => {}
+            _ => return,
+        }
+
+        let mut fields = FieldVisitor::default();
+        event.record(&mut fields);
+        let session = ctx
+            .event_scope(event)
+            .and_then(|mut scope| scope.find_map(|span| span.extensions().get::<SessionScope>().map(|scope| scope.0.clone())));
+        self.sink.log(LogRecord { level: *metadata.level(), target: metadata.target().to_string(), fields: fields.0, session });
+    }
+}
+
+/// Collects event or span fields as JSON
+#[derive(Default)]
+struct FieldVisitor(Map<String, Value>);
+
+impl Visit for FieldVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0.insert(field.name().to_string(), Value::String(format!("{:?}", value)));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), Value::String(value.to_string()));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use tracing::{debug, info, info_span, warn};
+
+    #[derive(Default)]
+    struct RecordingSink {
+        max_level: Option<Level>,
+        records: Mutex<Vec<LogRecord>>,
+    }
+
+    impl LogSink for RecordingSink {
+        fn max_level(&self) -> Option<Level> {
+            self.max_level
+        }
+
+        fn log(&self, record: LogRecord) {
+            self.records.lock().unwrap().push(record);
+        }
+    }
+
+    fn capture(max_level: Option<Level>, emit: impl FnOnce()) -> Vec<LogRecord> {
+        let sink = Arc::new(RecordingSink { max_level, ..Default::default() });
+        let subscriber = tracing_subscriber::registry().with(ForwardingLayer { sink: sink.clone() });
+        tracing::subscriber::with_default(subscriber, emit);
+        let records = sink.records.lock().unwrap().clone();
+        records
+    }
+
+    #[test]
+    fn test_forwards_events_up_to_the_sink_level() {
+        let records = capture(Some(Level::INFO), || {
+            debug!("too verbose");
+            info!(count = 3, "loaded");
+            warn!(reason = ?Some("x"), "degraded");
+        });
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].level, Level::INFO);
+        assert_eq!(records[0].fields["message"], "loaded");
+        assert_eq!(records[0].fields["count"], 3);
+        assert_eq!(records[1].fields["reason"], "Some(\"x\")");
+        assert!(records[0].target.starts_with("anytra"));
+    }
+
+    #[test]
+    fn test_nothing_is_forwarded_without_listeners() {
+        assert!(capture(None, || warn!("unheard")).is_empty());
+    }
+
+    #[test]
+    fn test_events_are_tagged_with_their_session() {
+        let records = capture(Some(Level::INFO), || {
+            let _outer = info_span!("mcp_http", session = "abc").entered();
+            let _inner = info_span!("request", id = 1).entered();
+            info!("in session");
+        });
+
+        assert_eq!(records[0].session.as_deref(), Some("abc"));
+    }
+
+    #[test]
+    fn test_other_crates_are_not_forwarded() {
+        let records = capture(Some(Level::TRACE), || {
+            tracing::info!(target: "hyper::proto", "connection closed");
+        });
+
+        assert!(records.is_empty());
+    }
 }
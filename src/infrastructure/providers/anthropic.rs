@@ -0,0 +1,330 @@
+use crate::domain::llm::{LLMError, LLMProvider, ProviderError};
+use crate::domain::models::{EnhancedPrompt, EnhancementOptions, Prompt, TokenUsage};
+use crate::domain::prompting::{enhancement_request, CompletionRequest};
+use crate::infrastructure::config::{AnthropicConfig, RetryConfig};
+use crate::infrastructure::providers::retry::{retry_after, send_with_retry};
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+
+/// Client for the native Anthropic Messages API
+pub struct AnthropicClient {
+    http: reqwest::Client,
+    config: AnthropicConfig,
+    retry: RetryConfig,
+}
+
+impl AnthropicClient {
+    pub fn new(config: AnthropicConfig) -> Result<Self, LLMError> {
+        let http = reqwest::Client::builder()
+            .user_agent("anytra/0.1")
+            .build()
+            .map_err(|e| LLMError::RequestFailed(e.to_string()))?;
+
+        Ok(Self { http, config, retry: RetryConfig::default() })
+    }
+
+    /// Replace the default retry policy
+    pub fn with_retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    fn endpoint(&self) -> String {
+        format!("{}/v1/messages", self.config.base_url.trim_end_matches('/'))
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct MessagesRequest<'a> {
+    model: &'a str,
+    max_tokens: u32,
+    system: &'a str,
+    messages: Vec<Message<'a>>,
+    temperature: f32,
+}
+
+#[derive(Debug, Serialize)]
+struct Message<'a> {
+    role: &'a str,
+    content: Vec<RequestContentBlock<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum RequestContentBlock<'a> {
+    Text { text: &'a str },
+}
+
+impl<'a> MessagesRequest<'a> {
+    fn new(model: &'a str, max_tokens: u32, request: &'a CompletionRequest) -> Self {
+        Self {
+            model,
+            max_tokens,
+            system: &request.system,
+            messages: vec![Message {
+                role: "user",
+                content: vec![RequestContentBlock::Text { text: &request.user }],
+            }],
+            temperature: request.temperature,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MessagesResponse {
+    content: Vec<ResponseContentBlock>,
+    #[serde(default)]
+    usage: Option<MessagesUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessagesUsage {
+    input_tokens: u64,
+    output_tokens: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseContentBlock {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    error: ErrorDetail,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorDetail {
+    #[serde(rename = "type")]
+    kind: String,
+    message: String,
+}
+
+impl MessagesResponse {
+    /// Concatenated text of all `text` content blocks
+    fn into_text(self) -> Result<String, LLMError> {
+        let text = self
+            .content
+            .into_iter()
+            .filter(|block| block.kind == "text")
+            .filter_map(|block| block.text)
+            .collect::<Vec<_>>()
+            .join("");
+
+        if text.trim().is_empty() {
+            return Err(LLMError::UnexpectedResponse("no text content".into()));
+        }
+        Ok(text.trim().to_string())
+    }
+}
+
+/// Map an Anthropic error body onto an `LLMError`
+fn map_error(status: StatusCode, retry_after: Option<Duration>, body: &str) -> LLMError {
+    let json: Option<Value> = serde_json::from_str(body).ok();
+    let Some(ErrorResponse { error }) = json.as_ref().and_then(|v| ErrorResponse::deserialize(v).ok()) else {
+        return LLMError::RequestFailed(format!("status {}", status));
+    };
+
+    let detail = ProviderError::new(status.as_u16(), format!("{}: {}", error.kind, error.message)).with_body(json);
+    match error.kind.as_str() {
+        "authentication_error" | "permission_error" => LLMError::Unauthorized(detail),
+        "rate_limit_error" => LLMError::RateLimited { retry_after, error: detail },
+        "not_found_error" => LLMError::ModelNotFound(detail),
+        "request_too_large" => LLMError::ContextLengthExceeded(detail),
+        "invalid_request_error" if error.message.contains("prompt is too long") => LLMError::ContextLengthExceeded(detail),
+        _ => LLMError::RequestFailed(detail.to_string()),
+    }
+}
+
+#[async_trait]
+impl LLMProvider for AnthropicClient {
+    async fn enhance(&self, prompt: Prompt, options: EnhancementOptions) -> Result<EnhancedPrompt, LLMError> {
+        self.complete(enhancement_request(&prompt, &options)).await
+    }
+
+    async fn complete(&self, request: CompletionRequest) -> Result<EnhancedPrompt, LLMError> {
+        let payload = MessagesRequest::new(&self.config.model, self.config.max_tokens, &request);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-api-key", HeaderValue::from_str(&self.config.api_key).map_err(|e| LLMError::RequestFailed(e.to_string()))?);
+        headers.insert("anthropic-version", HeaderValue::from_str(&self.config.version).map_err(|e| LLMError::RequestFailed(e.to_string()))?);
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+        let url = self.endpoint();
+        let resp = send_with_retry(&self.retry, || self.http.post(&url).headers(headers.clone()).json(&payload)).await?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let retry_after = retry_after(resp.headers());
+            let body = resp.text().await.unwrap_or_default();
+            return Err(map_error(status, retry_after, &body));
+        }
+
+        let mut parsed: MessagesResponse = resp.json().await.map_err(|e| LLMError::UnexpectedResponse(e.to_string()))?;
+        let usage = parsed.usage.take().map(|u| TokenUsage { input_tokens: u.input_tokens, output_tokens: u.output_tokens });
+        let text = parsed.into_text()?;
+
+        Ok(EnhancedPrompt { text, usage, ..Default::default() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::prompting::ENHANCEMENT_SYSTEM_PROMPT;
+    use crate::infrastructure::providers::openai_compatible::ChatRequest;
+    use serde_json::json;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn config_for(server: &MockServer) -> AnthropicConfig {
+        AnthropicConfig {
+            api_key: "test-key".to_string(),
+            model: "claude-test".to_string(),
+            base_url: server.uri(),
+            max_tokens: 512,
+            version: "2023-06-01".to_string(),
+        }
+    }
+
+    fn error_body(kind: &str, message: &str) -> serde_json::Value {
+        json!({ "type": "error", "error": { "type": kind, "message": message } })
+    }
+
+    #[tokio::test]
+    async fn test_enhance_sends_messages_request() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .and(header("x-api-key", "test-key"))
+            .and(header("anthropic-version", "2023-06-01"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "type": "message",
+                "role": "assistant",
+                "content": [
+                    { "type": "text", "text": "Improved " },
+                    { "type": "text", "text": "prompt" }
+                ],
+                "stop_reason": "end_turn",
+                "usage": { "input_tokens": 42, "output_tokens": 7 }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = AnthropicClient::new(config_for(&server)).unwrap();
+        let result = client
+            .enhance(Prompt { text: "plan a trip".into() }, EnhancementOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(result.text, "Improved prompt");
+        assert_eq!(result.usage, Some(TokenUsage { input_tokens: 42, output_tokens: 7 }));
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body["model"], "claude-test");
+        assert_eq!(body["max_tokens"], 512);
+        assert_eq!(body["system"], ENHANCEMENT_SYSTEM_PROMPT);
+        assert_eq!(body["messages"][0]["role"], "user");
+        assert_eq!(body["messages"][0]["content"][0]["type"], "text");
+        assert_eq!(body["messages"][0]["content"][0]["text"], "plan a trip");
+    }
+
+    #[test]
+    fn test_request_matches_openai_compatible_request() {
+        let prompt = Prompt { text: "write code for fibonacci".into() };
+        let options = EnhancementOptions { goal: Some("teach recursion".into()), ..Default::default() };
+        let request = enhancement_request(&prompt, &options);
+
+        let anthropic = serde_json::to_value(MessagesRequest::new("claude-test", 512, &request)).unwrap();
+        let openai = serde_json::to_value(ChatRequest::new("claude-test", &request)).unwrap();
+
+        assert_eq!(anthropic["system"], openai["messages"][0]["content"]);
+        assert_eq!(anthropic["messages"][0]["content"][0]["text"], openai["messages"][1]["content"]);
+        assert_eq!(anthropic["temperature"], openai["temperature"]);
+    }
+
+    #[tokio::test]
+    async fn test_enhance_authentication_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(error_body("authentication_error", "invalid x-api-key")))
+            .mount(&server)
+            .await;
+
+        let client = AnthropicClient::new(config_for(&server)).unwrap();
+        let result = client
+            .enhance(Prompt { text: "plan a trip".into() }, EnhancementOptions::default())
+            .await;
+
+        match result {
+            Err(LLMError::Unauthorized(error)) => {
+                assert_eq!(error.status, 401);
+                assert!(error.message.contains("invalid x-api-key"));
+            }
+            other => panic!("Expected Unauthorized error, got {:?}", other.map(|p| p.text)),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_enhance_overloaded_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(529).set_body_json(error_body("overloaded_error", "Overloaded")))
+            .mount(&server)
+            .await;
+
+        let retry = RetryConfig { max_attempts: 1, ..RetryConfig::default() };
+        let client = AnthropicClient::new(config_for(&server)).unwrap().with_retry(retry);
+        let result = client
+            .enhance(Prompt { text: "plan a trip".into() }, EnhancementOptions::default())
+            .await;
+
+        match result {
+            Err(LLMError::RequestFailed(msg)) => {
+                assert!(msg.contains("529"));
+                assert!(msg.contains("overloaded_error"));
+            }
+            other => panic!("Expected RequestFailed error, got {:?}", other.map(|p| p.text)),
+        }
+    }
+
+    #[test]
+    fn test_map_error_unparseable_body() {
+        let error = map_error(StatusCode::BAD_GATEWAY, None, "<html>bad gateway</html>");
+        assert!(matches!(error, LLMError::RequestFailed(msg) if msg.contains("502")));
+    }
+
+    #[test]
+    fn test_map_error_typed_kinds() {
+        let body = |kind: &str, message: &str| error_body(kind, message).to_string();
+
+        let error = map_error(StatusCode::TOO_MANY_REQUESTS, Some(Duration::from_secs(20)), &body("rate_limit_error", "Number of request tokens has exceeded your per-minute rate limit"));
+        assert!(matches!(error, LLMError::RateLimited { retry_after: Some(d), .. } if d == Duration::from_secs(20)));
+
+        let error = map_error(StatusCode::BAD_REQUEST, None, &body("invalid_request_error", "prompt is too long: 210000 tokens > 200000 maximum"));
+        assert!(matches!(error, LLMError::ContextLengthExceeded(_)));
+
+        let error = map_error(StatusCode::NOT_FOUND, None, &body("not_found_error", "model: claude-nope"));
+        assert!(matches!(error, LLMError::ModelNotFound(_)));
+
+        let error = map_error(StatusCode::BAD_REQUEST, None, &body("invalid_request_error", "max_tokens: field required"));
+        assert!(matches!(error, LLMError::RequestFailed(_)));
+    }
+
+    #[test]
+    fn test_into_text_without_text_blocks() {
+        let response = MessagesResponse {
+            content: vec![ResponseContentBlock { kind: "tool_use".into(), text: None }],
+            usage: None,
+        };
+        assert!(matches!(response.into_text(), Err(LLMError::UnexpectedResponse(_))));
+    }
+}
@@ -0,0 +1,334 @@
+use crate::domain::llm::{LLMError, LLMProvider, TokenSink};
+use crate::domain::models::{EnhancedPrompt, EnhancementOptions, Prompt};
+use crate::domain::prompting::CompletionRequest;
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tracing::warn;
+
+/// A provider with the name it is reported under, e.g. `openrouter:openai/gpt-4o-mini`;
+/// its results carry that name as their `backend`
+pub struct Backend {
+    pub label: String,
+    pub provider: Box<dyn LLMProvider + Send + Sync>,
+}
+
+#[async_trait]
+impl LLMProvider for Backend {
+    async fn enhance(&self, prompt: Prompt, options: EnhancementOptions) -> Result<EnhancedPrompt, LLMError> {
+        let mut enhanced = self.provider.enhance(prompt, options).await?;
+        enhanced.backend = Some(self.label.clone());
+        Ok(enhanced)
+    }
+
+    async fn enhance_streaming(&self, prompt: Prompt, options: EnhancementOptions, on_token: TokenSink<'_>) -> Result<EnhancedPrompt, LLMError> {
+        let mut enhanced = self.provider.enhance_streaming(prompt, options, on_token).await?;
+        enhanced.backend = Some(self.label.clone());
+        Ok(enhanced)
+    }
+
+    async fn complete(&self, request: CompletionRequest) -> Result<EnhancedPrompt, LLMError> {
+        let mut completed = self.provider.complete(request).await?;
+        completed.backend = Some(self.label.clone());
+        Ok(completed)
+    }
+
+    async fn complete_streaming(&self, request: CompletionRequest, on_token: TokenSink<'_>) -> Result<EnhancedPrompt, LLMError> {
+        let mut completed = self.provider.complete_streaming(request, on_token).await?;
+        completed.backend = Some(self.label.clone());
+        Ok(completed)
+    }
+}
+
+/// What is asked of each backend in turn
+#[derive(Clone)]
+enum Request {
+    Enhance(Prompt, EnhancementOptions),
+    Complete(CompletionRequest),
+}
+
+/// Tries an ordered list of backends, moving on to the next one when a
+/// backend fails to answer
+pub struct FallbackProvider {
+    backends: Vec<Backend>,
+    timeout: Option<Duration>,
+}
+
+impl FallbackProvider {
+    pub fn new(backends: Vec<Backend>, timeout: Option<Duration>) -> Self {
+        Self { backends, timeout }
+    }
+
+    /// Errors specific to one backend (availability, credentials, model, context size)
+    /// rather than to the request itself
+    fn should_failover(error: &LLMError) -> bool {
+        !matches!(error, LLMError::UnexpectedResponse(_) | LLMError::ContentFiltered(_))
+    }
+
+    async fn call(&self, backend: &Backend, request: Request, on_token: Option<TokenSink<'_>>) -> Result<EnhancedPrompt, LLMError> {
+        let call = async {
+            match (request, on_token) {
+                (Request::Enhance(prompt, options), Some(on_token)) => backend.enhance_streaming(prompt, options, on_token).await,
+                (Request::Enhance(prompt, options), None) => backend.enhance(prompt, options).await,
+                (Request::Complete(request), Some(on_token)) => backend.complete_streaming(request, on_token).await,
+                (Request::Complete(request), None) => backend.complete(request).await,
+            }
+        };
+        match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, call)
+                .await
+                .map_err(|_| LLMError::Timeout(format!("no response after {}s", timeout.as_secs_f32())))?,
+            None => call.await,
+        }
+    }
+
+    async fn run(&self, request: Request, on_token: Option<TokenSink<'_>>) -> Result<EnhancedPrompt, LLMError> {
+        let mut failures = Vec::new();
+        // Once a backend has streamed part of its answer, another backend's answer
+        // cannot be appended to it, so a failure from then on is final
+        let streamed = AtomicBool::new(false);
+        let forward = |token: &str| {
+            streamed.store(true, Ordering::Relaxed);
+            if let Some(on_token) = on_token {
+                on_token(token);
+            }
+        };
+        let sink: Option<TokenSink<'_>> = on_token.map(|_| &forward as TokenSink<'_>);
+
+        for backend in &self.backends {
+            match self.call(backend, request.clone(), sink).await {
+                Ok(enhanced) => return Ok(enhanced),
+                Err(e) if streamed.load(Ordering::Relaxed) => {
+                    warn!(backend = %backend.label, error = %e, "backend failed after streaming part of its answer, not failing over");
+                    return Err(e);
+                }
+                Err(e) if Self::should_failover(&e) => {
+                    warn!(backend = %backend.label, error = %e, "backend failed, trying next");
+                    failures.push(format!("{}: {}", backend.label, e));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(LLMError::RequestFailed(format!("all backends failed ({})", failures.join("; "))))
+    }
+}
+
+#[async_trait]
+impl LLMProvider for FallbackProvider {
+    async fn enhance(&self, prompt: Prompt, options: EnhancementOptions) -> Result<EnhancedPrompt, LLMError> {
+        self.run(Request::Enhance(prompt, options), None).await
+    }
+
+    async fn enhance_streaming(&self, prompt: Prompt, options: EnhancementOptions, on_token: TokenSink<'_>) -> Result<EnhancedPrompt, LLMError> {
+        self.run(Request::Enhance(prompt, options), Some(on_token)).await
+    }
+
+    async fn complete(&self, request: CompletionRequest) -> Result<EnhancedPrompt, LLMError> {
+        self.run(Request::Complete(request), None).await
+    }
+
+    async fn complete_streaming(&self, request: CompletionRequest, on_token: TokenSink<'_>) -> Result<EnhancedPrompt, LLMError> {
+        self.run(Request::Complete(request), Some(on_token)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::llm::ProviderError;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    struct ScriptedProvider {
+        result: fn() -> Result<EnhancedPrompt, LLMError>,
+        calls: Arc<AtomicUsize>,
+        delay: Option<Duration>,
+    }
+
+    #[async_trait]
+    impl LLMProvider for ScriptedProvider {
+        async fn enhance(&self, _prompt: Prompt, _options: EnhancementOptions) -> Result<EnhancedPrompt, LLMError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if let Some(delay) = self.delay {
+                tokio::time::sleep(delay).await;
+            }
+            (self.result)()
+        }
+    }
+
+    fn backend(label: &str, result: fn() -> Result<EnhancedPrompt, LLMError>) -> (Backend, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider = ScriptedProvider { result, calls: calls.clone(), delay: None };
+        (Backend { label: label.to_string(), provider: Box::new(provider) }, calls)
+    }
+
+    fn ok() -> Result<EnhancedPrompt, LLMError> {
+        Ok(EnhancedPrompt { text: "answer".into(), ..Default::default() })
+    }
+
+    fn down() -> Result<EnhancedPrompt, LLMError> {
+        Err(LLMError::RequestFailed("status 503 Service Unavailable".into()))
+    }
+
+    fn rate_limited() -> Result<EnhancedPrompt, LLMError> {
+        Err(LLMError::RateLimited { retry_after: None, error: ProviderError::new(429, "Rate limit exceeded") })
+    }
+
+    fn filtered() -> Result<EnhancedPrompt, LLMError> {
+        Err(LLMError::ContentFiltered(ProviderError::new(403, "Input was flagged")))
+    }
+
+    fn malformed() -> Result<EnhancedPrompt, LLMError> {
+        Err(LLMError::UnexpectedResponse("no choices".into()))
+    }
+
+    fn prompt() -> Prompt {
+        Prompt { text: "plan a trip".into() }
+    }
+
+    #[tokio::test]
+    async fn test_first_backend_answers() {
+        let (a, a_calls) = backend("openrouter:model-a", ok);
+        let (b, b_calls) = backend("openrouter:model-b", ok);
+        let chain = FallbackProvider::new(vec![a, b], None);
+
+        let result = chain.enhance(prompt(), EnhancementOptions::default()).await.unwrap();
+
+        assert_eq!(result.backend.as_deref(), Some("openrouter:model-a"));
+        assert_eq!(a_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(b_calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_fails_over_in_order() {
+        let (a, _) = backend("openrouter:model-a", down);
+        let (b, _) = backend("openrouter:model-b", down);
+        let (c, c_calls) = backend("ollama:llama3.2", ok);
+        let chain = FallbackProvider::new(vec![a, b, c], None);
+
+        let result = chain.enhance(prompt(), EnhancementOptions::default()).await.unwrap();
+
+        assert_eq!(result.backend.as_deref(), Some("ollama:llama3.2"));
+        assert_eq!(c_calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_does_not_fail_over_on_unexpected_response() {
+        let (a, _) = backend("openrouter:model-a", malformed);
+        let (b, b_calls) = backend("ollama:llama3.2", ok);
+        let chain = FallbackProvider::new(vec![a, b], None);
+
+        let result = chain.enhance(prompt(), EnhancementOptions::default()).await;
+
+        assert!(matches!(result, Err(LLMError::UnexpectedResponse(_))));
+        assert_eq!(b_calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_fails_over_on_rate_limit_but_not_content_filter() {
+        let (a, _) = backend("openrouter:model-a", rate_limited);
+        let (b, b_calls) = backend("ollama:llama3.2", ok);
+        let chain = FallbackProvider::new(vec![a, b], None);
+        let result = chain.enhance(prompt(), EnhancementOptions::default()).await.unwrap();
+        assert_eq!(result.backend.as_deref(), Some("ollama:llama3.2"));
+        assert_eq!(b_calls.load(Ordering::SeqCst), 1);
+
+        let (a, _) = backend("openrouter:model-a", filtered);
+        let (b, b_calls) = backend("ollama:llama3.2", ok);
+        let chain = FallbackProvider::new(vec![a, b], None);
+        let result = chain.enhance(prompt(), EnhancementOptions::default()).await;
+        assert!(matches!(result, Err(LLMError::ContentFiltered(_))));
+        assert_eq!(b_calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_all_backends_failed() {
+        let (a, _) = backend("openrouter:model-a", down);
+        let (b, _) = backend("ollama:llama3.2", down);
+        let chain = FallbackProvider::new(vec![a, b], None);
+
+        let result = chain.enhance(prompt(), EnhancementOptions::default()).await;
+
+        match result {
+            Err(LLMError::RequestFailed(msg)) => {
+                assert!(msg.contains("openrouter:model-a"));
+                assert!(msg.contains("ollama:llama3.2"));
+            }
+            other => panic!("Expected RequestFailed error, got {:?}", other.map(|p| p.text)),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streaming_uses_answering_backend() {
+        let (a, _) = backend("openrouter:model-a", down);
+        let (b, _) = backend("ollama:llama3.2", ok);
+        let chain = FallbackProvider::new(vec![a, b], None);
+
+        let tokens = std::sync::Mutex::new(Vec::new());
+        let on_token = |token: &str| tokens.lock().unwrap().push(token.to_string());
+        let result = chain.enhance_streaming(prompt(), EnhancementOptions::default(), &on_token).await.unwrap();
+
+        assert_eq!(result.backend.as_deref(), Some("ollama:llama3.2"));
+        assert_eq!(*tokens.lock().unwrap(), vec!["answer"]);
+    }
+
+    /// Streams one token, then fails
+    struct BrokenStream;
+
+    #[async_trait]
+    impl LLMProvider for BrokenStream {
+        async fn enhance(&self, _prompt: Prompt, _options: EnhancementOptions) -> Result<EnhancedPrompt, LLMError> {
+            down()
+        }
+
+        async fn enhance_streaming(&self, _prompt: Prompt, _options: EnhancementOptions, on_token: TokenSink<'_>) -> Result<EnhancedPrompt, LLMError> {
+            on_token("partial");
+            down()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_no_failover_after_streaming_started() {
+        let (b, b_calls) = backend("ollama:llama3.2", ok);
+        let chain = FallbackProvider::new(vec![Backend { label: "openrouter:model-a".into(), provider: Box::new(BrokenStream) }, b], None);
+
+        let tokens = std::sync::Mutex::new(Vec::new());
+        let on_token = |token: &str| tokens.lock().unwrap().push(token.to_string());
+        let error = chain.enhance_streaming(prompt(), EnhancementOptions::default(), &on_token).await.unwrap_err();
+
+        assert!(matches!(error, LLMError::RequestFailed(_)));
+        assert_eq!(*tokens.lock().unwrap(), vec!["partial"]);
+        assert_eq!(b_calls.load(Ordering::SeqCst), 0);
+        // Without streaming nothing was shown yet, so the chain still fails over
+        let result = chain.enhance(prompt(), EnhancementOptions::default()).await.unwrap();
+        assert_eq!(result.backend.as_deref(), Some("ollama:llama3.2"));
+    }
+
+    #[tokio::test]
+    async fn test_complete_fails_over_and_labels_result() {
+        let (a, a_calls) = backend("openrouter:model-a", down);
+        let (b, _) = backend("ollama:llama3.2", ok);
+        let chain = FallbackProvider::new(vec![a, b], None);
+
+        let request = CompletionRequest { system: "Review".into(), user: "Draft".into(), temperature: 0.0 };
+        let result = chain.complete(request).await.unwrap();
+
+        assert_eq!(a_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(result.backend.as_deref(), Some("ollama:llama3.2"));
+    }
+
+    #[tokio::test]
+    async fn test_fails_over_on_timeout() {
+        let slow = ScriptedProvider { result: ok, calls: Arc::new(AtomicUsize::new(0)), delay: Some(Duration::from_secs(5)) };
+        let (fast, _) = backend("ollama:llama3.2", ok);
+        let chain = FallbackProvider::new(
+            vec![Backend { label: "openrouter:slow".into(), provider: Box::new(slow) }, fast],
+            Some(Duration::from_millis(50)),
+        );
+
+        let result = chain.enhance(prompt(), EnhancementOptions::default()).await.unwrap();
+
+        assert_eq!(result.backend.as_deref(), Some("ollama:llama3.2"));
+    }
+}
@@ -0,0 +1,94 @@
+/// Splits a streamed response body into lines. Network chunks can end in the
+/// middle of a UTF-8 character, so bytes are kept until a whole line is in and
+/// only then decoded.
+#[derive(Debug, Default)]
+pub(crate) struct LineBuffer {
+    bytes: Vec<u8>,
+}
+
+impl LineBuffer {
+    pub(crate) fn push(&mut self, chunk: &[u8]) {
+        self.bytes.extend_from_slice(chunk);
+    }
+
+    /// The next complete line, without its `\n`
+    pub(crate) fn next_line(&mut self) -> Option<String> {
+        let end = self.bytes.iter().position(|&b| b == b'\n')?;
+        let line: Vec<u8> = self.bytes.drain(..=end).collect();
+        Some(String::from_utf8_lossy(&line[..end]).into_owned())
+    }
+
+    /// Whatever follows the last `\n`, for bodies that do not end with one
+    pub(crate) fn rest(&mut self) -> String {
+        String::from_utf8_lossy(&std::mem::take(&mut self.bytes)).into_owned()
+    }
+}
+
+/// Serve a single HTTP response whose body is sent in `pieces`, each flushed on
+/// its own, so the client reads them as separate chunks. Returns the base URL.
+#[cfg(test)]
+pub(crate) async fn serve_in_pieces(content_type: &'static str, pieces: Vec<Vec<u8>>) -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        // Read the whole request before answering
+        let mut request = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = socket.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&request).to_lowercase();
+            if let Some(end) = text.find("\r\n\r\n") {
+                let length = text.lines().find_map(|line| line.strip_prefix("content-length:")).map_or(0, |len| len.trim().parse().unwrap());
+                if request.len() >= end + 4 + length {
+                    break;
+                }
+            }
+        }
+
+        let head = format!("HTTP/1.1 200 OK\r\ncontent-type: {}\r\ntransfer-encoding: chunked\r\n\r\n", content_type);
+        socket.write_all(head.as_bytes()).await.unwrap();
+        for piece in pieces {
+            socket.write_all(format!("{:x}\r\n", piece.len()).as_bytes()).await.unwrap();
+            socket.write_all(&piece).await.unwrap();
+            socket.write_all(b"\r\n").await.unwrap();
+            socket.flush().await.unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        socket.write_all(b"0\r\n\r\n").await.unwrap();
+    });
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_character_split_across_chunks() {
+        let line = "{\"content\": \"café\"}\n".as_bytes();
+        let split = line.iter().position(|&b| b == 0xc3).unwrap() + 1;
+        let mut buffer = LineBuffer::default();
+
+        buffer.push(&line[..split]);
+        assert_eq!(buffer.next_line(), None);
+        buffer.push(&line[split..]);
+
+        assert_eq!(buffer.next_line().as_deref(), Some("{\"content\": \"café\"}"));
+        assert_eq!(buffer.next_line(), None);
+    }
+
+    #[test]
+    fn test_rest_is_what_follows_the_last_newline() {
+        let mut buffer = LineBuffer::default();
+        buffer.push(b"one\ntwo\nthr");
+
+        assert_eq!(buffer.next_line().as_deref(), Some("one"));
+        assert_eq!(buffer.next_line().as_deref(), Some("two"));
+        assert_eq!(buffer.rest(), "thr");
+        assert_eq!(buffer.rest(), "");
+    }
+}
@@ -1 +1,115 @@
 pub mod openrouter;
+pub mod openai_compatible;
+pub mod anthropic;
+pub mod ollama;
+pub mod fallback;
+pub mod retry;
+mod lines;
+
+use crate::domain::llm::{LLMError, LLMProvider};
+use crate::infrastructure::config::{Config, ProviderKind, ProviderTarget};
+use anthropic::AnthropicClient;
+use fallback::{Backend, FallbackProvider};
+use ollama::OllamaClient;
+use openai_compatible::OpenAICompatibleClient;
+use openrouter::OpenRouterClient;
+use tracing::info;
+
+/// Instantiate the LLM backend (or failover chain) described by the configuration
+pub fn build_provider(config: &Config) -> Result<Box<dyn LLMProvider + Send + Sync>, LLMError> {
+    if config.fallback.chain.is_empty() {
+        let target = ProviderTarget { kind: config.provider, model: None };
+        let backend = build_backend(config, &target)?;
+        info!(provider = %backend.label, "using LLM provider");
+        return Ok(Box::new(backend));
+    }
+
+    let backends = config
+        .fallback
+        .chain
+        .iter()
+        .map(|target| build_backend(config, target))
+        .collect::<Result<Vec<_>, _>>()?;
+    info!(chain = ?backends.iter().map(|b| b.label.as_str()).collect::<Vec<_>>(), "using LLM fallback chain");
+    Ok(Box::new(FallbackProvider::new(backends, config.fallback.timeout)))
+}
+
+/// Build a single backend, applying the target's model override
+fn build_backend(config: &Config, target: &ProviderTarget) -> Result<Backend, LLMError> {
+    let missing = || LLMError::NotConfigured(target.kind.to_string());
+
+    let (model, provider): (String, Box<dyn LLMProvider + Send + Sync>) = match target.kind {
+        ProviderKind::OpenRouter => {
+            let mut c = config.openrouter.clone().ok_or_else(missing)?;
+            if let Some(model) = &target.model { c.model = model.clone(); }
+            (c.model.clone(), Box::new(OpenRouterClient::new(c)?.with_retry(config.retry.clone())))
+        }
+        ProviderKind::OpenAICompatible => {
+            let mut c = config.openai_compatible.clone().ok_or_else(missing)?;
+            if let Some(model) = &target.model { c.model = model.clone(); }
+            (c.model.clone(), Box::new(OpenAICompatibleClient::new(c)?.with_retry(config.retry.clone())))
+        }
+        ProviderKind::Anthropic => {
+            let mut c = config.anthropic.clone().ok_or_else(missing)?;
+            if let Some(model) = &target.model { c.model = model.clone(); }
+            (c.model.clone(), Box::new(AnthropicClient::new(c)?.with_retry(config.retry.clone())))
+        }
+        ProviderKind::Ollama => {
+            let mut c = config.ollama.clone().ok_or_else(missing)?;
+            if let Some(model) = &target.model { c.model = model.clone(); }
+            (c.model.clone(), Box::new(OllamaClient::new(c)?.with_retry(config.retry.clone())))
+        }
+    };
+
+    Ok(Backend { label: format!("{}:{}", target.kind, model), provider })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::config::{create_test_config, FallbackConfig, OllamaConfig};
+
+    fn config_with_chain(chain: Vec<ProviderTarget>) -> Config {
+        Config {
+            ollama: Some(OllamaConfig {
+                host: "http://localhost:11434".to_string(),
+                model: "llama3.2".to_string(),
+                num_ctx: None,
+                temperature: None,
+                stream: false,
+            }),
+            fallback: FallbackConfig { chain, timeout: None },
+            ..create_test_config()
+        }
+    }
+
+    #[test]
+    fn test_build_backend_applies_model_override() {
+        let config = config_with_chain(Vec::new());
+
+        let backend = build_backend(&config, &ProviderTarget { kind: ProviderKind::OpenRouter, model: Some("model-a".into()) }).unwrap();
+        assert_eq!(backend.label, "openrouter:model-a");
+
+        let backend = build_backend(&config, &ProviderTarget { kind: ProviderKind::Ollama, model: None }).unwrap();
+        assert_eq!(backend.label, "ollama:llama3.2");
+    }
+
+    #[test]
+    fn test_build_backend_missing_settings() {
+        let config = config_with_chain(Vec::new());
+
+        let result = build_backend(&config, &ProviderTarget { kind: ProviderKind::Anthropic, model: None });
+        assert!(matches!(result, Err(LLMError::NotConfigured(name)) if name == "anthropic"));
+    }
+
+    #[test]
+    fn test_build_provider_with_chain() {
+        let config = config_with_chain(vec![
+            ProviderTarget { kind: ProviderKind::OpenRouter, model: Some("model-a".into()) },
+            ProviderTarget { kind: ProviderKind::OpenRouter, model: Some("model-b".into()) },
+            ProviderTarget { kind: ProviderKind::Ollama, model: None },
+        ]);
+
+        assert!(build_provider(&config).is_ok());
+    }
+}
@@ -0,0 +1,419 @@
+use crate::domain::llm::{LLMError, LLMProvider, ProviderError, TokenSink};
+use crate::domain::models::{EnhancedPrompt, EnhancementOptions, Prompt, TokenUsage};
+use crate::domain::prompting::{enhancement_request, CompletionRequest};
+use crate::infrastructure::config::{OllamaConfig, RetryConfig};
+use crate::infrastructure::providers::lines::LineBuffer;
+use crate::infrastructure::providers::retry::{retry_after, send_with_retry};
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Client for a local Ollama server's `/api/chat` endpoint
+pub struct OllamaClient {
+    http: reqwest::Client,
+    config: OllamaConfig,
+    retry: RetryConfig,
+}
+
+impl OllamaClient {
+    pub fn new(config: OllamaConfig) -> Result<Self, LLMError> {
+        let http = reqwest::Client::builder()
+            .user_agent("anytra/0.1")
+            .build()
+            .map_err(|e| LLMError::RequestFailed(e.to_string()))?;
+
+        Ok(Self { http, config, retry: RetryConfig::default() })
+    }
+
+    /// Replace the default retry policy
+    pub fn with_retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    fn endpoint(&self) -> String {
+        format!("{}/api/chat", self.config.host.trim_end_matches('/'))
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<ChatMessage<'a>>,
+    stream: bool,
+    options: ModelOptions,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatMessage<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct ModelOptions {
+    temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_ctx: Option<u32>,
+}
+
+impl<'a> ChatRequest<'a> {
+    fn new(config: &'a OllamaConfig, request: &'a CompletionRequest) -> Self {
+        Self {
+            model: &config.model,
+            messages: vec![
+                ChatMessage { role: "system", content: &request.system },
+                ChatMessage { role: "user", content: &request.user },
+            ],
+            stream: config.stream,
+            options: ModelOptions {
+                temperature: config.temperature.unwrap_or(request.temperature),
+                num_ctx: config.num_ctx,
+            },
+        }
+    }
+}
+
+/// A complete response, or a single line of a streamed (NDJSON) response
+#[derive(Debug, Deserialize)]
+struct ChatChunk {
+    #[serde(default)]
+    message: Option<ChunkMessage>,
+    #[serde(default)]
+    done: bool,
+    #[serde(default)]
+    error: Option<String>,
+    /// prompt tokens, reported on the final chunk
+    #[serde(default)]
+    prompt_eval_count: Option<u64>,
+    /// generated tokens, reported on the final chunk
+    #[serde(default)]
+    eval_count: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkMessage {
+    #[serde(default)]
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+impl ChatChunk {
+    fn parse(line: &str) -> Result<Self, LLMError> {
+        let chunk: ChatChunk = serde_json::from_str(line).map_err(|e| LLMError::UnexpectedResponse(e.to_string()))?;
+        match chunk.error {
+            Some(error) => Err(LLMError::RequestFailed(error)),
+            None => Ok(chunk),
+        }
+    }
+
+    fn content(&self) -> &str {
+        self.message.as_ref().map(|m| m.content.as_str()).unwrap_or("")
+    }
+
+    fn usage(&self) -> Option<TokenUsage> {
+        match (self.prompt_eval_count, self.eval_count) {
+            (None, None) => None,
+            (input, output) => Some(TokenUsage { input_tokens: input.unwrap_or(0), output_tokens: output.unwrap_or(0) }),
+        }
+    }
+}
+
+/// Map an Ollama error response onto an `LLMError`
+fn map_error(status: StatusCode, retry_after: Option<Duration>, body: &str) -> LLMError {
+    let Ok(ErrorResponse { error }) = serde_json::from_str::<ErrorResponse>(body) else {
+        return LLMError::RequestFailed(format!("status {}", status));
+    };
+
+    let detail = ProviderError::new(status.as_u16(), error).with_body(serde_json::from_str(body).ok());
+    match status.as_u16() {
+        401 | 403 => LLMError::Unauthorized(detail),
+        404 => LLMError::ModelNotFound(detail),
+        429 => LLMError::RateLimited { retry_after, error: detail },
+        _ => LLMError::RequestFailed(detail.to_string()),
+    }
+}
+
+/// Accumulate the content of a streamed NDJSON chat response, reporting each chunk,
+/// with the usage of the final chunk
+async fn read_stream(mut resp: reqwest::Response, on_token: TokenSink<'_>) -> Result<(String, Option<TokenUsage>), LLMError> {
+    let mut buffer = LineBuffer::default();
+    let mut text = String::new();
+
+    while let Some(bytes) = resp.chunk().await.map_err(|e| LLMError::RequestFailed(e.to_string()))? {
+        buffer.push(&bytes);
+        while let Some(line) = buffer.next_line() {
+            if line.trim().is_empty() { continue; }
+            let chunk = ChatChunk::parse(line.trim())?;
+            if !chunk.content().is_empty() {
+                on_token(chunk.content());
+            }
+            text.push_str(chunk.content());
+            if chunk.done {
+                return Ok((text, chunk.usage()));
+            }
+        }
+    }
+
+    let rest = buffer.rest();
+    if !rest.trim().is_empty() {
+        let chunk = ChatChunk::parse(rest.trim())?;
+        if !chunk.content().is_empty() {
+            on_token(chunk.content());
+        }
+        text.push_str(chunk.content());
+        if chunk.done {
+            return Ok((text, chunk.usage()));
+        }
+    }
+
+    Err(LLMError::UnexpectedResponse("stream ended before done".into()))
+}
+
+impl OllamaClient {
+    /// Run one chat request; the response is streamed when configured or when
+    /// the caller wants tokens as they arrive
+    async fn chat(&self, request: &CompletionRequest, on_token: Option<TokenSink<'_>>) -> Result<EnhancedPrompt, LLMError> {
+        let mut payload = ChatRequest::new(&self.config, request);
+        payload.stream |= on_token.is_some();
+
+        let url = self.endpoint();
+        let resp = send_with_retry(&self.retry, || self.http.post(&url).json(&payload)).await?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let retry_after = retry_after(resp.headers());
+            let body = resp.text().await.unwrap_or_default();
+            return Err(map_error(status, retry_after, &body));
+        }
+
+        let (text, usage) = if payload.stream {
+            read_stream(resp, on_token.unwrap_or(&|_| {})).await?
+        } else {
+            let body = resp.text().await.map_err(|e| LLMError::UnexpectedResponse(e.to_string()))?;
+            let chunk = ChatChunk::parse(&body)?;
+            (chunk.content().to_string(), chunk.usage())
+        };
+
+        let text = text.trim().to_string();
+        if text.is_empty() {
+            return Err(LLMError::UnexpectedResponse("empty message".into()));
+        }
+
+        Ok(EnhancedPrompt { text, usage, ..Default::default() })
+    }
+}
+
+#[async_trait]
+impl LLMProvider for OllamaClient {
+    async fn enhance(&self, prompt: Prompt, options: EnhancementOptions) -> Result<EnhancedPrompt, LLMError> {
+        self.chat(&enhancement_request(&prompt, &options), None).await
+    }
+
+    async fn enhance_streaming(&self, prompt: Prompt, options: EnhancementOptions, on_token: TokenSink<'_>) -> Result<EnhancedPrompt, LLMError> {
+        self.chat(&enhancement_request(&prompt, &options), Some(on_token)).await
+    }
+
+    async fn complete(&self, request: CompletionRequest) -> Result<EnhancedPrompt, LLMError> {
+        self.chat(&request, None).await
+    }
+
+    async fn complete_streaming(&self, request: CompletionRequest, on_token: TokenSink<'_>) -> Result<EnhancedPrompt, LLMError> {
+        self.chat(&request, Some(on_token)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::providers::lines::serve_in_pieces;
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn config_for(server: &MockServer, stream: bool) -> OllamaConfig {
+        OllamaConfig {
+            host: server.uri(),
+            model: "llama3.2".to_string(),
+            num_ctx: Some(8192),
+            temperature: None,
+            stream,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_enhance_non_streaming() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/chat"))
+            .and(body_partial_json(json!({
+                "model": "llama3.2",
+                "stream": false,
+                "options": { "num_ctx": 8192, "temperature": 0.2 }
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "model": "llama3.2",
+                "message": { "role": "assistant", "content": " Improved prompt " },
+                "done": true,
+                "prompt_eval_count": 26,
+                "eval_count": 9
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::new(config_for(&server, false)).unwrap();
+        let result = client
+            .enhance(Prompt { text: "plan a trip".into() }, EnhancementOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(result.text, "Improved prompt");
+        assert_eq!(result.usage, Some(TokenUsage { input_tokens: 26, output_tokens: 9 }));
+    }
+
+    #[tokio::test]
+    async fn test_enhance_streaming() {
+        let server = MockServer::start().await;
+        let body = [
+            json!({ "message": { "role": "assistant", "content": "Improved" }, "done": false }),
+            json!({ "message": { "role": "assistant", "content": " streamed" }, "done": false }),
+            json!({ "message": { "role": "assistant", "content": " prompt" }, "done": false }),
+            json!({ "message": { "role": "assistant", "content": "" }, "done": true, "prompt_eval_count": 26, "eval_count": 3 }),
+        ]
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+        Mock::given(method("POST"))
+            .and(path("/api/chat"))
+            .and(body_partial_json(json!({ "stream": true })))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(body, "application/x-ndjson"))
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::new(config_for(&server, true)).unwrap();
+        let result = client
+            .enhance(Prompt { text: "plan a trip".into() }, EnhancementOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(result.text, "Improved streamed prompt");
+        assert_eq!(result.usage, Some(TokenUsage { input_tokens: 26, output_tokens: 3 }));
+    }
+
+    #[tokio::test]
+    async fn test_enhance_streaming_forces_stream() {
+        let server = MockServer::start().await;
+        let body = format!(
+            "{}\n{}\n{}\n",
+            json!({ "message": { "content": "Improved" }, "done": false }),
+            json!({ "message": { "content": " prompt" }, "done": false }),
+            json!({ "message": { "content": "" }, "done": true })
+        );
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "stream": true })))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(body, "application/x-ndjson"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::new(config_for(&server, false)).unwrap();
+        let tokens = std::sync::Mutex::new(Vec::new());
+        let on_token = |token: &str| tokens.lock().unwrap().push(token.to_string());
+        let result = client
+            .enhance_streaming(Prompt { text: "plan a trip".into() }, EnhancementOptions::default(), &on_token)
+            .await
+            .unwrap();
+
+        assert_eq!(result.text, "Improved prompt");
+        assert_eq!(*tokens.lock().unwrap(), vec!["Improved", " prompt"]);
+    }
+
+    #[tokio::test]
+    async fn test_streamed_character_split_across_chunks() {
+        let body = format!(
+            "{}\n{}\n",
+            json!({ "message": { "content": "Plan a café crawl" }, "done": false }),
+            json!({ "message": { "content": "" }, "done": true })
+        )
+        .into_bytes();
+        let split = body.iter().position(|&b| b == 0xc3).unwrap() + 1;
+        let host = serve_in_pieces("application/x-ndjson", vec![body[..split].to_vec(), body[split..].to_vec()]).await;
+
+        let client = OllamaClient::new(OllamaConfig { host, model: "llama3.2".to_string(), num_ctx: None, temperature: None, stream: true }).unwrap();
+        let tokens = std::sync::Mutex::new(Vec::new());
+        let on_token = |token: &str| tokens.lock().unwrap().push(token.to_string());
+        let result = client
+            .enhance_streaming(Prompt { text: "plan a trip".into() }, EnhancementOptions::default(), &on_token)
+            .await
+            .unwrap();
+
+        assert_eq!(result.text, "Plan a café crawl");
+        assert_eq!(*tokens.lock().unwrap(), vec!["Plan a café crawl"]);
+    }
+
+    #[tokio::test]
+    async fn test_enhance_stream_error_line() {
+        let server = MockServer::start().await;
+        let body = format!(
+            "{}\n{}\n",
+            json!({ "message": { "content": "partial" }, "done": false }),
+            json!({ "error": "out of memory" })
+        );
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(body, "application/x-ndjson"))
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::new(config_for(&server, true)).unwrap();
+        let result = client
+            .enhance(Prompt { text: "plan a trip".into() }, EnhancementOptions::default())
+            .await;
+
+        assert!(matches!(result, Err(LLMError::RequestFailed(msg)) if msg == "out of memory"));
+    }
+
+    #[tokio::test]
+    async fn test_enhance_model_not_found() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(json!({ "error": "model \"llama3.2\" not found, try pulling it first" })))
+            .mount(&server)
+            .await;
+
+        let client = OllamaClient::new(config_for(&server, false)).unwrap();
+        let result = client
+            .enhance(Prompt { text: "plan a trip".into() }, EnhancementOptions::default())
+            .await;
+
+        match result {
+            Err(LLMError::ModelNotFound(error)) => {
+                assert_eq!(error.status, 404);
+                assert!(error.message.contains("not found"));
+            }
+            other => panic!("Expected ModelNotFound error, got {:?}", other.map(|p| p.text)),
+        }
+    }
+
+    #[test]
+    fn test_configured_temperature_overrides_request() {
+        let config = OllamaConfig {
+            host: "http://localhost:11434".into(),
+            model: "llama3.2".into(),
+            num_ctx: None,
+            temperature: Some(0.7),
+            stream: false,
+        };
+        let request = enhancement_request(&Prompt { text: "plan a trip".into() }, &EnhancementOptions::default());
+        let body = serde_json::to_value(ChatRequest::new(&config, &request)).unwrap();
+
+        assert_eq!(body["options"]["temperature"].as_f64().unwrap() as f32, 0.7);
+        assert!(body["options"].get("num_ctx").is_none());
+    }
+}
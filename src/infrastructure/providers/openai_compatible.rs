@@ -0,0 +1,637 @@
+use crate::domain::llm::{LLMError, LLMProvider, ProviderError, TokenSink};
+use crate::domain::models::{EnhancedPrompt, EnhancementOptions, Prompt, TokenUsage};
+use crate::domain::prompting::{enhancement_request, CompletionRequest};
+use crate::infrastructure::config::{OpenAICompatibleConfig, RetryConfig};
+use crate::infrastructure::providers::lines::LineBuffer;
+use crate::infrastructure::providers::retry::{retry_after, send_with_retry};
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+
+/// Client for any server speaking the OpenAI chat-completions wire format
+/// (vLLM, LiteLLM, llama.cpp server, ...)
+pub struct OpenAICompatibleClient {
+    http: reqwest::Client,
+    config: OpenAICompatibleConfig,
+    retry: RetryConfig,
+}
+
+impl OpenAICompatibleClient {
+    pub fn new(config: OpenAICompatibleConfig) -> Result<Self, LLMError> {
+        let http = reqwest::Client::builder()
+            .user_agent("anytra/0.1")
+            .build()
+            .map_err(|e| LLMError::RequestFailed(e.to_string()))?;
+
+        Ok(Self { http, config, retry: RetryConfig::default() })
+    }
+
+    /// Replace the default retry policy
+    pub fn with_retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    fn endpoint(&self) -> String {
+        format!("{}/chat/completions", self.config.base_url.trim_end_matches('/'))
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<ChatMessage<'a>>,
+    temperature: f32,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
+}
+
+#[derive(Debug, Serialize)]
+struct StreamOptions {
+    /// end the stream with a chunk carrying the token usage
+    include_usage: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatMessage<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+impl<'a> ChatRequest<'a> {
+    pub fn new(model: &'a str, request: &'a CompletionRequest) -> Self {
+        Self {
+            model,
+            messages: vec![
+                ChatMessage { role: "system", content: &request.system },
+                ChatMessage { role: "user", content: &request.user },
+            ],
+            temperature: request.temperature,
+            stream: false,
+            stream_options: None,
+        }
+    }
+
+    /// Ask for the response as server-sent events
+    pub fn streaming(mut self) -> Self {
+        self.stream = true;
+        self.stream_options = Some(StreamOptions { include_usage: true });
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ChatResponse {
+    choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<ChatUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatUsage {
+    prompt_tokens: u64,
+    completion_tokens: u64,
+}
+
+impl From<ChatUsage> for TokenUsage {
+    fn from(usage: ChatUsage) -> Self {
+        TokenUsage { input_tokens: usage.prompt_tokens, output_tokens: usage.completion_tokens }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Choice {
+    message: ChoiceMessage,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChoiceMessage {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+impl ChatResponse {
+    /// Trimmed content of the first choice
+    pub fn into_text(self) -> Result<String, LLMError> {
+        let choice = self.choices.first().ok_or_else(|| LLMError::UnexpectedResponse("no choices".into()))?;
+        if choice.finish_reason.as_deref() == Some("content_filter") {
+            return Err(LLMError::ContentFiltered(ProviderError::new(200, "completion stopped by content filter")));
+        }
+        Ok(choice.message.content.as_deref().unwrap_or("").trim().to_string())
+    }
+
+    /// The first choice as an enhancement, with the usage the server reported
+    pub fn into_enhanced(mut self) -> Result<EnhancedPrompt, LLMError> {
+        let usage = self.usage.take().map(TokenUsage::from);
+        Ok(EnhancedPrompt { text: self.into_text()?, usage, ..Default::default() })
+    }
+}
+
+/// One `data:` event of a streamed chat completion
+#[derive(Debug, Deserialize)]
+struct StreamChunk {
+    #[serde(default)]
+    choices: Vec<StreamChoice>,
+    #[serde(default)]
+    error: Option<ErrorBody>,
+    /// only on the last chunk, and only when the server reports usage
+    #[serde(default)]
+    usage: Option<ChatUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamChoice {
+    #[serde(default)]
+    delta: Option<ChoiceMessage>,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+/// Accumulate a streamed (SSE) chat completion, reporting each content delta
+pub(crate) async fn read_sse(mut resp: reqwest::Response, on_token: TokenSink<'_>) -> Result<EnhancedPrompt, LLMError> {
+    let mut buffer = LineBuffer::default();
+    let mut text = String::new();
+    let mut usage = None;
+
+    while let Some(bytes) = resp.chunk().await.map_err(|e| LLMError::RequestFailed(e.to_string()))? {
+        buffer.push(&bytes);
+        while let Some(line) = buffer.next_line() {
+            // Blank lines separate events and lines starting with ':' are keep-alive comments
+            let Some(data) = line.trim().strip_prefix("data:") else { continue };
+            let data = data.trim();
+            if data == "[DONE]" {
+                return Ok(EnhancedPrompt { text: text.trim().to_string(), usage, ..Default::default() });
+            }
+
+            let chunk: StreamChunk = serde_json::from_str(data).map_err(|e| LLMError::UnexpectedResponse(e.to_string()))?;
+            if let Some(error) = chunk.error {
+                return Err(LLMError::RequestFailed(format!("stream error: {}", error.message)));
+            }
+            if let Some(chunk_usage) = chunk.usage {
+                usage = Some(TokenUsage::from(chunk_usage));
+            }
+            for choice in chunk.choices {
+                if choice.finish_reason.as_deref() == Some("content_filter") {
+                    return Err(LLMError::ContentFiltered(ProviderError::new(200, "completion stopped by content filter")));
+                }
+                if let Some(content) = choice.delta.and_then(|d| d.content).filter(|c| !c.is_empty()) {
+                    on_token(&content);
+                    text.push_str(&content);
+                }
+            }
+        }
+    }
+
+    Err(LLMError::UnexpectedResponse("stream ended before [DONE]".into()))
+}
+
+/// Error body used by OpenAI and OpenRouter; `code` is a string for the former
+/// and the HTTP status for the latter
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    error: ErrorBody,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorBody {
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    code: Option<Value>,
+    #[serde(default)]
+    metadata: Option<Value>,
+}
+
+/// Map an error response in the OpenAI / OpenRouter format onto a typed `LLMError`
+pub(crate) fn map_error(status: StatusCode, retry_after: Option<Duration>, body: &str) -> LLMError {
+    let json: Option<Value> = serde_json::from_str(body).ok();
+    let parsed = json.as_ref().and_then(|v| ErrorResponse::deserialize(v).ok()).map(|r| r.error);
+
+    let message = parsed
+        .as_ref()
+        .map(|e| e.message.clone())
+        .filter(|m| !m.is_empty())
+        .unwrap_or_else(|| status.canonical_reason().unwrap_or("error").to_string());
+    let code = parsed.as_ref().and_then(|e| e.code.as_ref()).and_then(Value::as_str).unwrap_or("");
+    // OpenRouter attaches the moderation verdict to 403 responses
+    let flagged = parsed
+        .as_ref()
+        .and_then(|e| e.metadata.as_ref())
+        .is_some_and(|m| m.get("reasons").is_some() || m.get("flagged_input").is_some());
+    let lower = message.to_lowercase();
+    let detail = ProviderError::new(status.as_u16(), message).with_body(json);
+
+    match status.as_u16() {
+        401 => LLMError::Unauthorized(detail),
+        403 if flagged || lower.contains("moderation") || lower.contains("flagged") => LLMError::ContentFiltered(detail),
+        403 => LLMError::Unauthorized(detail),
+        404 => LLMError::ModelNotFound(detail),
+        408 => LLMError::Timeout(detail.to_string()),
+        429 => LLMError::RateLimited { retry_after, error: detail },
+        _ if code == "context_length_exceeded" || lower.contains("context length") || lower.contains("maximum context") => {
+            LLMError::ContextLengthExceeded(detail)
+        }
+        _ if code == "model_not_found" || lower.contains("not a valid model") || lower.contains("no endpoints found") => {
+            LLMError::ModelNotFound(detail)
+        }
+        _ if code == "content_filter" || code == "content_policy_violation" => LLMError::ContentFiltered(detail),
+        _ => LLMError::RequestFailed(detail.to_string()),
+    }
+}
+
+impl OpenAICompatibleClient {
+    /// Send a chat request, returning the successful response
+    async fn send(&self, payload: &ChatRequest<'_>) -> Result<reqwest::Response, LLMError> {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        if let Some(ref api_key) = self.config.api_key {
+            headers.insert(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", api_key)).map_err(|e| LLMError::RequestFailed(e.to_string()))?);
+        }
+
+        let url = self.endpoint();
+        let resp = send_with_retry(&self.retry, || self.http.post(&url).headers(headers.clone()).json(payload)).await?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let retry_after = retry_after(resp.headers());
+            let body = resp.text().await.unwrap_or_default();
+            return Err(map_error(status, retry_after, &body));
+        }
+        Ok(resp)
+    }
+}
+
+#[async_trait]
+impl LLMProvider for OpenAICompatibleClient {
+    async fn enhance(&self, prompt: Prompt, options: EnhancementOptions) -> Result<EnhancedPrompt, LLMError> {
+        self.complete(enhancement_request(&prompt, &options)).await
+    }
+
+    async fn enhance_streaming(&self, prompt: Prompt, options: EnhancementOptions, on_token: TokenSink<'_>) -> Result<EnhancedPrompt, LLMError> {
+        self.complete_streaming(enhancement_request(&prompt, &options), on_token).await
+    }
+
+    async fn complete(&self, request: CompletionRequest) -> Result<EnhancedPrompt, LLMError> {
+        let resp = self.send(&ChatRequest::new(&self.config.model, &request)).await?;
+
+        let parsed: ChatResponse = resp.json().await.map_err(|e| LLMError::UnexpectedResponse(e.to_string()))?;
+        parsed.into_enhanced()
+    }
+
+    async fn complete_streaming(&self, request: CompletionRequest, on_token: TokenSink<'_>) -> Result<EnhancedPrompt, LLMError> {
+        let resp = self.send(&ChatRequest::new(&self.config.model, &request).streaming()).await?;
+        read_sse(resp, on_token).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::prompting::ENHANCEMENT_SYSTEM_PROMPT;
+    use crate::infrastructure::providers::lines::serve_in_pieces;
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn config_for(server: &MockServer, api_key: Option<&str>) -> OpenAICompatibleConfig {
+        OpenAICompatibleConfig {
+            base_url: format!("{}/v1/", server.uri()),
+            api_key: api_key.map(str::to_string),
+            model: "local-model".to_string(),
+        }
+    }
+
+    fn fast_retries() -> RetryConfig {
+        RetryConfig { base_delay: Duration::from_millis(1), jitter: false, ..RetryConfig::default() }
+    }
+
+    fn completion(content: &str) -> serde_json::Value {
+        json!({ "choices": [ { "message": { "role": "assistant", "content": content } } ] })
+    }
+
+    #[tokio::test]
+    async fn test_enhance_against_mock_server() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("authorization", "Bearer secret"))
+            .and(body_partial_json(json!({ "model": "local-model", "temperature": 0.2 })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion("  Improved prompt  ")))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = OpenAICompatibleClient::new(config_for(&server, Some("secret"))).unwrap();
+        let result = client
+            .enhance(Prompt { text: "plan a trip".into() }, EnhancementOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(result.text, "Improved prompt");
+    }
+
+    #[tokio::test]
+    async fn test_enhance_reports_usage() {
+        let server = MockServer::start().await;
+        let mut body = completion("Improved prompt");
+        body["usage"] = json!({ "prompt_tokens": 30, "completion_tokens": 12, "total_tokens": 42 });
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .mount(&server)
+            .await;
+
+        let client = OpenAICompatibleClient::new(config_for(&server, None)).unwrap();
+        let result = client
+            .enhance(Prompt { text: "plan a trip".into() }, EnhancementOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(result.usage, Some(TokenUsage { input_tokens: 30, output_tokens: 12 }));
+    }
+
+    #[tokio::test]
+    async fn test_enhance_sends_system_and_user_messages() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion("ok")))
+            .mount(&server)
+            .await;
+
+        let client = OpenAICompatibleClient::new(config_for(&server, None)).unwrap();
+        client
+            .enhance(Prompt { text: "plan a trip".into() }, EnhancementOptions::default())
+            .await
+            .unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].headers.get("authorization").is_none());
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body["messages"][0]["role"], "system");
+        assert_eq!(body["messages"][0]["content"], ENHANCEMENT_SYSTEM_PROMPT);
+        assert_eq!(body["messages"][1]["role"], "user");
+        assert_eq!(body["messages"][1]["content"], "plan a trip");
+    }
+
+    #[tokio::test]
+    async fn test_complete_sends_request_as_given() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({
+                "temperature": 0.0,
+                "messages": [ { "role": "system", "content": "Review" }, { "role": "user", "content": "Draft" } ]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion("NO ISSUES")))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = OpenAICompatibleClient::new(config_for(&server, None)).unwrap();
+        let request = CompletionRequest { system: "Review".into(), user: "Draft".into(), temperature: 0.0 };
+        let result = client.complete(request).await.unwrap();
+
+        assert_eq!(result.text, "NO ISSUES");
+    }
+
+    #[tokio::test]
+    async fn test_enhance_error_status() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let client = OpenAICompatibleClient::new(config_for(&server, None)).unwrap().with_retry(fast_retries());
+        let result = client
+            .enhance(Prompt { text: "plan a trip".into() }, EnhancementOptions::default())
+            .await;
+
+        match result {
+            Err(LLMError::RequestFailed(msg)) => assert!(msg.contains("500")),
+            other => panic!("Expected RequestFailed error, got {:?}", other.map(|p| p.text)),
+        }
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_enhance_retries_rate_limit() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "0"))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion("Improved prompt")))
+            .mount(&server)
+            .await;
+
+        let client = OpenAICompatibleClient::new(config_for(&server, None)).unwrap().with_retry(fast_retries());
+        let result = client
+            .enhance(Prompt { text: "plan a trip".into() }, EnhancementOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(result.text, "Improved prompt");
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_enhance_no_choices() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "choices": [] })))
+            .mount(&server)
+            .await;
+
+        let client = OpenAICompatibleClient::new(config_for(&server, None)).unwrap();
+        let result = client
+            .enhance(Prompt { text: "plan a trip".into() }, EnhancementOptions::default())
+            .await;
+
+        assert!(matches!(result, Err(LLMError::UnexpectedResponse(_))));
+    }
+
+    fn error_body(code: Value, message: &str) -> String {
+        json!({ "error": { "code": code, "message": message } }).to_string()
+    }
+
+    #[test]
+    fn test_map_error_openrouter_codes() {
+        let error = map_error(StatusCode::UNAUTHORIZED, None, &error_body(json!(401), "No auth credentials found"));
+        assert!(matches!(error, LLMError::Unauthorized(ref e) if e.status == 401 && e.message == "No auth credentials found"));
+
+        let error = map_error(StatusCode::TOO_MANY_REQUESTS, Some(Duration::from_secs(4)), &error_body(json!(429), "Rate limit exceeded"));
+        assert!(matches!(error, LLMError::RateLimited { retry_after: Some(d), .. } if d == Duration::from_secs(4)));
+
+        let error = map_error(StatusCode::REQUEST_TIMEOUT, None, &error_body(json!(408), "Request timed out"));
+        assert!(matches!(error, LLMError::Timeout(_)));
+
+        let error = map_error(
+            StatusCode::BAD_REQUEST,
+            None,
+            &error_body(json!(400), "This endpoint's maximum context length is 8192 tokens. However, you requested about 9000 tokens"),
+        );
+        assert!(matches!(error, LLMError::ContextLengthExceeded(_)));
+
+        let error = map_error(StatusCode::BAD_REQUEST, None, &error_body(json!(400), "foo/bar is not a valid model ID"));
+        assert!(matches!(error, LLMError::ModelNotFound(_)));
+
+        let error = map_error(StatusCode::BAD_GATEWAY, None, &error_body(json!(502), "Provider returned error"));
+        assert!(matches!(error, LLMError::RequestFailed(ref msg) if msg.contains("502") && msg.contains("Provider returned error")));
+    }
+
+    #[test]
+    fn test_map_error_moderation() {
+        let body = json!({
+            "error": {
+                "code": 403,
+                "message": "Input was flagged",
+                "metadata": { "reasons": ["violence"], "flagged_input": "...", "provider_name": "openai", "model_slug": "openai/gpt-4o" }
+            }
+        });
+
+        let error = map_error(StatusCode::FORBIDDEN, None, &body.to_string());
+
+        match error {
+            LLMError::ContentFiltered(detail) => assert_eq!(detail.body, Some(body)),
+            other => panic!("Expected ContentFiltered error, got {:?}", other),
+        }
+        let error = map_error(StatusCode::FORBIDDEN, None, &error_body(json!(403), "Key is disabled"));
+        assert!(matches!(error, LLMError::Unauthorized(_)));
+    }
+
+    #[test]
+    fn test_map_error_openai_string_codes() {
+        let error = map_error(StatusCode::BAD_REQUEST, None, &error_body(json!("context_length_exceeded"), "Too long"));
+        assert!(matches!(error, LLMError::ContextLengthExceeded(_)));
+
+        let error = map_error(StatusCode::BAD_REQUEST, None, &error_body(json!("content_policy_violation"), "Rejected"));
+        assert!(matches!(error, LLMError::ContentFiltered(_)));
+
+        let error = map_error(StatusCode::SERVICE_UNAVAILABLE, None, "<html>unavailable</html>");
+        assert!(matches!(error, LLMError::RequestFailed(ref msg) if msg == "status 503: Service Unavailable"));
+    }
+
+    #[test]
+    fn test_into_text_content_filter() {
+        let response: ChatResponse = serde_json::from_value(json!({
+            "choices": [ { "message": { "role": "assistant", "content": null }, "finish_reason": "content_filter" } ]
+        }))
+        .unwrap();
+        assert!(matches!(response.into_text(), Err(LLMError::ContentFiltered(_))));
+    }
+
+    fn sse(events: &[serde_json::Value]) -> String {
+        let mut body = String::from(": keep-alive\n\n");
+        for event in events {
+            body.push_str(&format!("data: {}\n\n", event));
+        }
+        body.push_str("data: [DONE]\n\n");
+        body
+    }
+
+    fn delta(content: &str) -> serde_json::Value {
+        json!({ "choices": [ { "delta": { "content": content }, "finish_reason": null } ] })
+    }
+
+    #[tokio::test]
+    async fn test_enhance_streaming_reports_tokens() {
+        let server = MockServer::start().await;
+        let body = sse(&[delta("Improved"), delta(" streamed"), delta(" prompt"), json!({ "choices": [ { "delta": {}, "finish_reason": "stop" } ] })]);
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(body_partial_json(json!({ "stream": true })))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = OpenAICompatibleClient::new(config_for(&server, None)).unwrap();
+        let tokens = std::sync::Mutex::new(Vec::new());
+        let on_token = |token: &str| tokens.lock().unwrap().push(token.to_string());
+        let result = client
+            .enhance_streaming(Prompt { text: "plan a trip".into() }, EnhancementOptions::default(), &on_token)
+            .await
+            .unwrap();
+
+        assert_eq!(result.text, "Improved streamed prompt");
+        assert_eq!(*tokens.lock().unwrap(), vec!["Improved", " streamed", " prompt"]);
+        assert_eq!(result.usage, None);
+    }
+
+    #[tokio::test]
+    async fn test_streamed_character_split_across_chunks() {
+        let body = sse(&[delta("Plan a café crawl")]).into_bytes();
+        let split = body.iter().position(|&b| b == 0xc3).unwrap() + 1;
+        let url = serve_in_pieces("text/event-stream", vec![body[..split].to_vec(), body[split..].to_vec()]).await;
+
+        let config = OpenAICompatibleConfig { base_url: format!("{}/v1", url), api_key: None, model: "local-model".to_string() };
+        let client = OpenAICompatibleClient::new(config).unwrap();
+        let tokens = std::sync::Mutex::new(Vec::new());
+        let on_token = |token: &str| tokens.lock().unwrap().push(token.to_string());
+        let result = client
+            .enhance_streaming(Prompt { text: "plan a trip".into() }, EnhancementOptions::default(), &on_token)
+            .await
+            .unwrap();
+
+        assert_eq!(result.text, "Plan a café crawl");
+        assert_eq!(*tokens.lock().unwrap(), vec!["Plan a café crawl"]);
+    }
+
+    #[tokio::test]
+    async fn test_enhance_streaming_reports_usage_chunk() {
+        let server = MockServer::start().await;
+        let usage = json!({ "choices": [], "usage": { "prompt_tokens": 30, "completion_tokens": 3 } });
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "stream": true, "stream_options": { "include_usage": true } })))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(sse(&[delta("Improved prompt"), usage]), "text/event-stream"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = OpenAICompatibleClient::new(config_for(&server, None)).unwrap();
+        let result = client
+            .enhance_streaming(Prompt { text: "plan a trip".into() }, EnhancementOptions::default(), &|_| {})
+            .await
+            .unwrap();
+
+        assert_eq!(result.text, "Improved prompt");
+        assert_eq!(result.usage, Some(TokenUsage { input_tokens: 30, output_tokens: 3 }));
+    }
+
+    #[tokio::test]
+    async fn test_enhance_streaming_error_event() {
+        let server = MockServer::start().await;
+        let body = sse(&[delta("partial"), json!({ "error": { "code": 502, "message": "Provider disconnected" } })]);
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"))
+            .mount(&server)
+            .await;
+
+        let client = OpenAICompatibleClient::new(config_for(&server, None)).unwrap();
+        let result = client
+            .enhance_streaming(Prompt { text: "plan a trip".into() }, EnhancementOptions::default(), &|_| {})
+            .await;
+
+        assert!(matches!(result, Err(LLMError::RequestFailed(msg)) if msg.contains("Provider disconnected")));
+    }
+
+    #[test]
+    fn test_stream_flag_only_serialized_when_streaming() {
+        let request = enhancement_request(&Prompt { text: "plan a trip".into() }, &EnhancementOptions::default());
+        let plain = serde_json::to_value(ChatRequest::new("m", &request)).unwrap();
+        let streaming = serde_json::to_value(ChatRequest::new("m", &request).streaming()).unwrap();
+        assert!(plain.get("stream").is_none());
+        assert!(plain.get("stream_options").is_none());
+        assert_eq!(streaming["stream"], true);
+    }
+}
@@ -1,15 +1,16 @@
-use crate::domain::llm::{LLMError, LLMProvider};
+use crate::domain::llm::{LLMError, LLMProvider, TokenSink};
 use crate::domain::models::{EnhancedPrompt, EnhancementOptions, Prompt};
-use crate::domain::fewshot;
-use crate::infrastructure::config::{Config, OpenRouterConfig};
+use crate::domain::prompting::{enhancement_request, CompletionRequest};
+use crate::infrastructure::config::{OpenRouterConfig, RetryConfig};
+use crate::infrastructure::providers::openai_compatible::{map_error, read_sse, ChatRequest, ChatResponse};
+use crate::infrastructure::providers::retry::{retry_after, send_with_retry};
 use async_trait::async_trait;
 use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
-use serde::{Deserialize, Serialize};
-use tokio::time::{sleep, Duration};
 
 pub struct OpenRouterClient {
     http: reqwest::Client,
     config: OpenRouterConfig,
+    retry: RetryConfig,
 }
 
 impl OpenRouterClient {
@@ -19,101 +20,21 @@ impl OpenRouterClient {
             .build()
             .map_err(|e| LLMError::RequestFailed(e.to_string()))?;
 
-        Ok(Self { http, config })
+        Ok(Self { http, config, retry: RetryConfig::default() })
     }
 
-    /// Legacy method for backwards compatibility
-    pub fn from_env() -> Result<Self, LLMError> {
-        let config = OpenRouterConfig::from_env()
-            .map_err(|e| LLMError::NotConfigured(e))?;
-        Self::new(config)
+    /// Replace the default retry policy
+    pub fn with_retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
     }
-}
-
-#[derive(Debug, Serialize)]
-struct ChatRequest<'a> {
-    model: &'a str,
-    messages: Vec<ChatMessage<'a>>,
-    temperature: f32,
-}
-
-#[derive(Debug, Serialize)]
-struct ChatMessage<'a> {
-    role: &'a str,
-    content: &'a str,
-}
-
-#[derive(Debug, Deserialize)]
-struct ChatResponse {
-    choices: Vec<Choice>,
-}
-
-#[derive(Debug, Deserialize)]
-struct Choice {
-    message: ChoiceMessage,
-}
-
-#[derive(Debug, Deserialize)]
-struct ChoiceMessage {
-    content: String,
-}
-
-#[async_trait]
-impl LLMProvider for OpenRouterClient {
-    async fn enhance(&self, prompt: Prompt, options: EnhancementOptions) -> Result<EnhancedPrompt, LLMError> {
-        let system = "You are an expert prompt engineering assistant. Your ONLY task is to refine and enhance user prompts for Large Language Models. You must return ONLY the enhanced prompt text - no introductions, no explanations, no additional commentary of any kind. Simply output the improved prompt directly.
 
-CRITICAL: Your response must contain ONLY the enhanced prompt. No prefixes like 'Enhanced prompt:' or 'Here is the enhanced version:'. No meta-commentary. No acknowledgments. Just the enhanced prompt text itself.
-
-Guidelines for enhancement:
-- Maximize clarity and specificity
-- Specify clear goals and constraints
-- Resolve ambiguities while staying faithful to original intent
-- Structure the prompt for optimal LLM performance
-- If a specific language is requested, write the entire enhanced prompt in that language
-
-Remember: Output ONLY the enhanced prompt. Nothing else.";
-
-        let mut instruction = String::new();
-        if let Some(goal) = options.goal { instruction.push_str(&format!("Goal: {}\n", goal)); }
-        if let Some(style) = options.style { instruction.push_str(&format!("Style: {}\n", style)); }
-        if let Some(tone) = options.tone { instruction.push_str(&format!("Tone: {}\n", tone)); }
-        if let Some(level) = options.level { instruction.push_str(&format!("Enhancement level: {} (1-5)\n", level)); }
-        if let Some(audience) = options.audience { instruction.push_str(&format!("Audience: {}\n", audience)); }
-        if let Some(language) = options.language { instruction.push_str(&format!("Language: {}\n", language)); }
-
-        let mut user = if instruction.is_empty() {
-            prompt.text.clone()
-        } else {
-            format!("{}\n\n---\nOriginal prompt:\n{}", instruction, prompt.text.clone())
-        };
-
-        // Add few-shot examples
-        let category = if prompt.text.to_lowercase().contains("code") || prompt.text.to_lowercase().contains("function") || prompt.text.to_lowercase().contains("program") {
-            "code"
-        } else if prompt.text.to_lowercase().contains("explain") || prompt.text.to_lowercase().contains("what is") {
-            "explanation"
-        } else if prompt.text.to_lowercase().contains("define") || prompt.text.to_lowercase().contains("definition") {
-            "definition"
-        } else {
-            "general"
-        };
-
-        let examples = fewshot::select_examples(&category, 2);
-        if !examples.is_empty() {
-            let examples_text = examples.iter().map(|ex| format!("Example Input: {}\nExample Output: {}", ex.input, ex.output)).collect::<Vec<_>>().join("\n\n");
-            user = format!("Here are some examples to guide your response:\n\n{}\n\n{}", examples_text, user);
-        }
-
-        let payload = ChatRequest {
-            model: &self.config.model,
-            messages: vec![
-                ChatMessage { role: "system", content: system },
-                ChatMessage { role: "user", content: &user },
-            ],
-            temperature: 0.2,
-        };
+    fn endpoint(&self) -> String {
+        format!("{}/chat/completions", self.config.base_url.trim_end_matches('/'))
+    }
 
+    /// Send a chat request, returning the successful response
+    async fn send(&self, payload: &ChatRequest<'_>) -> Result<reqwest::Response, LLMError> {
         let mut headers = HeaderMap::new();
         headers.insert(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", self.config.api_key)).map_err(|e| LLMError::RequestFailed(e.to_string()))?);
         headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
@@ -124,64 +45,146 @@ Remember: Output ONLY the enhanced prompt. Nothing else.";
             headers.insert("X-Title", HeaderValue::from_str(title).map_err(|e| LLMError::RequestFailed(e.to_string()))?);
         }
 
-        const MAX_RETRIES: u32 = 3;
-        let mut attempts = 0;
-
-        let resp = loop {
-            attempts += 1;
-            match self.http.post("https://openrouter.ai/api/v1/chat/completions").headers(headers.clone()).json(&payload).send().await {
-                Ok(r) => break r,
-                Err(_e) => {
-                    if attempts >= MAX_RETRIES {
-                        // Graceful degradation: return a simple enhanced prompt
-                        return Ok(EnhancedPrompt {
-                            text: format!("Enhanced: {}", prompt.text),
-                            rationale: Some("Fallback due to API failure after retries".to_string()),
-                            confidence: Some(0.3),
-                        });
-                    }
-                    let delay = Duration::from_millis(500 * 2u64.pow(attempts - 1));
-                    sleep(delay).await;
-                }
-            }
-        };
+        let url = self.endpoint();
+        let resp = send_with_retry(&self.retry, || self.http.post(&url).headers(headers.clone()).json(payload)).await?;
 
-        if !resp.status().is_success() {
-            return Err(LLMError::RequestFailed(format!("status {}", resp.status())));
+        let status = resp.status();
+        if !status.is_success() {
+            let retry_after = retry_after(resp.headers());
+            let body = resp.text().await.unwrap_or_default();
+            return Err(map_error(status, retry_after, &body));
         }
+        Ok(resp)
+    }
+}
+
+#[async_trait]
+impl LLMProvider for OpenRouterClient {
+    async fn enhance(&self, prompt: Prompt, options: EnhancementOptions) -> Result<EnhancedPrompt, LLMError> {
+        self.complete(enhancement_request(&prompt, &options)).await
+    }
+
+    async fn enhance_streaming(&self, prompt: Prompt, options: EnhancementOptions, on_token: TokenSink<'_>) -> Result<EnhancedPrompt, LLMError> {
+        self.complete_streaming(enhancement_request(&prompt, &options), on_token).await
+    }
+
+    async fn complete(&self, request: CompletionRequest) -> Result<EnhancedPrompt, LLMError> {
+        let resp = self.send(&ChatRequest::new(&self.config.model, &request)).await?;
 
         let parsed: ChatResponse = resp.json().await.map_err(|e| LLMError::UnexpectedResponse(e.to_string()))?;
-        let text = parsed
-            .choices
-            .get(0)
-            .map(|c| c.message.content.trim().to_string())
-            .ok_or_else(|| LLMError::UnexpectedResponse("no choices".into()))?;
+        parsed.into_enhanced()
+    }
 
-        Ok(EnhancedPrompt { text, rationale: None, confidence: None })
+    async fn complete_streaming(&self, request: CompletionRequest, on_token: TokenSink<'_>) -> Result<EnhancedPrompt, LLMError> {
+        let resp = self.send(&ChatRequest::new(&self.config.model, &request).streaming()).await?;
+        read_sse(resp, on_token).await
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::infrastructure::config::lock_env;
+    use serde_json::json;
     use std::env;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn client_for(server: &MockServer) -> OpenRouterClient {
+        let config = OpenRouterConfig {
+            api_key: "test-api-key".to_string(),
+            model: "openai/gpt-4o-mini".to_string(),
+            base_url: format!("{}/api/v1", server.uri()),
+            referer: None,
+            title: Some("anytra".to_string()),
+        };
+        let retry = RetryConfig { max_attempts: 1, ..RetryConfig::default() };
+        OpenRouterClient::new(config).unwrap().with_retry(retry)
+    }
 
-    fn set_test_env() {
-        env::set_var("OPENROUTER_API_KEY", "test-api-key");
-        env::set_var("OPENROUTER_MODEL", "test-model");
-        env::set_var("OPENROUTER_REFERER", "test-referer");
-        env::set_var("OPENROUTER_TITLE", "test-title");
+    /// A client configured the way `build_provider` configures it, from the environment
+    fn client_from_env() -> Result<OpenRouterClient, LLMError> {
+        OpenRouterConfig::from_env().map_err(LLMError::NotConfigured).and_then(OpenRouterClient::new)
     }
 
-    fn clear_test_env() {
-        env::remove_var("OPENROUTER_API_KEY");
-        env::remove_var("OPENROUTER_MODEL");
-        env::remove_var("OPENROUTER_REFERER");
-        env::remove_var("OPENROUTER_TITLE");
+    #[tokio::test]
+    async fn test_enhance_against_mock_server() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/chat/completions"))
+            .and(header("authorization", "Bearer test-api-key"))
+            .and(header("x-title", "anytra"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [ { "message": { "role": "assistant", "content": "Improved prompt" } } ]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let result = client_for(&server)
+            .enhance(Prompt { text: "plan a trip".into() }, EnhancementOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(result.text, "Improved prompt");
+    }
+
+    #[tokio::test]
+    async fn test_enhance_streaming() {
+        let server = MockServer::start().await;
+        let body = [
+            ": OPENROUTER PROCESSING\n\n".to_string(),
+            format!("data: {}\n\n", json!({ "choices": [ { "delta": { "content": "Improved" } } ] })),
+            format!("data: {}\n\n", json!({ "choices": [ { "delta": { "content": " prompt" } } ] })),
+            "data: [DONE]\n\n".to_string(),
+        ]
+        .concat();
+        Mock::given(method("POST"))
+            .and(path("/api/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"))
+            .mount(&server)
+            .await;
+
+        let tokens = std::sync::Mutex::new(Vec::new());
+        let on_token = |token: &str| tokens.lock().unwrap().push(token.to_string());
+        let result = client_for(&server)
+            .enhance_streaming(Prompt { text: "plan a trip".into() }, EnhancementOptions::default(), &on_token)
+            .await
+            .unwrap();
+
+        assert_eq!(result.text, "Improved prompt");
+        assert_eq!(tokens.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_enhance_rate_limited() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .insert_header("retry-after", "12")
+                    .set_body_json(json!({ "error": { "code": 429, "message": "Rate limit exceeded: free-models-per-min" } })),
+            )
+            .mount(&server)
+            .await;
+
+        let result = client_for(&server)
+            .enhance(Prompt { text: "plan a trip".into() }, EnhancementOptions::default())
+            .await;
+
+        match result {
+            Err(LLMError::RateLimited { retry_after, error }) => {
+                assert_eq!(retry_after, Some(std::time::Duration::from_secs(12)));
+                assert_eq!(error.status, 429);
+                assert!(error.message.contains("free-models-per-min"));
+            }
+            other => panic!("Expected RateLimited error, got {:?}", other.map(|p| p.text)),
+        }
     }
 
     #[test]
     fn test_from_env_success() {
+        let _guard = lock_env();
         // Save original env vars to restore later
         let original_api_key = env::var("OPENROUTER_API_KEY").ok();
         let original_model = env::var("OPENROUTER_MODEL").ok();
@@ -194,7 +197,7 @@ mod tests {
         env::set_var("OPENROUTER_REFERER", "test-referer");
         env::set_var("OPENROUTER_TITLE", "test-title");
 
-        let client = OpenRouterClient::from_env().unwrap();
+        let client = client_from_env().unwrap();
 
         assert_eq!(client.config.api_key, "test-api-key");
         assert_eq!(client.config.model, "test-model");
@@ -226,6 +229,7 @@ mod tests {
 
     #[test]
     fn test_from_env_missing_api_key() {
+        let _guard = lock_env();
         // Save original env vars to restore later
         let original_api_key = env::var("OPENROUTER_API_KEY").ok();
         let original_model = env::var("OPENROUTER_MODEL").ok();
@@ -238,7 +242,7 @@ mod tests {
         env::remove_var("OPENROUTER_REFERER");
         env::remove_var("OPENROUTER_TITLE");
 
-        let result = OpenRouterClient::from_env();
+        let result = client_from_env();
         assert!(result.is_err());
 
         if let Err(LLMError::NotConfigured(msg)) = result {
@@ -264,6 +268,7 @@ mod tests {
 
     #[test]
     fn test_from_env_defaults() {
+        let _guard = lock_env();
         // Save original env vars to restore later
         let original_api_key = env::var("OPENROUTER_API_KEY").ok();
         let original_model = env::var("OPENROUTER_MODEL").ok();
@@ -274,7 +279,7 @@ mod tests {
         env::set_var("OPENROUTER_API_KEY", "test-api-key");
         // Don't set other vars to test defaults
 
-        let client = OpenRouterClient::from_env().unwrap();
+        let client = client_from_env().unwrap();
 
         assert_eq!(client.config.api_key, "test-api-key");
         assert_eq!(client.config.model, "openrouter/auto"); // Default model
@@ -0,0 +1,215 @@
+use crate::domain::llm::LLMError;
+use crate::infrastructure::config::RetryConfig;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::time::{Duration, SystemTime};
+use tokio::time::sleep;
+use tracing::debug;
+
+/// Statuses worth retrying: timeouts, rate limiting and transient server failures
+/// (529 is Anthropic's "overloaded")
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(status.as_u16(), 408 | 429 | 500 | 502 | 503 | 504 | 529)
+}
+
+/// Delay requested by a `Retry-After` header, given either as seconds or as an HTTP date
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(SystemTime::now()).unwrap_or(Duration::ZERO))
+}
+
+/// Exponential backoff before retry number `retry` (1-based), capped at `max_delay`
+pub fn backoff_delay(config: &RetryConfig, retry: u32) -> Duration {
+    let exponential = config.base_delay.saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)));
+    let delay = exponential.min(config.max_delay);
+    if config.jitter {
+        delay.mul_f64(0.5 + fastrand::f64() * 0.5)
+    } else {
+        delay
+    }
+}
+
+/// Send a request, retrying transport errors and retryable statuses according to `config`.
+///
+/// `request` builds a fresh request for every attempt. The last response is returned
+/// as-is once attempts are exhausted, so callers keep mapping error statuses themselves.
+pub async fn send_with_retry<F>(config: &RetryConfig, request: F) -> Result<Response, LLMError>
+where
+    F: Fn() -> RequestBuilder,
+{
+    let max_attempts = config.max_attempts.max(1);
+    let mut attempt = 0;
+
+    loop {
+        attempt += 1;
+        let delay = match request().send().await {
+            Ok(resp) if attempt < max_attempts && is_retryable_status(resp.status()) => {
+                let delay = retry_after(resp.headers())
+                    .map(|d| d.min(config.max_delay))
+                    .unwrap_or_else(|| backoff_delay(config, attempt));
+                debug!(status = %resp.status(), attempt, delay_ms = delay.as_millis() as u64, "retryable status, retrying");
+                delay
+            }
+            Ok(resp) => return Ok(resp),
+            Err(e) if attempt < max_attempts => {
+                let delay = backoff_delay(config, attempt);
+                debug!(error = %e, attempt, delay_ms = delay.as_millis() as u64, "request failed, retrying");
+                delay
+            }
+            Err(e) => return Err(LLMError::RequestFailed(e.to_string())),
+        };
+        sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use std::time::Instant;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn fast_retries(max_attempts: u32) -> RetryConfig {
+        RetryConfig {
+            max_attempts,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_secs(5),
+            jitter: false,
+        }
+    }
+
+    /// Respond with `status` for the next `times` requests
+    async fn script(server: &MockServer, status: u16, times: u64) {
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(status))
+            .up_to_n_times(times)
+            .mount(server)
+            .await;
+    }
+
+    async fn send(server: &MockServer, config: &RetryConfig) -> Result<Response, LLMError> {
+        let http = reqwest::Client::new();
+        let url = server.uri();
+        send_with_retry(config, || http.post(&url)).await
+    }
+
+    #[test]
+    fn test_is_retryable_status() {
+        for code in [408, 429, 500, 502, 503, 504, 529] {
+            assert!(is_retryable_status(StatusCode::from_u16(code).unwrap()), "{} should be retryable", code);
+        }
+        for code in [400, 401, 403, 404, 413, 422, 501] {
+            assert!(!is_retryable_status(StatusCode::from_u16(code).unwrap()), "{} should not be retryable", code);
+        }
+    }
+
+    #[test]
+    fn test_retry_after_parsing() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("7"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(7)));
+
+        let future = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(120));
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(&future).unwrap());
+        let delay = retry_after(&headers).unwrap();
+        assert!(delay > Duration::from_secs(100) && delay <= Duration::from_secs(120));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"));
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("soon"));
+        assert_eq!(retry_after(&headers), None);
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        let config = RetryConfig {
+            max_attempts: 10,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1000),
+            jitter: false,
+        };
+        assert_eq!(backoff_delay(&config, 1), Duration::from_millis(100));
+        assert_eq!(backoff_delay(&config, 2), Duration::from_millis(200));
+        assert_eq!(backoff_delay(&config, 4), Duration::from_millis(800));
+        assert_eq!(backoff_delay(&config, 5), Duration::from_millis(1000));
+        assert_eq!(backoff_delay(&config, 40), Duration::from_millis(1000));
+
+        let jittered = RetryConfig { jitter: true, ..config };
+        for _ in 0..100 {
+            let delay = backoff_delay(&jittered, 3);
+            assert!(delay >= Duration::from_millis(200) && delay <= Duration::from_millis(400));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_until_success() {
+        let server = MockServer::start().await;
+        script(&server, 503, 1).await;
+        script(&server, 429, 1).await;
+        script(&server, 200, 1).await;
+
+        let resp = send(&server, &fast_retries(3)).await.unwrap();
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_does_not_retry_client_errors() {
+        let server = MockServer::start().await;
+        script(&server, 400, 1).await;
+        script(&server, 200, 1).await;
+
+        let resp = send(&server, &fast_retries(3)).await.unwrap();
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_returns_last_response_when_exhausted() {
+        let server = MockServer::start().await;
+        script(&server, 502, 5).await;
+
+        let resp = send(&server, &fast_retries(2)).await.unwrap();
+
+        assert_eq!(resp.status(), StatusCode::BAD_GATEWAY);
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_honors_retry_after() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "1"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        script(&server, 200, 1).await;
+
+        let started = Instant::now();
+        let resp = send(&server, &fast_retries(2)).await.unwrap();
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(started.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_transport_error_after_retries() {
+        let config = fast_retries(2);
+        let http = reqwest::Client::new();
+
+        // Nothing listens on port 9 (discard) on a test machine
+        let result = send_with_retry(&config, || http.post("http://127.0.0.1:9")).await;
+
+        assert!(matches!(result, Err(LLMError::RequestFailed(_))));
+    }
+}
@@ -0,0 +1,87 @@
+use crate::domain::templates::PromptTemplate;
+use anyhow::{bail, Context};
+use std::path::Path;
+use tracing::{debug, info};
+
+/// Load every template in `dir`. A `.json` file holds a full `PromptTemplate`;
+/// a `.md` or `.txt` file is the template text itself, named after the file,
+/// with each `{{placeholder}}` as a required argument. Other files are skipped.
+pub fn load_templates(dir: &Path) -> anyhow::Result<Vec<PromptTemplate>> {
+    let entries = std::fs::read_dir(dir).with_context(|| format!("reading template directory {}", dir.display()))?;
+    let mut paths = entries.map(|entry| entry.map(|e| e.path())).collect::<Result<Vec<_>, _>>()?;
+    // Deterministic order, so a name defined twice always resolves the same way
+    paths.sort();
+
+    let mut templates = Vec::new();
+    for path in paths {
+        let Some(extension) = path.extension().and_then(|e| e.to_str()) else { continue };
+        let template = match extension {
+            "json" => {
+                let raw = std::fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+                serde_json::from_str::<PromptTemplate>(&raw).with_context(|| format!("invalid template {}", path.display()))?
+            }
+            "md" | "txt" => {
+                let raw = std::fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+                let name = path.file_stem().and_then(|s| s.to_str()).with_context(|| format!("template file name {} is not UTF-8", path.display()))?;
+                PromptTemplate::from_text(name, raw)
+            }
+            _ => {
+                debug!(path = %path.display(), "skipping non-template file");
+                continue;
+            }
+        };
+        if template.name.trim().is_empty() || template.template.trim().is_empty() {
+            bail!("template {} needs a name and text", path.display());
+        }
+        templates.push(template);
+    }
+
+    info!(dir = %dir.display(), count = templates.len(), "loaded prompt templates");
+    Ok(templates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::TempDir;
+
+    #[test]
+    fn test_load_json_and_text_templates() {
+        let dir = TempDir::new();
+        dir.write("release-notes.md", "Write release notes for {{version}} from:\n{{changes}}\n");
+        dir.write("tweet.json", r#"{"name": "tweet", "description": "Short post", "arguments": [{"name": "topic", "required": true}], "template": "Tweet about {{topic}}"}"#);
+        dir.write("README", "not a template");
+
+        let templates = load_templates(dir.path()).unwrap();
+
+        assert_eq!(templates.len(), 2);
+        let notes = &templates[0];
+        assert_eq!(notes.name, "release-notes");
+        let arguments: Vec<_> = notes.arguments.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(arguments, vec!["version", "changes"]);
+        assert_eq!(templates[1].description.as_deref(), Some("Short post"));
+    }
+
+    #[test]
+    fn test_invalid_json_names_the_file() {
+        let dir = TempDir::new();
+        dir.write("broken.json", "{");
+
+        let error = load_templates(dir.path()).unwrap_err();
+
+        assert!(format!("{:#}", error).contains("broken.json"));
+    }
+
+    #[test]
+    fn test_empty_template_is_rejected() {
+        let dir = TempDir::new();
+        dir.write("empty.md", "   \n");
+
+        assert!(load_templates(dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_missing_directory() {
+        assert!(load_templates(Path::new("/nonexistent/anytra-templates")).is_err());
+    }
+}
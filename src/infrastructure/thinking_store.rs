@@ -0,0 +1,134 @@
+use crate::domain::thinking_store::{check_session_id, ThinkingSession, ThinkingStore};
+use anyhow::Context;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// Thinking sessions saved as `<session id>.json` in one directory, so they
+/// survive restarts of the server
+#[derive(Debug)]
+pub struct FileThinkingStore {
+    dir: PathBuf,
+}
+
+impl FileThinkingStore {
+    /// Use `dir`, creating it if it does not exist
+    pub fn open(dir: &Path) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(dir).with_context(|| format!("creating thinking session directory {}", dir.display()))?;
+        Ok(Self { dir: dir.to_path_buf() })
+    }
+
+    fn path(&self, id: &str) -> anyhow::Result<PathBuf> {
+        check_session_id(id)?;
+        Ok(self.dir.join(format!("{}.json", id)))
+    }
+}
+
+impl ThinkingStore for FileThinkingStore {
+    fn load(&self, id: &str) -> anyhow::Result<Option<ThinkingSession>> {
+        let path = self.path(id)?;
+        let raw = match std::fs::read_to_string(&path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("reading {}", path.display())),
+        };
+        let session = serde_json::from_str(&raw).with_context(|| format!("invalid thinking session {}", path.display()))?;
+        Ok(Some(session))
+    }
+
+    fn save(&self, session: &ThinkingSession) -> anyhow::Result<()> {
+        let path = self.path(&session.id)?;
+        // Write then rename, so a crash never leaves half a session behind
+        let partial = self.dir.join(format!(".{}.json.tmp", session.id));
+        std::fs::write(&partial, serde_json::to_vec(session)?).with_context(|| format!("writing {}", partial.display()))?;
+        std::fs::rename(&partial, &path).with_context(|| format!("writing {}", path.display()))?;
+        debug!(session = %session.id, "saved thinking session");
+        Ok(())
+    }
+
+    fn remove(&self, id: &str) -> anyhow::Result<Option<ThinkingSession>> {
+        let Some(session) = self.load(id)? else { return Ok(None) };
+        let path = self.path(id)?;
+        std::fs::remove_file(&path).with_context(|| format!("removing {}", path.display()))?;
+        Ok(Some(session))
+    }
+
+    fn list(&self) -> anyhow::Result<Vec<ThinkingSession>> {
+        let entries = std::fs::read_dir(&self.dir).with_context(|| format!("reading thinking session directory {}", self.dir.display()))?;
+        let mut sessions = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            // Skips partial writes and files that are not ours
+            let Some(id) = path.file_stem().and_then(|s| s.to_str()).filter(|id| check_session_id(id).is_ok()) else { continue };
+            // One unreadable file should not hide every other session
+            match self.load(id) {
+                Ok(Some(session)) => sessions.push(session),
+                Ok(None) => {}
+                Err(e) => warn!(error = %e, "skipping unreadable thinking session"),
+            }
+        }
+        Ok(sessions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::sequential_thinking::ThoughtData;
+    use crate::infrastructure::TempDir;
+
+    fn session(id: &str, thoughts: u32) -> ThinkingSession {
+        let mut session = ThinkingSession { id: id.to_string(), ..ThinkingSession::new(None, id) };
+        for number in 1..=thoughts {
+            session.thinking.record_thought(ThoughtData::new(format!("step {}", number), number, thoughts, number < thoughts));
+        }
+        session
+    }
+
+    #[test]
+    fn test_sessions_survive_reopening() {
+        let dir = TempDir::new();
+        let mut saved = session("alpha", 2);
+        saved.thinking.record_thought(ThoughtData::new("alt".to_string(), 2, 2, false).with_branch(1, "b".to_string()));
+        FileThinkingStore::open(dir.path()).unwrap().save(&saved).unwrap();
+
+        let store = FileThinkingStore::open(dir.path()).unwrap();
+        let loaded = store.load("alpha").unwrap().unwrap();
+
+        assert_eq!(loaded.updated_at, saved.updated_at);
+        assert_eq!(loaded.thinking.get_thought_history().len(), 3);
+        assert_eq!(loaded.thinking.get_branches()["b"][0].thought, "alt");
+        assert!(store.load("beta").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_list_and_remove() {
+        let dir = TempDir::new();
+        let store = FileThinkingStore::open(dir.path()).unwrap();
+        store.save(&session("alpha", 1)).unwrap();
+        store.save(&session("beta", 2)).unwrap();
+        dir.write("broken.json", "{");
+        dir.write("notes.txt", "not a session");
+
+        let mut ids: Vec<_> = store.list().unwrap().into_iter().map(|s| s.id).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["alpha", "beta"]);
+
+        let removed = store.remove("beta").unwrap().unwrap();
+        assert_eq!(removed.thinking.get_thought_history().len(), 2);
+        assert!(!dir.path().join("beta.json").exists());
+        assert!(store.remove("beta").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_ids_cannot_escape_the_directory() {
+        let dir = TempDir::new();
+        let store = FileThinkingStore::open(dir.path()).unwrap();
+
+        assert!(store.load("../secret").is_err());
+        assert!(store.save(&session("a/b", 1)).is_err());
+    }
+}
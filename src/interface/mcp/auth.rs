@@ -0,0 +1,250 @@
+use crate::interface::mcp::server::{error_response, FORBIDDEN, UNAUTHENTICATED};
+use anyhow::{bail, Context};
+use axum::extract::{Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tracing::{debug, info};
+
+/// Who made an HTTP request, as named in the token file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    pub client: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenFile {
+    tokens: Vec<TokenEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenEntry {
+    client: String,
+    /// hex SHA-256 of the token; the token itself is never stored
+    sha256: String,
+}
+
+/// Static bearer tokens, kept only as SHA-256 digests
+#[derive(Debug, Default)]
+pub struct TokenStore {
+    clients: HashMap<[u8; 32], Identity>,
+}
+
+impl TokenStore {
+    /// Load a JSON file of the form
+    /// `{"tokens": [{"client": "alice", "sha256": "<hex digest of the token>"}]}`
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let raw = std::fs::read_to_string(path).with_context(|| format!("reading token file {}", path.display()))?;
+        let store = Self::parse(&raw).with_context(|| format!("invalid token file {}", path.display()))?;
+        info!(clients = store.clients.len(), "loaded HTTP access tokens");
+        Ok(store)
+    }
+
+    fn parse(raw: &str) -> anyhow::Result<Self> {
+        let file: TokenFile = serde_json::from_str(raw)?;
+        if file.tokens.is_empty() {
+            bail!("no tokens configured");
+        }
+        let mut clients = HashMap::new();
+        for entry in file.tokens {
+            let digest = parse_digest(&entry.sha256).with_context(|| format!("token of client {}", entry.client))?;
+            if clients.insert(digest, Identity { client: entry.client.clone() }).is_some() {
+                bail!("token of client {} is listed twice", entry.client);
+            }
+        }
+        Ok(Self { clients })
+    }
+
+    /// The identity a presented token belongs to
+    pub fn verify(&self, token: &str) -> Option<&Identity> {
+        self.clients.get(&digest(token))
+    }
+}
+
+fn digest(token: &str) -> [u8; 32] {
+    Sha256::digest(token.as_bytes()).into()
+}
+
+fn parse_digest(hex: &str) -> anyhow::Result<[u8; 32]> {
+    let hex = hex.trim();
+    if hex.len() != 64 || !hex.is_ascii() {
+        bail!("sha256 must be 64 hex characters");
+    }
+    let mut digest = [0u8; 32];
+    for (i, byte) in digest.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).context("sha256 must be 64 hex characters")?;
+    }
+    Ok(digest)
+}
+
+/// Requests and tool calls made by each client since startup
+#[derive(Debug, Default)]
+pub struct UsageLedger {
+    clients: Mutex<BTreeMap<String, ClientUsage>>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ClientUsage {
+    /// JSON-RPC messages received, counting each batch member
+    pub messages: u64,
+    pub tool_calls: u64,
+}
+
+impl UsageLedger {
+    pub fn record(&self, identity: &Identity, message: &Value) {
+        let members = match message {
+            Value::Array(batch) => batch.iter().collect(),
+            message => vec![message],
+        };
+        let tool_calls = members.iter().filter(|m| m.get("method").and_then(Value::as_str) == Some("tools/call")).count();
+
+        let mut clients = self.clients.lock().unwrap();
+        let usage = clients.entry(identity.client.clone()).or_default();
+        usage.messages += members.len() as u64;
+        usage.tool_calls += tool_calls as u64;
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<String, ClientUsage> {
+        self.clients.lock().unwrap().clone()
+    }
+
+    /// Log what every client used, e.g. at shutdown
+    pub fn report(&self) {
+        for (client, usage) in self.snapshot() {
+            info!(%client, messages = usage.messages, tool_calls = usage.tool_calls, "client usage");
+        }
+    }
+}
+
+/// Middleware rejecting requests without a known bearer token with 401 and
+/// attaching the caller's `Identity` to the rest
+pub(super) async fn require_token(State(tokens): State<Arc<TokenStore>>, mut request: Request, next: Next) -> Response {
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let Some(identity) = presented.and_then(|token| tokens.verify(token.trim())) else {
+        debug!(token_present = presented.is_some(), "rejecting unauthenticated request");
+        return unauthenticated();
+    };
+    request.extensions_mut().insert(identity.clone());
+    next.run(request).await
+}
+
+/// Middleware rejecting requests whose `Origin` header names a site other than
+/// a loopback one or one of `allowed`, so a web page cannot reach the server
+/// through DNS rebinding. Requests without `Origin` do not come from a browser
+pub(super) async fn check_origin(State(allowed): State<Arc<Vec<String>>>, request: Request, next: Next) -> Response {
+    if let Some(origin) = request.headers().get(header::ORIGIN) {
+        let origin = origin.to_str().unwrap_or_default();
+        if !origin_allowed(origin, &allowed) {
+            debug!(origin, "rejecting request from a foreign origin");
+            return forbidden("origin not allowed");
+        }
+    }
+    next.run(request).await
+}
+
+fn origin_allowed(origin: &str, allowed: &[String]) -> bool {
+    let origin = origin.trim_end_matches('/');
+    if allowed.iter().any(|a| a.trim_end_matches('/').eq_ignore_ascii_case(origin)) {
+        return true;
+    }
+    let Ok(url) = reqwest::Url::parse(origin) else { return false };
+    match url.host_str() {
+        Some("localhost") => true,
+        Some(host) => host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback()),
+        None => false,
+    }
+}
+
+fn unauthenticated() -> Response {
+    let body = Json(error_response(UNAUTHENTICATED, "missing or invalid bearer token".into()));
+    (StatusCode::UNAUTHORIZED, [(header::WWW_AUTHENTICATE, "Bearer")], body).into_response()
+}
+
+/// 403 for an authenticated client touching something it does not own
+pub(super) fn forbidden(message: &str) -> Response {
+    (StatusCode::FORBIDDEN, Json(error_response(FORBIDDEN, message.into()))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn hex(token: &str) -> String {
+        digest(token).iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn store() -> TokenStore {
+        let file = json!({ "tokens": [
+            { "client": "alice", "sha256": hex("alice-secret") },
+            { "client": "bob", "sha256": hex("bob-secret").to_uppercase() }
+        ] });
+        TokenStore::parse(&file.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_verify_known_tokens() {
+        let store = store();
+
+        assert_eq!(store.verify("alice-secret").unwrap().client, "alice");
+        assert_eq!(store.verify("bob-secret").unwrap().client, "bob");
+        assert!(store.verify("mallory").is_none());
+        assert!(store.verify(&hex("alice-secret")).is_none());
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_files() {
+        assert!(TokenStore::parse(r#"{"tokens": []}"#).is_err());
+        assert!(TokenStore::parse(r#"{"tokens": [{"client": "a", "sha256": "abc"}]}"#).is_err());
+        assert!(TokenStore::parse(&format!(r#"{{"tokens": [{{"client": "a", "sha256": "{}"}}]}}"#, "zz".repeat(32))).is_err());
+
+        let duplicate = json!({ "tokens": [{ "client": "a", "sha256": hex("t") }, { "client": "b", "sha256": hex("t") }] });
+        assert!(TokenStore::parse(&duplicate.to_string()).is_err());
+    }
+
+    #[test]
+    fn test_load_reports_missing_file() {
+        let error = TokenStore::load(Path::new("/nonexistent/anytra-tokens.json")).unwrap_err();
+
+        assert!(error.to_string().contains("/nonexistent/anytra-tokens.json"));
+    }
+
+    #[test]
+    fn test_origin_allowed() {
+        let allowed = vec!["https://app.example.com".to_string()];
+
+        assert!(origin_allowed("http://localhost:8080", &allowed));
+        assert!(origin_allowed("http://127.0.0.1", &allowed));
+        assert!(origin_allowed("http://[::1]:3000", &allowed));
+        assert!(origin_allowed("https://APP.example.com/", &allowed));
+        assert!(!origin_allowed("https://evil.example.com", &allowed));
+        assert!(!origin_allowed("http://localhost.evil.example.com", &allowed));
+        assert!(!origin_allowed("null", &allowed));
+        assert!(!origin_allowed("https://app.example.com", &[]));
+    }
+
+    #[test]
+    fn test_usage_counts_batch_members() {
+        let ledger = UsageLedger::default();
+        let alice = Identity { client: "alice".into() };
+
+        ledger.record(&alice, &json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/call" }));
+        ledger.record(&alice, &json!([{ "method": "tools/call" }, { "method": "ping" }]));
+
+        let usage = ledger.snapshot();
+        assert_eq!(usage["alice"], ClientUsage { messages: 3, tool_calls: 2 });
+        assert!(!usage.contains_key("bob"));
+    }
+}
@@ -0,0 +1,720 @@
+use crate::interface::mcp::auth::{check_origin, forbidden, require_token, Identity, TokenStore, UsageLedger};
+use crate::interface::mcp::server::{invalid_request, parse_error, Connection, Dispatcher, Notifier, ServerOptions, ServerState};
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::middleware;
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::sse::{Event, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Extension, Json, Router};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::{Future, IntoFuture};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+use tokio::select;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::sync::Notify;
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::{Stream, StreamExt};
+use tracing::{debug, info, info_span, warn};
+
+const SESSION_HEADER: &str = "mcp-session-id";
+
+/// HTTP-only settings
+#[derive(Debug)]
+pub struct HttpOptions {
+    pub bind: SocketAddr,
+    /// Accepted bearer tokens; `None` leaves the endpoint open
+    pub tokens: Option<TokenStore>,
+    /// Close sessions that go this long without a request; `None` keeps them until DELETE
+    pub session_idle_timeout: Option<Duration>,
+    /// Browser origins accepted besides loopback ones
+    pub allowed_origins: Vec<String>,
+}
+
+/// Shared by every HTTP request: the dispatcher, so the tool-call limit is
+/// server-wide, the open sessions by id and per-client usage
+struct AppState {
+    dispatcher: Dispatcher,
+    sessions: Mutex<HashMap<String, HttpSession>>,
+    usage: UsageLedger,
+}
+
+#[derive(Clone)]
+struct HttpSession {
+    /// the client that initialized the session; only it may use the session
+    owner: Option<Identity>,
+    connection: Arc<Mutex<Connection>>,
+    /// messages not tied to a request, until a GET takes them as its event stream
+    stream: Arc<Mutex<Option<UnboundedReceiver<String>>>>,
+    /// when the owner last sent a request in this session
+    last_seen: Instant,
+}
+
+impl HttpSession {
+    /// Whether the client is busy with the session even if it sends nothing:
+    /// a request is still running or its event stream is open
+    fn in_use(&self) -> bool {
+        let mut connection = self.connection.lock().unwrap();
+        connection.in_flight.reap();
+        let streaming = self.stream.lock().unwrap().is_none() && !connection.outbound.is_closed();
+        !connection.in_flight.is_empty() || streaming
+    }
+}
+
+impl AppState {
+    /// The session with this id, if `identity` may use it
+    fn session(&self, id: &str, identity: Option<&Identity>) -> Result<HttpSession, SessionError> {
+        match self.sessions.lock().unwrap().get_mut(id) {
+            None => Err(SessionError::Unknown),
+            Some(session) if session.owner.as_ref() != identity => Err(SessionError::NotOwner),
+            Some(session) => {
+                session.last_seen = Instant::now();
+                Ok(session.clone())
+            }
+        }
+    }
+
+    fn open_session(&self, owner: Option<Identity>) -> (String, Arc<Mutex<Connection>>) {
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let (outbound, stream) = Notifier::channel();
+        let connection = Arc::new(Mutex::new(Connection { id: Some(id.clone()), owner: owner.clone(), ..Connection::new(outbound) }));
+        let session = HttpSession { owner, connection: connection.clone(), stream: Arc::new(Mutex::new(Some(stream))), last_seen: Instant::now() };
+        self.sessions.lock().unwrap().insert(id.clone(), session);
+        debug!(session = %id, "session opened");
+        (id, connection)
+    }
+
+    fn close_session(&self, id: &str) {
+        if let Some(session) = self.sessions.lock().unwrap().remove(id) {
+            let mut connection = session.connection.lock().unwrap();
+            connection.in_flight.abort_all();
+            self.dispatcher.end_session(&connection);
+            connection.outbound = Notifier::default();
+            debug!(session = %id, "session closed");
+        }
+    }
+
+    /// Close the sessions that have had no request for `idle_timeout` and are not
+    /// in use, as if their clients had sent DELETE
+    fn expire_idle(&self, idle_timeout: Duration) {
+        let idle: Vec<String> = self.sessions.lock().unwrap().iter()
+            .filter(|(_, session)| session.last_seen.elapsed() >= idle_timeout && !session.in_use())
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in idle {
+            debug!(session = %id, "session idle, closing");
+            self.close_session(&id);
+        }
+    }
+
+    /// End every standalone event stream once the requests still using it are done,
+    /// so open GETs do not hold up a graceful shutdown
+    fn close_streams(&self) {
+        for session in self.sessions.lock().unwrap().values() {
+            let mut connection = session.connection.lock().unwrap();
+            self.dispatcher.end_session(&connection);
+            connection.outbound = Notifier::default();
+        }
+    }
+
+    fn abort_all(&self) {
+        for session in self.sessions.lock().unwrap().values() {
+            session.connection.lock().unwrap().in_flight.abort_all();
+        }
+    }
+}
+
+enum SessionError {
+    Unknown,
+    NotOwner,
+}
+
+impl IntoResponse for SessionError {
+    fn into_response(self) -> Response {
+        match self {
+            SessionError::Unknown => rejection(StatusCode::NOT_FOUND, "unknown session"),
+            SessionError::NotOwner => forbidden("session belongs to another client"),
+        }
+    }
+}
+
+/// Serve MCP over streamable HTTP until Ctrl-C
+pub async fn run_http_server(state: Arc<ServerState>, options: ServerOptions, http: HttpOptions) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(http.bind).await?;
+    if http.tokens.is_none() && !http.bind.ip().is_loopback() {
+        warn!(addr = %http.bind, "HTTP transport is reachable from the network without authentication; pass --auth-tokens");
+    }
+    info!(addr = %listener.local_addr()?, authenticated = http.tokens.is_some(), "MCP HTTP server ready");
+    let shutdown = async {
+        let _ = tokio::signal::ctrl_c().await;
+        info!("shutting down");
+    };
+    serve(listener, state, options, http.tokens, http.session_idle_timeout, http.allowed_origins, shutdown).await
+}
+
+/// Serve until `shutdown` resolves, then give open requests `shutdown_timeout`
+/// to finish before aborting them. Sessions idle for `idle_timeout` are closed
+/// along the way.
+async fn serve(
+    listener: TcpListener,
+    server: Arc<ServerState>,
+    options: ServerOptions,
+    tokens: Option<TokenStore>,
+    idle_timeout: Option<Duration>,
+    allowed_origins: Vec<String>,
+    shutdown: impl Future<Output = ()>,
+) -> anyhow::Result<()> {
+    let state = Arc::new(AppState {
+        dispatcher: Dispatcher::new(server, options.max_concurrent_requests),
+        sessions: Mutex::default(),
+        usage: UsageLedger::default(),
+    });
+    let mut app = Router::new().route("/mcp", post(handle_post).get(handle_get).delete(handle_delete));
+    if let Some(tokens) = tokens {
+        app = app.route_layer(middleware::from_fn_with_state(Arc::new(tokens), require_token));
+    }
+    // Added last so it runs first, before a token is even looked at
+    app = app.route_layer(middleware::from_fn_with_state(Arc::new(allowed_origins), check_origin));
+    let app = app.with_state(state.clone());
+
+    // Aborted when dropped, whichever way serving ends
+    let mut background = JoinSet::new();
+    if let Some(idle_timeout) = idle_timeout {
+        let state = state.clone();
+        background.spawn(async move {
+            let mut ticks = tokio::time::interval(idle_timeout);
+            loop {
+                ticks.tick().await;
+                state.expire_idle(idle_timeout);
+            }
+        });
+    }
+
+    let stop = Arc::new(Notify::new());
+    let server = axum::serve(listener, app)
+        .with_graceful_shutdown({
+            let stop = stop.clone();
+            async move { stop.notified().await }
+        })
+        .into_future();
+    tokio::pin!(server);
+
+    select! {
+        res = &mut server => {
+            state.usage.report();
+            return Ok(res?);
+        }
+        _ = shutdown => {
+            stop.notify_one();
+            state.close_streams();
+        }
+    }
+    if timeout(options.shutdown_timeout, &mut server).await.is_err() {
+        warn!("shutdown timeout elapsed, aborting in-flight requests");
+        state.abort_all();
+    }
+    state.usage.report();
+    Ok(())
+}
+
+/// One JSON-RPC message or batch per request. Requests are answered with JSON,
+/// or with an event stream carrying progress notifications before the response
+/// when the client accepts `text/event-stream`; notifications get 202.
+async fn handle_post(State(state): State<Arc<AppState>>, identity: Option<Extension<Identity>>, headers: HeaderMap, body: Bytes) -> Response {
+    let identity = identity.map(|Extension(identity)| identity);
+    let message: Value = match serde_json::from_slice(&body) {
+        Ok(message) => message,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(parse_error(&e))).into_response(),
+    };
+    if let Some(ref identity) = identity {
+        state.usage.record(identity, &message);
+    }
+
+    let (session_id, connection) = match session_id(&headers) {
+        Some(id) => match state.session(id, identity.as_ref()) {
+            Ok(session) => (id.to_string(), session.connection),
+            Err(e) => return e.into_response(),
+        },
+        // Only `initialize` may start a session
+        None if is_initialize(&message) => state.open_session(identity.clone()),
+        None => return rejection(StatusCode::BAD_REQUEST, "missing Mcp-Session-Id header"),
+    };
+    let session_header = [(SESSION_HEADER, HeaderValue::from_str(&session_id).expect("session ids are hex"))];
+
+    let (notifier, events) = match accepts_event_stream(&headers) {
+        true => {
+            let (notifier, events) = Notifier::channel();
+            (notifier, Some(events))
+        }
+        false => (Notifier::default(), None),
+    };
+    let client = identity.as_ref().map_or("anonymous", |identity| identity.client.as_str());
+    let span = info_span!("mcp_http", %client, session = %session_id);
+    let pending = span.in_scope(|| connection.lock().unwrap().accept(message, &state.dispatcher, &notifier));
+    let Some(pending) = pending else {
+        return (StatusCode::ACCEPTED, session_header).into_response();
+    };
+
+    match events {
+        Some(events) => {
+            // The stream ends once the answer is queued and every sender is gone
+            tokio::spawn(async move {
+                if let Some(answer) = pending.finish().await {
+                    notifier.send(&answer);
+                }
+            });
+            (session_header, Sse::new(event_stream(events))).into_response()
+        }
+        None => match pending.finish().await {
+            Some(answer) => (session_header, Json(answer)).into_response(),
+            // Every request was cancelled
+            None => (StatusCode::ACCEPTED, session_header).into_response(),
+        },
+    }
+}
+
+/// The session's standalone event stream, carrying server-initiated messages
+/// such as resource updates. A session has one; a second GET gets 409.
+async fn handle_get(State(state): State<Arc<AppState>>, identity: Option<Extension<Identity>>, headers: HeaderMap) -> Response {
+    let identity = identity.map(|Extension(identity)| identity);
+    let Some(id) = session_id(&headers) else {
+        return rejection(StatusCode::BAD_REQUEST, "missing Mcp-Session-Id header");
+    };
+    let session = match state.session(id, identity.as_ref()) {
+        Ok(session) => session,
+        Err(e) => return e.into_response(),
+    };
+    let Some(events) = session.stream.lock().unwrap().take() else {
+        return rejection(StatusCode::CONFLICT, "session already has an event stream");
+    };
+    debug!(session = %id, "event stream opened");
+    Sse::new(event_stream(events)).into_response()
+}
+
+/// End a session, aborting whatever it still has running
+async fn handle_delete(State(state): State<Arc<AppState>>, identity: Option<Extension<Identity>>, headers: HeaderMap) -> Response {
+    let identity = identity.map(|Extension(identity)| identity);
+    let Some(id) = session_id(&headers) else {
+        return rejection(StatusCode::BAD_REQUEST, "missing Mcp-Session-Id header");
+    };
+    match state.session(id, identity.as_ref()) {
+        Ok(_) => {
+            state.close_session(id);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+fn event_stream(events: UnboundedReceiver<String>) -> impl Stream<Item = Result<Event, Infallible>> {
+    UnboundedReceiverStream::new(events).map(|line| Ok(Event::default().event("message").data(line)))
+}
+
+fn session_id(headers: &HeaderMap) -> Option<&str> {
+    headers.get(SESSION_HEADER).and_then(|value| value.to_str().ok())
+}
+
+fn is_initialize(message: &Value) -> bool {
+    matches!(message.get("method").and_then(Value::as_str), Some("initialize" | "mcp/initialize"))
+}
+
+fn accepts_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .any(|accept| accept.contains("text/event-stream"))
+}
+
+fn rejection(status: StatusCode, message: &str) -> Response {
+    (status, Json(invalid_request(message.into()))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::llm::{LLMError, LLMProvider};
+    use crate::domain::models::{EnhancedPrompt, EnhancementOptions, Prompt};
+    use crate::usecases::enhance_prompt::EnhancePrompt;
+    use crate::infrastructure::config::create_test_config;
+    use async_trait::async_trait;
+    use serde_json::json;
+    use std::time::Duration;
+    use tokio::sync::oneshot;
+
+    struct MockProvider;
+
+    #[async_trait]
+    impl LLMProvider for MockProvider {
+        async fn enhance(&self, prompt: Prompt, _options: EnhancementOptions) -> Result<EnhancedPrompt, LLMError> {
+            Ok(EnhancedPrompt {
+                text: format!("Enhanced: {} - this is a longer text with enough words to pass validation", prompt.text),
+                ..Default::default()
+            })
+        }
+    }
+
+    struct TestServer {
+        url: String,
+        http: reqwest::Client,
+        token: Option<&'static str>,
+        stop: Option<oneshot::Sender<()>>,
+        server: tokio::task::JoinHandle<anyhow::Result<()>>,
+    }
+
+    impl TestServer {
+        async fn start() -> Self {
+            Self::start_with(None).await
+        }
+
+        async fn start_with(tokens: Option<TokenStore>) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/mcp", listener.local_addr().unwrap());
+            let state = Arc::new(ServerState::new(EnhancePrompt::new(Box::new(MockProvider), create_test_config())));
+            let options = ServerOptions { shutdown_timeout: Duration::from_secs(1), max_concurrent_requests: 4 };
+            let (stop, stopped) = oneshot::channel::<()>();
+            let server = tokio::spawn(serve(listener, state, options, tokens, None, vec!["https://app.example.com".to_string()], async {
+                let _ = stopped.await;
+            }));
+            Self { url, http: reqwest::Client::new(), token: None, stop: Some(stop), server }
+        }
+
+        async fn post(&self, session: Option<&str>, message: Value) -> reqwest::Response {
+            let mut request = self.http.post(&self.url).header("accept", "application/json").json(&message);
+            if let Some(session) = session {
+                request = request.header(SESSION_HEADER, session);
+            }
+            if let Some(token) = self.token {
+                request = request.bearer_auth(token);
+            }
+            request.send().await.unwrap()
+        }
+
+        /// Run the handshake and return the session id
+        async fn initialize(&self) -> String {
+            let resp = self.post(None, json!({ "jsonrpc": "2.0", "id": 0, "method": "initialize", "params": { "protocolVersion": "2025-03-26" } })).await;
+            assert_eq!(resp.status(), 200);
+            let session = resp.headers()[SESSION_HEADER].to_str().unwrap().to_string();
+            let body: Value = resp.json().await.unwrap();
+            assert_eq!(body["result"]["protocolVersion"], "2025-03-26");
+
+            let resp = self.post(Some(&session), json!({ "jsonrpc": "2.0", "method": "notifications/initialized" })).await;
+            assert_eq!(resp.status(), 202);
+            session
+        }
+
+        async fn stop(mut self) {
+            self.stop.take().unwrap().send(()).unwrap();
+            self.server.await.unwrap().unwrap();
+        }
+    }
+
+    fn tool_call(id: i64) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "tools/call",
+            "params": { "name": "enhance_prompt", "arguments": { "prompt": "test prompt", "enable_sequential_thinking": false } }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_in_session() {
+        let server = TestServer::start().await;
+        let session = server.initialize().await;
+
+        let resp = server.post(Some(&session), tool_call(1)).await;
+
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.headers()[SESSION_HEADER], session.as_str());
+        let body: Value = resp.json().await.unwrap();
+        assert_eq!(body["id"], 1);
+        assert!(body["result"]["content"][0]["text"].as_str().unwrap().contains("test prompt"));
+        server.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_requests_need_a_known_session() {
+        let server = TestServer::start().await;
+
+        let missing = server.post(None, tool_call(1)).await;
+        let unknown = server.post(Some("nope"), tool_call(1)).await;
+
+        assert_eq!(missing.status(), 400);
+        assert_eq!(missing.json::<Value>().await.unwrap()["error"]["code"], -32600);
+        assert_eq!(unknown.status(), 404);
+        server.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_sessions_are_independent() {
+        let server = TestServer::start().await;
+        let first = server.initialize().await;
+
+        // A session that has not been initialized cannot piggyback on another one
+        let resp = server.post(None, json!({ "jsonrpc": "2.0", "id": 0, "method": "initialize", "params": {} })).await;
+        let second = resp.headers()[SESSION_HEADER].to_str().unwrap().to_string();
+
+        assert_ne!(first, second);
+        let body: Value = server.post(Some(&second), tool_call(1)).await.json().await.unwrap();
+        assert!(body["result"].is_object());
+        server.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_sequential_thinking_history_is_per_client() {
+        let mut server = TestServer::start_with(Some(tokens())).await;
+        let thought = |id: i64| json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "tools/call",
+            "params": { "name": "sequential_thinking", "arguments": { "thought": "step", "thoughtNumber": 1, "totalThoughts": 2, "nextThoughtNeeded": true } }
+        });
+        server.token = Some("alice-secret");
+        let first = server.initialize().await;
+        let second = server.initialize().await;
+        server.post(Some(&first), thought(1)).await;
+        // The history is the client's, so it carries over to its other sessions
+        let alice: Value = server.post(Some(&second), thought(2)).await.json().await.unwrap();
+
+        server.token = Some("bob-secret");
+        let third = server.initialize().await;
+        let bob: Value = server.post(Some(&third), thought(3)).await.json().await.unwrap();
+
+        assert_eq!(alice["result"]["structuredContent"]["thoughtHistoryLength"], 2);
+        assert_eq!(bob["result"]["structuredContent"]["thoughtHistoryLength"], 1);
+        server.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_batch_over_http() {
+        let server = TestServer::start().await;
+        let session = server.initialize().await;
+
+        let resp = server.post(Some(&session), json!([tool_call(1), { "jsonrpc": "2.0", "id": 2, "method": "ping" }])).await;
+
+        let body: Value = resp.json().await.unwrap();
+        assert_eq!(body.as_array().unwrap().len(), 2);
+        server.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_carries_progress_then_response() {
+        let server = TestServer::start().await;
+        let session = server.initialize().await;
+        let mut call = tool_call(3);
+        call["params"]["_meta"] = json!({ "progressToken": "p" });
+
+        let resp = server
+            .http
+            .post(&server.url)
+            .header("accept", "application/json, text/event-stream")
+            .header(SESSION_HEADER, &session)
+            .json(&call)
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(resp.headers()["content-type"], "text/event-stream");
+        let body = resp.text().await.unwrap();
+        let messages: Vec<Value> = body
+            .lines()
+            .filter_map(|line| line.strip_prefix("data: "))
+            .map(|data| serde_json::from_str(data).unwrap())
+            .collect();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["method"], "notifications/progress");
+        assert_eq!(messages[1]["id"], 3);
+        server.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_delete_ends_session() {
+        let server = TestServer::start().await;
+        let session = server.initialize().await;
+
+        let deleted = server.http.delete(&server.url).header(SESSION_HEADER, &session).send().await.unwrap();
+        let after = server.post(Some(&session), tool_call(1)).await;
+
+        assert_eq!(deleted.status(), 204);
+        assert_eq!(after.status(), 404);
+        server.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_idle_sessions_are_closed() {
+        let server = Arc::new(ServerState::new(EnhancePrompt::new(Box::new(MockProvider), create_test_config())));
+        let state = AppState { dispatcher: Dispatcher::new(server, 4), sessions: Mutex::default(), usage: UsageLedger::default() };
+        let (idle, idle_connection) = state.open_session(None);
+        let (streaming, _) = state.open_session(None);
+        let (recent, _) = state.open_session(None);
+        let events = state.sessions.lock().unwrap()[&streaming].stream.lock().unwrap().take();
+        for id in [&idle, &streaming] {
+            state.sessions.lock().unwrap().get_mut(id).unwrap().last_seen -= Duration::from_secs(120);
+        }
+
+        state.expire_idle(Duration::from_secs(60));
+
+        let mut open: Vec<_> = state.sessions.lock().unwrap().keys().cloned().collect();
+        open.sort();
+        let mut expected = vec![streaming, recent];
+        expected.sort();
+        assert_eq!(open, expected);
+        assert!(idle_connection.lock().unwrap().outbound.is_closed());
+        drop(events);
+    }
+
+    #[tokio::test]
+    async fn test_get_stream_carries_resource_updates() {
+        let server = TestServer::start().await;
+        let session = server.initialize().await;
+        let subscribe = json!({ "jsonrpc": "2.0", "id": 1, "method": "resources/subscribe", "params": { "uri": "anytra://history" } });
+        assert!(server.post(Some(&session), subscribe).await.json::<Value>().await.unwrap()["result"].is_object());
+
+        let mut stream = server.http.get(&server.url).header(SESSION_HEADER, &session).send().await.unwrap();
+        let second = server.http.get(&server.url).header(SESSION_HEADER, &session).send().await.unwrap();
+        let missing = server.http.get(&server.url).send().await.unwrap();
+        server.post(Some(&session), tool_call(2)).await;
+
+        assert_eq!(stream.headers()["content-type"], "text/event-stream");
+        let chunk = timeout(Duration::from_secs(5), stream.chunk()).await.unwrap().unwrap().unwrap();
+        let data = std::str::from_utf8(&chunk).unwrap().lines().find_map(|line| line.strip_prefix("data: ")).unwrap();
+        let message: Value = serde_json::from_str(data).unwrap();
+        assert_eq!(message["method"], "notifications/resources/updated");
+        assert_eq!(message["params"]["uri"], "anytra://history");
+        assert_eq!(second.status(), 409);
+        assert_eq!(missing.status(), 400);
+        drop(stream);
+        server.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_malformed_body_is_a_parse_error() {
+        let server = TestServer::start().await;
+
+        let resp = server.http.post(&server.url).body("{not json").send().await.unwrap();
+
+        assert_eq!(resp.status(), 400);
+        assert_eq!(resp.json::<Value>().await.unwrap()["error"]["code"], -32700);
+        server.stop().await;
+    }
+
+    fn tokens() -> TokenStore {
+        let digest = |token: &str| -> String {
+            use sha2::{Digest, Sha256};
+            Sha256::digest(token).iter().map(|b| format!("{:02x}", b)).collect()
+        };
+        let file = json!({ "tokens": [
+            { "client": "alice", "sha256": digest("alice-secret") },
+            { "client": "bob", "sha256": digest("bob-secret") }
+        ] });
+        let path = std::env::temp_dir().join(format!("anytra-tokens-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(&path, file.to_string()).unwrap();
+        let tokens = TokenStore::load(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        tokens
+    }
+
+    #[tokio::test]
+    async fn test_requests_from_foreign_origins_are_forbidden() {
+        let server = TestServer::start().await;
+        let initialize = json!({ "jsonrpc": "2.0", "id": 0, "method": "initialize", "params": {} });
+        let from = |origin: &str| server.http.post(&server.url).header("accept", "application/json").header("origin", origin).json(&initialize);
+
+        let without_origin = server.post(None, initialize.clone()).await;
+        assert_eq!(without_origin.status(), 200);
+        for origin in ["http://localhost:6274", "https://app.example.com"] {
+            assert_eq!(from(origin).send().await.unwrap().status(), 200, "{}", origin);
+        }
+
+        let foreign = from("https://evil.example.com").send().await.unwrap();
+        assert_eq!(foreign.status(), 403);
+        let body: Value = foreign.json().await.unwrap();
+        assert_eq!(body["error"]["code"], crate::interface::mcp::server::FORBIDDEN);
+        let stream = server.http.get(&server.url).header("origin", "https://evil.example.com").send().await.unwrap();
+        assert_eq!(stream.status(), 403);
+        server.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_foreign_origin_is_rejected_before_authentication() {
+        let server = TestServer::start_with(Some(tokens())).await;
+
+        let resp = server.http.delete(&server.url).header("origin", "https://evil.example.com").send().await.unwrap();
+
+        assert_eq!(resp.status(), 403);
+        server.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_missing_or_unknown_token_is_unauthorized() {
+        let mut server = TestServer::start_with(Some(tokens())).await;
+        let initialize = json!({ "jsonrpc": "2.0", "id": 0, "method": "initialize", "params": {} });
+
+        let missing = server.post(None, initialize.clone()).await;
+        server.token = Some("wrong");
+        let unknown = server.post(None, initialize).await;
+
+        for resp in [missing, unknown] {
+            assert_eq!(resp.status(), 401);
+            assert_eq!(resp.headers()["www-authenticate"], "Bearer");
+            let body: Value = resp.json().await.unwrap();
+            assert_eq!(body["error"]["code"], crate::interface::mcp::server::UNAUTHENTICATED);
+        }
+        server.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_known_token_is_served() {
+        let mut server = TestServer::start_with(Some(tokens())).await;
+        server.token = Some("alice-secret");
+        let session = server.initialize().await;
+
+        let resp = server.post(Some(&session), tool_call(1)).await;
+
+        assert_eq!(resp.status(), 200);
+        server.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_session_of_another_client_is_forbidden() {
+        let mut server = TestServer::start_with(Some(tokens())).await;
+        server.token = Some("alice-secret");
+        let session = server.initialize().await;
+
+        server.token = Some("bob-secret");
+        let resp = server.post(Some(&session), tool_call(1)).await;
+        let deleted = server.http.delete(&server.url).bearer_auth("bob-secret").header(SESSION_HEADER, &session).send().await.unwrap();
+
+        assert_eq!(resp.status(), 403);
+        assert_eq!(resp.json::<Value>().await.unwrap()["error"]["code"], crate::interface::mcp::server::FORBIDDEN);
+        assert_eq!(deleted.status(), 403);
+        server.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_history_of_another_client_is_not_readable() {
+        let mut server = TestServer::start_with(Some(tokens())).await;
+        let read = json!({ "jsonrpc": "2.0", "id": 2, "method": "resources/read", "params": { "uri": "anytra://history/1" } });
+        server.token = Some("alice-secret");
+        let alice = server.initialize().await;
+        server.post(Some(&alice), tool_call(1)).await;
+        let own: Value = server.post(Some(&alice), read.clone()).await.json().await.unwrap();
+
+        server.token = Some("bob-secret");
+        let bob = server.initialize().await;
+        let other: Value = server.post(Some(&bob), read).await.json().await.unwrap();
+
+        assert!(own["result"]["contents"].is_array());
+        assert_eq!(other["error"]["code"], -32602);
+        server.stop().await;
+    }
+}
@@ -0,0 +1,198 @@
+use crate::infrastructure::logger::{LogRecord, LogSink};
+use crate::interface::mcp::server::Notifier;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Mutex;
+use tracing::Level;
+
+/// MCP log severities, least severe first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LoggingLevel {
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Critical,
+    Alert,
+    Emergency,
+}
+
+impl LoggingLevel {
+    fn of(level: Level) -> Self {
+        match level {
+            Level::TRACE | Level::DEBUG => LoggingLevel::Debug,
+            Level::INFO => LoggingLevel::Info,
+            Level::WARN => LoggingLevel::Warning,
+            Level::ERROR => LoggingLevel::Error,
+        }
+    }
+
+    /// The most verbose tracing level that is still at least this severe
+    fn tracing_level(self) -> Level {
+        match self {
+            LoggingLevel::Debug => Level::DEBUG,
+            LoggingLevel::Info => Level::INFO,
+            LoggingLevel::Notice | LoggingLevel::Warning => Level::WARN,
+            _ => Level::ERROR,
+        }
+    }
+}
+
+/// Sessions that asked for `notifications/message` with `logging/setLevel`.
+/// A session scoped to an HTTP session id only hears events from spans carrying
+/// that id, so clients never see each other's requests; a stdio session hears
+/// everything.
+#[derive(Default)]
+pub struct ClientLogs {
+    listeners: Mutex<Vec<Listener>>,
+    /// `encode(max level)` over all listeners, read on every event without locking
+    max_level: AtomicU8,
+}
+
+struct Listener {
+    session: Notifier,
+    scope: Option<String>,
+    level: LoggingLevel,
+}
+
+impl ClientLogs {
+    pub(super) fn set_level(&self, session: &Notifier, scope: Option<&str>, level: LoggingLevel) {
+        let mut listeners = self.listeners.lock().unwrap();
+        listeners.retain(|l| !l.session.same_channel(session));
+        listeners.push(Listener { session: session.clone(), scope: scope.map(str::to_string), level });
+        self.update_max_level(&listeners);
+    }
+
+    /// Stop forwarding to a session that has ended
+    pub(super) fn forget(&self, session: &Notifier) {
+        let mut listeners = self.listeners.lock().unwrap();
+        listeners.retain(|l| !l.session.same_channel(session));
+        self.update_max_level(&listeners);
+    }
+
+    fn update_max_level(&self, listeners: &[Listener]) {
+        let max = listeners.iter().map(|l| l.level).min().map(LoggingLevel::tracing_level);
+        self.max_level.store(encode(max), Ordering::Relaxed);
+    }
+}
+
+// Nothing in here may emit tracing events: they would come straight back to `log`
+// while the listeners are locked
+impl LogSink for ClientLogs {
+    fn max_level(&self) -> Option<Level> {
+        decode(self.max_level.load(Ordering::Relaxed))
+    }
+
+    fn log(&self, record: LogRecord) {
+        let level = LoggingLevel::of(record.level);
+        let mut listeners = self.listeners.lock().unwrap();
+        listeners.retain(|l| !l.session.is_closed());
+        for listener in listeners.iter() {
+            let in_scope = listener.scope.is_none() || listener.scope == record.session;
+            if in_scope && level >= listener.level {
+                listener.session.notify("notifications/message", json!({
+                    "level": level,
+                    "logger": record.target,
+                    "data": record.fields
+                }));
+            }
+        }
+    }
+}
+
+fn encode(level: Option<Level>) -> u8 {
+    match level {
+        None => 0,
+        Some(Level::ERROR) => 1,
+        Some(Level::WARN) => 2,
+        Some(Level::INFO) => 3,
+        Some(Level::DEBUG) => 4,
+        Some(Level::TRACE) => 5,
+    }
+}
+
+fn decode(level: u8) -> Option<Level> {
+    match level {
+        1 => Some(Level::ERROR),
+        2 => Some(Level::WARN),
+        3 => Some(Level::INFO),
+        4 => Some(Level::DEBUG),
+        5 => Some(Level::TRACE),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Map, Value};
+
+    fn record(level: Level, session: Option<&str>) -> LogRecord {
+        let mut fields = Map::new();
+        fields.insert("message".into(), json!("hello"));
+        LogRecord { level, target: "anytra::usecases".into(), fields, session: session.map(str::to_string) }
+    }
+
+    #[tokio::test]
+    async fn test_forwards_at_or_above_the_session_level() {
+        let logs = ClientLogs::default();
+        let (session, mut outbound) = Notifier::channel();
+        logs.set_level(&session, None, LoggingLevel::Notice);
+
+        logs.log(record(Level::INFO, None));
+        logs.log(record(Level::WARN, Some("abc")));
+        logs.forget(&session);
+        logs.log(record(Level::ERROR, None));
+        drop(session);
+
+        let message: Value = serde_json::from_str(&outbound.recv().await.unwrap()).unwrap();
+        assert_eq!(message["method"], "notifications/message");
+        assert_eq!(message["params"], json!({ "level": "warning", "logger": "anytra::usecases", "data": { "message": "hello" } }));
+        assert!(outbound.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_scoped_sessions_only_hear_their_own_events() {
+        let logs = ClientLogs::default();
+        let (alice, mut alice_outbound) = Notifier::channel();
+        logs.set_level(&alice, Some("alice-session"), LoggingLevel::Debug);
+
+        logs.log(record(Level::INFO, Some("bob-session")));
+        logs.log(record(Level::INFO, None));
+        logs.log(record(Level::DEBUG, Some("alice-session")));
+        logs.forget(&alice);
+        drop(alice);
+
+        let message: Value = serde_json::from_str(&alice_outbound.recv().await.unwrap()).unwrap();
+        assert_eq!(message["params"]["level"], "debug");
+        assert!(alice_outbound.recv().await.is_none());
+    }
+
+    #[test]
+    fn test_max_level_follows_the_most_verbose_listener() {
+        let logs = ClientLogs::default();
+        let (first, _first_outbound) = Notifier::channel();
+        let (second, _second_outbound) = Notifier::channel();
+        assert_eq!(logs.max_level(), None);
+
+        logs.set_level(&first, None, LoggingLevel::Error);
+        logs.set_level(&second, None, LoggingLevel::Info);
+        assert_eq!(logs.max_level(), Some(Level::INFO));
+
+        logs.set_level(&second, None, LoggingLevel::Emergency);
+        assert_eq!(logs.max_level(), Some(Level::ERROR));
+
+        logs.forget(&first);
+        logs.forget(&second);
+        assert_eq!(logs.max_level(), None);
+    }
+
+    #[test]
+    fn test_level_names() {
+        assert_eq!(serde_json::from_value::<LoggingLevel>(json!("warning")).unwrap(), LoggingLevel::Warning);
+        assert!(serde_json::from_value::<LoggingLevel>(json!("verbose")).is_err());
+    }
+}
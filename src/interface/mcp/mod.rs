@@ -1 +1,7 @@
+pub mod auth;
+pub mod http;
+pub mod logging;
+mod resources;
 pub mod server;
+pub mod session;
+mod tools;
@@ -0,0 +1,296 @@
+use crate::domain::fewshot::{categories, select_examples};
+use crate::domain::history::EnhancementHistory;
+use crate::interface::mcp::auth::Identity;
+use crate::interface::mcp::server::Notifier;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use thiserror::Error;
+use tracing::debug;
+
+/// Every enhancement the client has recorded, newest first; the one resource that changes
+pub(super) const HISTORY_URI: &str = "anytra://history";
+const HISTORY_PREFIX: &str = "anytra://history/";
+const EXAMPLES_PREFIX: &str = "anytra://examples/";
+const JSON: &str = "application/json";
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub(super) enum ResourceError {
+    #[error("unknown resource: {0}")]
+    Unknown(String),
+}
+
+/// Concrete resources for `resources/list`; history entries are those of `owner`
+pub(super) fn list(history: &EnhancementHistory, owner: Option<&Identity>) -> Vec<Value> {
+    let mut resources = vec![json!({
+        "uri": HISTORY_URI,
+        "name": "history",
+        "description": "Recent enhancements, newest first",
+        "mimeType": JSON
+    })];
+    resources.extend(history.list(client(owner)).iter().map(|entry| json!({
+        "uri": history_uri(entry.id),
+        "name": format!("history-{}", entry.id),
+        "description": summary(&entry.prompt),
+        "mimeType": JSON
+    })));
+    resources.extend(categories().into_iter().map(|category| json!({
+        "uri": format!("{}{}", EXAMPLES_PREFIX, category),
+        "name": format!("examples-{}", category),
+        "description": format!("Few-shot examples for {} prompts, best first", category),
+        "mimeType": JSON
+    })));
+    resources
+}
+
+/// Parameterized resources for `resources/templates/list`
+pub(super) fn templates() -> Vec<Value> {
+    vec![
+        json!({
+            "uriTemplate": format!("{}{{id}}", HISTORY_PREFIX),
+            "name": "history-entry",
+            "description": "One past enhancement: the prompt, options and result",
+            "mimeType": JSON
+        }),
+        json!({
+            "uriTemplate": format!("{}{{category}}", EXAMPLES_PREFIX),
+            "name": "examples",
+            "description": "Few-shot examples of one category, best first",
+            "mimeType": JSON
+        }),
+    ]
+}
+
+/// The `contents` of `resources/read`. History entries of other clients are
+/// unknown resources, so their ids give nothing away.
+pub(super) fn read(history: &EnhancementHistory, owner: Option<&Identity>, uri: &str) -> Result<Vec<Value>, ResourceError> {
+    let unknown = || ResourceError::Unknown(uri.to_string());
+    let body = if uri == HISTORY_URI {
+        json!(history.list(client(owner)))
+    } else if let Some(id) = uri.strip_prefix(HISTORY_PREFIX) {
+        let entry = id.parse().ok().and_then(|id| history.get(id, client(owner))).ok_or_else(unknown)?;
+        json!(entry)
+    } else if let Some(category) = uri.strip_prefix(EXAMPLES_PREFIX) {
+        let examples = select_examples(category, usize::MAX);
+        if examples.is_empty() {
+            return Err(unknown());
+        }
+        json!(examples)
+    } else {
+        return Err(unknown());
+    };
+    let text = serde_json::to_string_pretty(&body).expect("resource bodies serialize");
+    Ok(vec![json!({ "uri": uri, "mimeType": JSON, "text": text })])
+}
+
+pub(super) fn history_uri(id: u64) -> String {
+    format!("{}{}", HISTORY_PREFIX, id)
+}
+
+/// Whose history to use; everyone shares one when the server has no authentication
+fn client(owner: Option<&Identity>) -> Option<&str> {
+    owner.map(|identity| identity.client.as_str())
+}
+
+fn summary(prompt: &str) -> String {
+    const MAX_CHARS: usize = 80;
+    let line = prompt.lines().next().unwrap_or_default();
+    match line.char_indices().nth(MAX_CHARS) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line.to_string(),
+    }
+}
+
+/// Sessions that asked for `notifications/resources/updated`, by resource URI,
+/// and sessions that listed resources and so are told when the list changes.
+/// Each session is represented by the notifier of its server-initiated messages
+/// and only hears about changes made by its own client; sessions that have gone
+/// away are dropped the next time they would be notified.
+#[derive(Default)]
+pub(super) struct Subscriptions {
+    by_uri: Mutex<HashMap<String, Vec<Subscriber>>>,
+    listing: Mutex<Vec<Subscriber>>,
+}
+
+struct Subscriber {
+    owner: Option<Identity>,
+    session: Notifier,
+}
+
+impl Subscriber {
+    fn new(session: &Notifier, owner: Option<&Identity>) -> Self {
+        Self { owner: owner.cloned(), session: session.clone() }
+    }
+}
+
+/// Add `session` unless it is there already
+fn add(subscribers: &mut Vec<Subscriber>, session: &Notifier, owner: Option<&Identity>) {
+    if !subscribers.iter().any(|s| s.session.same_channel(session)) {
+        subscribers.push(Subscriber::new(session, owner));
+    }
+}
+
+/// Send to the live subscribers of `owner`, dropping those that have gone away
+fn notify(subscribers: &mut Vec<Subscriber>, owner: Option<&Identity>, method: &str, params: Value) -> usize {
+    subscribers.retain(|s| !s.session.is_closed());
+    let mut notified = 0;
+    for subscriber in subscribers.iter().filter(|s| s.owner.as_ref() == owner) {
+        subscriber.session.notify(method, params.clone());
+        notified += 1;
+    }
+    notified
+}
+
+impl Subscriptions {
+    pub(super) fn subscribe(&self, uri: &str, session: &Notifier, owner: Option<&Identity>) {
+        add(self.by_uri.lock().unwrap().entry(uri.to_string()).or_default(), session, owner);
+    }
+
+    pub(super) fn unsubscribe(&self, uri: &str, session: &Notifier) {
+        if let Some(sessions) = self.by_uri.lock().unwrap().get_mut(uri) {
+            sessions.retain(|s| !s.session.same_channel(session));
+        }
+    }
+
+    /// Tell the sessions of `owner` subscribed to `uri` that it changed
+    pub(super) fn updated(&self, uri: &str, owner: Option<&Identity>) {
+        let mut by_uri = self.by_uri.lock().unwrap();
+        let Some(sessions) = by_uri.get_mut(uri) else { return };
+        let subscribers = notify(sessions, owner, "notifications/resources/updated", json!({ "uri": uri }));
+        debug!(%uri, subscribers, "resource updated");
+    }
+
+    /// Tell `session` whenever the resources its client can list change
+    pub(super) fn listen(&self, session: &Notifier, owner: Option<&Identity>) {
+        add(&mut self.listing.lock().unwrap(), session, owner);
+    }
+
+    /// Tell the listening sessions of `owner` that its resource list changed
+    pub(super) fn list_changed(&self, owner: Option<&Identity>) {
+        let listeners = notify(&mut self.listing.lock().unwrap(), owner, "notifications/resources/list_changed", json!({}));
+        debug!(listeners, "resource list changed");
+    }
+
+    /// Drop every subscription of a session that has ended
+    pub(super) fn forget(&self, session: &Notifier) {
+        self.by_uri.lock().unwrap().retain(|_, sessions| {
+            sessions.retain(|s| !s.session.same_channel(session));
+            !sessions.is_empty()
+        });
+        self.listing.lock().unwrap().retain(|s| !s.session.same_channel(session));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::{EnhancedPrompt, EnhancementOptions};
+
+    fn history() -> EnhancementHistory {
+        let history = EnhancementHistory::default();
+        let result = EnhancedPrompt { text: "Write a haiku about rain".into(), ..Default::default() };
+        history.record(None, "haiku about rain", &EnhancementOptions::default(), &result);
+        history
+    }
+
+    #[test]
+    fn test_list_includes_history_and_examples() {
+        let uris: Vec<_> = list(&history(), None).iter().map(|r| r["uri"].as_str().unwrap().to_string()).collect();
+
+        assert_eq!(uris, vec!["anytra://history", "anytra://history/1", "anytra://examples/code", "anytra://examples/definition", "anytra://examples/explanation"]);
+    }
+
+    #[test]
+    fn test_read_history_entry() {
+        let contents = read(&history(), None, "anytra://history/1").unwrap();
+
+        assert_eq!(contents[0]["mimeType"], "application/json");
+        let entry: Value = serde_json::from_str(contents[0]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(entry["prompt"], "haiku about rain");
+        assert_eq!(entry["result"]["text"], "Write a haiku about rain");
+    }
+
+    #[test]
+    fn test_read_examples_best_first() {
+        let contents = read(&history(), None, "anytra://examples/code").unwrap();
+
+        let examples: Value = serde_json::from_str(contents[0]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(examples[0]["quality_score"], json!(0.95f32));
+        assert_eq!(examples.as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_read_unknown_resources() {
+        let history = history();
+
+        for uri in ["anytra://history/2", "anytra://history/x", "anytra://examples/poetry", "file:///etc/passwd"] {
+            assert_eq!(read(&history, None, uri).unwrap_err(), ResourceError::Unknown(uri.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_history_of_other_clients_is_unknown() {
+        let history = EnhancementHistory::default();
+        let alice = Identity { client: "alice".into() };
+        let bob = Identity { client: "bob".into() };
+        history.record(Some("alice"), "haiku about rain", &EnhancementOptions::default(), &EnhancedPrompt::default());
+
+        assert!(read(&history, Some(&alice), "anytra://history/1").is_ok());
+        assert!(read(&history, Some(&bob), "anytra://history/1").is_err());
+        assert_eq!(read(&history, Some(&bob), HISTORY_URI).unwrap()[0]["text"], "[]");
+        assert!(!list(&history, Some(&bob)).iter().any(|r| r["uri"] == "anytra://history/1"));
+    }
+
+    #[test]
+    fn test_summary_truncates_long_prompts() {
+        assert_eq!(summary("short\nsecond line"), "short");
+        assert_eq!(summary(&"é".repeat(100)).chars().count(), 81);
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_are_notified_until_they_unsubscribe() {
+        let subscriptions = Subscriptions::default();
+        let (session, mut outbound) = Notifier::channel();
+        subscriptions.subscribe(HISTORY_URI, &session, None);
+        subscriptions.subscribe(HISTORY_URI, &session, None);
+
+        subscriptions.updated(HISTORY_URI, None);
+        subscriptions.updated("anytra://examples/code", None);
+        subscriptions.unsubscribe(HISTORY_URI, &session);
+        subscriptions.updated(HISTORY_URI, None);
+        subscriptions.subscribe("anytra://history/1", &session, None);
+        subscriptions.forget(&session);
+        drop(session);
+
+        let message: Value = serde_json::from_str(&outbound.recv().await.unwrap()).unwrap();
+        assert_eq!(message["method"], "notifications/resources/updated");
+        assert_eq!(message["params"]["uri"], "anytra://history");
+        assert!(outbound.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_changes_only_reach_sessions_of_the_same_client() {
+        let subscriptions = Subscriptions::default();
+        let alice = Identity { client: "alice".into() };
+        let bob = Identity { client: "bob".into() };
+        let (alice_session, mut alice_outbound) = Notifier::channel();
+        let (bob_session, mut bob_outbound) = Notifier::channel();
+        for (session, owner) in [(&alice_session, &alice), (&bob_session, &bob)] {
+            subscriptions.subscribe(HISTORY_URI, session, Some(owner));
+            subscriptions.listen(session, Some(owner));
+        }
+
+        subscriptions.updated(HISTORY_URI, Some(&alice));
+        subscriptions.list_changed(Some(&alice));
+        subscriptions.forget(&alice_session);
+        subscriptions.list_changed(Some(&alice));
+        drop((subscriptions, alice_session, bob_session));
+
+        let update: Value = serde_json::from_str(&alice_outbound.recv().await.unwrap()).unwrap();
+        assert_eq!(update["method"], "notifications/resources/updated");
+        let changed: Value = serde_json::from_str(&alice_outbound.recv().await.unwrap()).unwrap();
+        assert_eq!(changed["method"], "notifications/resources/list_changed");
+        assert!(alice_outbound.recv().await.is_none());
+        assert!(bob_outbound.recv().await.is_none());
+    }
+}
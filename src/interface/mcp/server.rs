@@ -1,12 +1,24 @@
-use crate::domain::models::{EnhancedPrompt, EnhancementOptions, Prompt};
+use crate::domain::history::EnhancementHistory;
+use crate::domain::llm::LLMError;
+use crate::domain::templates::PromptCatalog;
+use crate::domain::thinking_store::ThinkingStore;
+use crate::interface::mcp::auth::Identity;
+use crate::interface::mcp::logging::{ClientLogs, LoggingLevel};
+use crate::interface::mcp::resources::{self, Subscriptions};
+use crate::interface::mcp::session::{negotiate_protocol_version, Session, SessionState, SUPPORTED_PROTOCOL_VERSIONS};
+use crate::interface::mcp::tools::{ThinkingSessions, ToolContext, ToolError, ToolRegistry};
 use crate::usecases::enhance_prompt::EnhancePrompt;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
-use tokio::io::{self, AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::io::{self, AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
 use tokio::select;
-use tokio::time::sleep;
-use tracing::{debug, error, info};
+use tokio::sync::{mpsc, Semaphore};
+use tokio::task::{AbortHandle, JoinError, JoinSet};
+use tokio::time::timeout;
+use tracing::{debug, error, info, warn, Instrument, Span};
 
 #[derive(Debug, Deserialize)]
 struct JsonRpcRequest {
@@ -23,7 +35,7 @@ struct JsonRpcRequest {
 fn default_jsonrpc() -> String { "2.0".into() }
 
 #[derive(Debug, Serialize)]
-struct JsonRpcResponse {
+pub(super) struct JsonRpcResponse {
     jsonrpc: &'static str,
     #[serde(skip_serializing_if = "Option::is_none")]
     id: Option<Value>,
@@ -40,13 +52,18 @@ struct JsonRpcError {
     #[serde(skip_serializing_if = "Option::is_none")]
     data: Option<Value>,
 }
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct ToolDescription {
-    name: String,
-    description: String,
-    input_schema: Value,
-}
+
+/// Server-defined JSON-RPC error codes for typed provider failures
+const UNAUTHORIZED: i32 = -32001;
+const RATE_LIMITED: i32 = -32002;
+const CONTEXT_LENGTH_EXCEEDED: i32 = -32003;
+const CONTENT_FILTERED: i32 = -32004;
+const TIMEOUT: i32 = -32005;
+const MODEL_NOT_FOUND: i32 = -32006;
+
+/// Server-defined JSON-RPC error codes for HTTP access control
+pub(super) const UNAUTHENTICATED: i32 = -32010;
+pub(super) const FORBIDDEN: i32 = -32011;
 
 #[derive(Debug, Deserialize)]
 struct ToolCallParams {
@@ -56,54 +73,177 @@ struct ToolCallParams {
 }
 
 #[derive(Debug, Deserialize)]
-struct EnhanceArgs {
-    prompt: String,
-    #[serde(default)] goal: Option<String>,
-    #[serde(default)] style: Option<String>,
-    #[serde(default)] tone: Option<String>,
-    #[serde(default)] level: Option<u8>,
-    #[serde(default)] audience: Option<String>,
-    #[serde(default)] language: Option<String>,
-    #[serde(default)] enable_sequential_thinking: Option<bool>,
-    #[serde(default)] thought_count: Option<u32>,
-}
-
-pub async fn run_stdio_server(usecase: EnhancePrompt, shutdown_timeout: Duration) -> anyhow::Result<()> {
-    let mut stdout = io::stdout();
-    let stdin = io::stdin();
-    let mut reader = BufReader::new(stdin).lines();
+struct PromptGetParams {
+    name: String,
+    #[serde(default)]
+    arguments: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResourceParams {
+    uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SetLevelParams {
+    level: LoggingLevel,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CancelledParams {
+    request_id: Value,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+/// Queues outgoing messages for the single stdout writer, so notifications sent
+/// while a request runs are written before its response
+#[derive(Clone, Default)]
+pub(super) struct Notifier {
+    tx: Option<mpsc::UnboundedSender<String>>,
+}
+
+impl Notifier {
+    pub(super) fn channel() -> (Self, mpsc::UnboundedReceiver<String>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (Self { tx: Some(tx) }, rx)
+    }
+
+    pub(super) fn send<T: Serialize>(&self, message: &T) {
+        let Some(ref tx) = self.tx else { return };
+        match serde_json::to_string(message) {
+            Ok(line) => { let _ = tx.send(line); }
+            Err(e) => error!(error = %e, "failed to serialize outgoing message"),
+        }
+    }
+
+    pub(super) fn notify(&self, method: &str, params: Value) {
+        self.send(&json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
 
+    /// Whether both deliver to the same place; detached notifiers never do
+    pub(super) fn same_channel(&self, other: &Notifier) -> bool {
+        matches!((&self.tx, &other.tx), (Some(a), Some(b)) if a.same_channel(b))
+    }
+
+    /// Whether nobody will read what is sent any more
+    pub(super) fn is_closed(&self) -> bool {
+        match self.tx {
+            Some(ref tx) => tx.is_closed(),
+            None => true,
+        }
+    }
+}
+
+/// Transport-independent server settings
+#[derive(Debug, Clone)]
+pub struct ServerOptions {
+    pub shutdown_timeout: Duration,
+    /// Maximum number of `tools/call` requests processed at the same time
+    pub max_concurrent_requests: usize,
+}
+
+/// What request handlers work with, shared by every connection
+pub struct ServerState {
+    pub usecase: EnhancePrompt,
+    /// templates served through `prompts/list` and `prompts/get`
+    pub prompts: PromptCatalog,
+    /// completed enhancements, served as `anytra://history` resources
+    pub history: EnhancementHistory,
+    pub(super) subscriptions: Subscriptions,
+    /// sessions receiving log events as `notifications/message`
+    pub logs: Arc<ClientLogs>,
+    /// what `tools/list` offers and `tools/call` dispatches to
+    pub(super) tools: ToolRegistry,
+    /// the `sequential_thinking` sessions of every client
+    pub(super) thinking: ThinkingSessions,
+}
+
+impl ServerState {
+    pub fn new(usecase: EnhancePrompt) -> Self {
+        Self {
+            usecase,
+            prompts: PromptCatalog::builtin(),
+            history: EnhancementHistory::default(),
+            subscriptions: Subscriptions::default(),
+            logs: Arc::default(),
+            tools: ToolRegistry::builtin(),
+            thinking: ThinkingSessions::default(),
+        }
+    }
+
+    /// Use the sink that `init_tracing` forwards events to
+    pub fn with_logs(mut self, logs: Arc<ClientLogs>) -> Self {
+        self.logs = logs;
+        self
+    }
+
+    pub fn with_prompts(mut self, prompts: PromptCatalog) -> Self {
+        self.prompts = prompts;
+        self
+    }
+
+    /// Keep `sequential_thinking` sessions in `store`, dropping those untouched
+    /// for longer than `ttl`; sessions that already expired are dropped now
+    pub fn with_thinking_store(mut self, store: Box<dyn ThinkingStore>, ttl: Option<Duration>) -> Self {
+        self.thinking = ThinkingSessions::new(store, ttl);
+        match self.thinking.expire() {
+            Ok(0) => {}
+            Ok(expired) => info!(expired, "removed expired thinking sessions"),
+            Err(e) => warn!(error = %e, "could not remove expired thinking sessions"),
+        }
+        self
+    }
+}
+
+pub async fn run_stdio_server(state: Arc<ServerState>, options: ServerOptions) -> anyhow::Result<()> {
     info!("MCP stdio server ready");
-    let shutting_down = false;
+    serve(io::stdin(), io::stdout(), state, options).await
+}
+
+/// Serve newline-delimited JSON-RPC from `input`, writing responses and
+/// notifications to `output` as they complete
+async fn serve<R, W>(input: R, output: W, state: Arc<ServerState>, options: ServerOptions) -> anyhow::Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let mut reader = BufReader::new(input).lines();
+
+    let (notifier, mut outbound) = Notifier::channel();
+    let writer = tokio::spawn(async move {
+        let mut output = output;
+        while let Some(line) = outbound.recv().await {
+            output.write_all(line.as_bytes()).await?;
+            output.write_all(b"\n").await?;
+            output.flush().await?;
+        }
+        Ok::<_, io::Error>(())
+    });
+
+    let dispatcher = Dispatcher::new(state, options.max_concurrent_requests);
+    let mut connection = Connection::new(notifier.clone());
 
-    loop {
+    while connection.session.state() != SessionState::ShuttingDown {
         select! {
             line = reader.next_line() => {
                 match line {
                     Ok(Some(line)) => {
                         if line.trim().is_empty() { continue; }
                         debug!(%line, "stdin line");
-                        match serde_json::from_str::<JsonRpcRequest>(&line) {
-                            Ok(req) => {
-                                let resp = handle_request(&usecase, req).await;
-                                let bytes = serde_json::to_vec(&resp)?;
-                                stdout.write_all(&bytes).await?;
-                                stdout.write_all(b"\n").await?;
-                                stdout.flush().await?;
-                                if shutting_down { break; }
-                            }
-                            Err(e) => {
-                                let resp = JsonRpcResponse {
-                                    jsonrpc: "2.0",
-                                    id: None,
-                                    result: None,
-                                    error: Some(JsonRpcError { code: -32700, message: format!("parse error: {}", e), data: None }),
-                                };
-                                let bytes = serde_json::to_vec(&resp)?;
-                                stdout.write_all(&bytes).await?;
-                                stdout.write_all(b"\n").await?;
-                                stdout.flush().await?;
+                        match serde_json::from_str::<Value>(&line) {
+                            Ok(message) => {
+                                if let Some(pending) = connection.accept(message, &dispatcher, &notifier) {
+                                    let notifier = notifier.clone();
+                                    connection.in_flight.spawn_untracked(async move {
+                                        if let Some(answer) = pending.finish().await {
+                                            notifier.send(&answer);
+                                        }
+                                    });
+                                }
                             }
+                            Err(e) => notifier.send(&parse_error(&e)),
                         }
                     }
                     Ok(None) => { // EOF
@@ -115,94 +255,398 @@ pub async fn run_stdio_server(usecase: EnhancePrompt, shutdown_timeout: Duration
                     }
                 }
             }
+            // Reap finished requests so the set does not grow for the whole session
+            Some(joined) = connection.in_flight.join_next(), if !connection.in_flight.is_empty() => {
+                if let Err(e) = joined {
+                    if !e.is_cancelled() {
+                        error!(error = %e, "request task failed");
+                    }
+                }
+            }
         }
     }
 
-    if shutting_down {
-        sleep(shutdown_timeout).await;
+    // Let requests that are still running write their responses, but only for so long
+    let in_flight = &mut connection.in_flight;
+    if timeout(options.shutdown_timeout, in_flight.drain()).await.is_err() {
+        warn!(remaining = in_flight.len(), "shutdown timeout elapsed, aborting in-flight requests");
+        in_flight.abort_all();
+        in_flight.drain().await;
     }
 
+    // Closing the queue lets the writer flush what is left and exit
+    dispatcher.end_session(&connection);
+    drop(connection);
+    drop(notifier);
+    writer.await??;
+
     Ok(())
 }
 
-async fn handle_request(usecase: &EnhancePrompt, req: JsonRpcRequest) -> JsonRpcResponse {
-    match req.method.as_str() {
-        "initialize" | "mcp/initialize" => JsonRpcResponse {
-            jsonrpc: "2.0",
-            id: req.id,
-            result: Some(json!({
-                "protocolVersion": "2024-11-05",
-                "capabilities": {
-                    "tools": { "list": true, "call": true }
-                },
-                "serverInfo": { "name": "anytra", "version": env!("CARGO_PKG_VERSION") }
-            })),
-            error: None,
-        },
+/// Protocol state of one client: its lifecycle, the requests it has running and
+/// where messages not tied to a request go, such as resource updates.
+/// A stdio server has one; the HTTP transport keeps one per `Mcp-Session-Id`.
+#[derive(Default)]
+pub(super) struct Connection {
+    pub(super) session: Session,
+    pub(super) in_flight: InFlight,
+    pub(super) outbound: Notifier,
+    /// the HTTP session id, which scopes the log events the client is sent
+    pub(super) id: Option<String>,
+    /// the authenticated client that opened it, whose history it sees
+    pub(super) owner: Option<Identity>,
+}
 
-        "tools/list" => {
-            let tool = ToolDescription {
-                name: "enhance_prompt".into(),
-                description: "Enhance a user prompt for clarity, constraints, and specificity".into(),
-                input_schema: json!({
-                    "$schema": "http://json-schema.org/draft-07/schema#",
-                    "type": "object",
-                    "required": ["prompt"],
-                    "properties": {
-                        "prompt": { "type": "string", "description": "The raw prompt to enhance" },
-                        "goal": { "type": ["string", "null"], "description": "Desired outcome" },
-                        "style": { "type": ["string", "null"], "description": "Writing style (concise, formal, etc.)" },
-                        "tone": { "type": ["string", "null"], "description": "Tone (neutral, persuasive, etc.)" },
-                        "level": { "type": ["integer", "null"], "minimum": 1, "maximum": 5, "description": "Enhancement strength 1-5" },
-                        "audience": { "type": ["string", "null"], "description": "Target audience" },
-                        "language": { "type": ["string", "null"], "description": "Output language, e.g., en, id" },
-                        "enable_sequential_thinking": { "type": ["boolean", "null"], "description": "Enable sequential thinking for step-by-step reasoning" },
-                        "thought_count": { "type": ["integer", "null"], "description": "Number of thoughts to generate (default: 3)" }
-                    }
-                }),
-            };
-            JsonRpcResponse { jsonrpc: "2.0", id: req.id, result: Some(json!({ "tools": [tool] })), error: None }
+impl Connection {
+    pub(super) fn new(outbound: Notifier) -> Self {
+        Self { outbound, ..Self::default() }
+    }
+
+    /// Accept one incoming message, a single request or notification or a batch.
+    /// Notifications are applied in arrival order and never answered; requests run
+    /// on the dispatcher once the session allows them, sending progress through
+    /// `notifier`. Returns the responses still owed, or `None` when there are none.
+    pub(super) fn accept(&mut self, message: Value, dispatcher: &Dispatcher, notifier: &Notifier) -> Option<Pending> {
+        self.in_flight.reap();
+        let (tx, responses) = mpsc::unbounded_channel();
+        let reply = Reply { notifier: notifier.clone(), session: self.outbound.clone(), session_id: self.id.clone(), owner: self.owner.clone(), tx };
+
+        let (batch, answered) = match message {
+            Value::Array(batch) if batch.is_empty() => {
+                reply.send(invalid_request("empty batch".into()));
+                (false, true)
+            }
+            Value::Array(batch) => {
+                let mut answered = false;
+                for message in batch {
+                    answered |= self.accept_one(message, true, dispatcher, &reply);
+                }
+                (true, answered)
+            }
+            message => (false, self.accept_one(message, false, dispatcher, &reply)),
+        };
+
+        // The answer is complete once every request task has dropped its copy of `reply`
+        answered.then_some(Pending { responses, batch })
+    }
+
+    /// Returns whether a response will be sent for the message
+    fn accept_one(&mut self, message: Value, in_batch: bool, dispatcher: &Dispatcher, reply: &Reply) -> bool {
+        if message.get("method").is_none() && (message.get("result").is_some() || message.get("error").is_some()) {
+            // This server never sends requests, so there is nothing to match a client response to
+            debug!(id = ?message.get("id"), "ignoring response from client");
+            return false;
+        }
+        let req = match serde_json::from_value::<JsonRpcRequest>(message) {
+            Ok(req) => req,
+            Err(e) => {
+                reply.send(invalid_request(format!("invalid request: {}", e)));
+                return true;
+            }
+        };
+
+        if req.id.is_none() {
+            self.handle_notification(req);
+            return false;
         }
+        if in_batch && matches!(req.method.as_str(), "initialize" | "mcp/initialize") {
+            reply.send(JsonRpcResponse { id: req.id, ..invalid_request("initialize must not be part of a batch".into()) });
+            return true;
+        }
+        match self.session.accept_request(&req.method) {
+            Ok(()) => self.in_flight.spawn(dispatcher.clone(), req, reply.clone()),
+            Err(e) => reply.send(JsonRpcResponse { id: req.id, ..invalid_request(e.to_string()) }),
+        }
+        true
+    }
+
+    fn handle_notification(&mut self, req: JsonRpcRequest) {
+        self.session.accept_notification(&req.method);
+        match req.method.as_str() {
+            "notifications/cancelled" => match serde_json::from_value::<CancelledParams>(req.params) {
+                Ok(params) => {
+                    let cancelled = self.in_flight.cancel(&params.request_id);
+                    debug!(request_id = %params.request_id, reason = ?params.reason, cancelled, "cancellation requested");
+                }
+                Err(e) => debug!(error = %e, "ignoring malformed cancellation"),
+            },
+            "notifications/initialized" | "exit" => debug!(method = %req.method, state = ?self.session.state(), "lifecycle notification"),
+            method => debug!(%method, "ignoring notification"),
+        }
+    }
+}
+
+/// Responses owed for one incoming message
+pub(super) struct Pending {
+    responses: mpsc::UnboundedReceiver<JsonRpcResponse>,
+    batch: bool,
+}
+
+impl Pending {
+    /// Wait for every response. A batch is answered with an array, leaving out
+    /// cancelled requests; `None` when nothing is left to send.
+    pub(super) async fn finish(mut self) -> Option<Value> {
+        let mut collected = Vec::new();
+        while let Some(resp) = self.responses.recv().await {
+            collected.push(resp);
+        }
+        if self.batch {
+            (!collected.is_empty()).then(|| json!(collected))
+        } else {
+            collected.pop().map(|resp| json!(resp))
+        }
+    }
+}
+
+pub(super) fn parse_error(e: &serde_json::Error) -> JsonRpcResponse {
+    JsonRpcResponse { jsonrpc: "2.0", id: None, result: None, error: Some(JsonRpcError { code: -32700, message: format!("parse error: {}", e), data: None }) }
+}
+
+pub(super) fn invalid_request(message: String) -> JsonRpcResponse {
+    error_response(-32600, message)
+}
+
+/// Error response that cannot be matched to a request id
+pub(super) fn error_response(code: i32, message: String) -> JsonRpcResponse {
+    JsonRpcResponse { jsonrpc: "2.0", id: None, result: None, error: Some(JsonRpcError { code, message, data: None }) }
+}
+
+/// Requests that are still running. Tool calls are indexed by their JSON-RPC id
+/// so `notifications/cancelled` can abort them: dropping the task drops the
+/// provider request, any retry wait and the thinking loop, and no response is
+/// written.
+#[derive(Default)]
+pub(super) struct InFlight {
+    tasks: JoinSet<()>,
+    tool_calls: HashMap<String, AbortHandle>,
+}
+
+impl InFlight {
+    fn spawn(&mut self, dispatcher: Dispatcher, req: JsonRpcRequest, reply: Reply) {
+        let key = match req.id {
+            Some(ref id) if req.method == "tools/call" => Some(request_key(id)),
+            _ => None,
+        };
+        // Keep the caller's span (e.g. the HTTP client identity) on the request task
+        let handle = self.tasks.spawn(dispatcher.dispatch(req, reply).instrument(Span::current()));
+        if let Some(key) = key {
+            self.tool_calls.insert(key, handle);
+        }
+    }
+
+    /// Run work that must finish before shutdown but cannot be cancelled by id
+    fn spawn_untracked(&mut self, task: impl std::future::Future<Output = ()> + Send + 'static) {
+        self.tasks.spawn(task);
+    }
+
+    /// Abort the tool call with this id; false if it is unknown or already finished
+    fn cancel(&mut self, id: &Value) -> bool {
+        match self.tool_calls.remove(&request_key(id)) {
+            Some(handle) => {
+                handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    async fn join_next(&mut self) -> Option<Result<(), JoinError>> {
+        let joined = self.tasks.join_next_with_id().await?;
+        Some(self.forget(joined))
+    }
+
+    /// Drop finished tasks without waiting, for owners that do not poll `join_next`
+    pub(super) fn reap(&mut self) {
+        while let Some(joined) = self.tasks.try_join_next_with_id() {
+            if let Err(e) = self.forget(joined) {
+                if !e.is_cancelled() {
+                    error!(error = %e, "request task failed");
+                }
+            }
+        }
+    }
+
+    fn forget(&mut self, joined: Result<(tokio::task::Id, ()), JoinError>) -> Result<(), JoinError> {
+        let task = match joined {
+            Ok((task, _)) => task,
+            Err(ref e) => e.id(),
+        };
+        self.tool_calls.retain(|_, handle| handle.id() != task);
+        joined.map(|_| ())
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Wait for every request to finish
+    async fn drain(&mut self) {
+        while self.join_next().await.is_some() {}
+    }
+
+    pub(super) fn abort_all(&mut self) {
+        self.tasks.abort_all();
+    }
+}
+
+/// Map key for a JSON-RPC id; the serialized form keeps `1` and `"1"` apart
+fn request_key(id: &Value) -> String {
+    id.to_string()
+}
+
+/// Runs each request on its own task and sends the response when it completes,
+/// so responses may arrive in a different order than the requests (clients match
+/// them by id). Tool calls are limited to a fixed number at a time across all
+/// connections; cheap methods such as `ping` never wait behind them.
+#[derive(Clone)]
+pub(super) struct Dispatcher {
+    state: Arc<ServerState>,
+    tool_calls: Arc<Semaphore>,
+}
+
+impl Dispatcher {
+    pub(super) fn new(state: Arc<ServerState>, max_concurrent_requests: usize) -> Self {
+        Self { state, tool_calls: Arc::new(Semaphore::new(max_concurrent_requests.max(1))) }
+    }
+
+    async fn dispatch(self, req: JsonRpcRequest, reply: Reply) {
+        let _permit = match req.method.as_str() {
+            "tools/call" => Some(self.tool_calls.clone().acquire_owned().await.expect("semaphore is never closed")),
+            _ => None,
+        };
+        let resp = match req.method.as_str() {
+            "resources/list" | "resources/subscribe" | "resources/unsubscribe" | "logging/setLevel" => handle_session_request(&self.state, req, &reply),
+            _ => handle_request(&self.state, req, &reply.notifier, reply.owner.as_ref()).await,
+        };
+        reply.send(resp);
+    }
+
+    /// Release what the server keeps for a connection that has ended
+    pub(super) fn end_session(&self, connection: &Connection) {
+        self.state.subscriptions.forget(&connection.outbound);
+        self.state.logs.forget(&connection.outbound);
+    }
+}
+
+/// Where a request reports progress and delivers its response
+#[derive(Clone)]
+struct Reply {
+    notifier: Notifier,
+    /// the connection's channel for messages not tied to this request
+    session: Notifier,
+    session_id: Option<String>,
+    owner: Option<Identity>,
+    tx: mpsc::UnboundedSender<JsonRpcResponse>,
+}
+
+impl Reply {
+    fn send(&self, resp: JsonRpcResponse) {
+        let _ = self.tx.send(resp);
+    }
+}
+
+async fn handle_request(state: &ServerState, req: JsonRpcRequest, notifier: &Notifier, owner: Option<&Identity>) -> JsonRpcResponse {
+    match req.method.as_str() {
+        "initialize" | "mcp/initialize" => {
+            let requested = req.params.get("protocolVersion").and_then(Value::as_str);
+            let version = negotiate_protocol_version(requested);
+            if requested.is_some_and(|requested| requested != version) {
+                info!(requested, offered = version, supported = ?SUPPORTED_PROTOCOL_VERSIONS, "client asked for an unsupported protocol version");
+            }
+            JsonRpcResponse {
+                jsonrpc: "2.0",
+                id: req.id,
+                result: Some(json!({
+                    "protocolVersion": version,
+                    "capabilities": {
+                        "tools": { "list": true, "call": true },
+                        "prompts": { "listChanged": false },
+                        "resources": { "subscribe": true, "listChanged": true },
+                        "logging": {}
+                    },
+                    "serverInfo": { "name": "anytra", "version": env!("CARGO_PKG_VERSION") }
+                })),
+                error: None,
+            }
+        }
+
+        "tools/list" => JsonRpcResponse { jsonrpc: "2.0", id: req.id, result: Some(json!({ "tools": state.tools.list() })), error: None },
 
         "tools/call" => {
             let params: Result<ToolCallParams, _> = serde_json::from_value(req.params.clone());
             match params {
                 Ok(p) => {
-                    if p.name != "enhance_prompt" {
-                        return JsonRpcResponse { jsonrpc: "2.0", id: req.id, result: None, error: Some(JsonRpcError { code: -32601, message: format!("unknown tool: {}", p.name), data: None }) };
-                    }
-                    let args: Result<EnhanceArgs, _> = serde_json::from_value(p.arguments);
-                    match args {
-                        Ok(a) => {
-                            let opt = EnhancementOptions { goal: a.goal, style: a.style, tone: a.tone, level: a.level, audience: a.audience, language: a.language, enable_sequential_thinking: a.enable_sequential_thinking, thought_count: a.thought_count };
-                            let res = usecase.execute(Prompt { text: a.prompt }, opt).await;
-                            match res {
-                                Ok(EnhancedPrompt { text, rationale: _, .. }) => JsonRpcResponse {
-                                    jsonrpc: "2.0",
-                                    id: req.id,
-                                    result: Some(json!({
-                                        "content": [ { "type": "text", "text": text } ]
-                                    })),
-                                    error: None,
-                                },
-                                Err(e) => JsonRpcResponse {
-                                    jsonrpc: "2.0",
-                                    id: req.id,
-                                    result: Some(json!({
-                                        "content": [ { "type": "text", "text": format!("tool error: {}", e) } ],
-                                        "isError": true
-                                    })),
-                                    error: None,
-                                },
-                            }
-                        }
-                        Err(e) => JsonRpcResponse { jsonrpc: "2.0", id: req.id, result: None, error: Some(JsonRpcError { code: -32602, message: format!("invalid arguments: {}", e), data: None }) },
+                    let progress_token = req.params.get("_meta").and_then(|meta| meta.get("progressToken")).cloned();
+                    let context = ToolContext { notifier, progress_token, owner };
+                    match state.tools.call(state, &p.name, p.arguments, &context).await {
+                        Ok(result) => JsonRpcResponse { jsonrpc: "2.0", id: req.id, result: Some(result), error: None },
+                        Err(e @ ToolError::Unknown(_)) => JsonRpcResponse { jsonrpc: "2.0", id: req.id, result: None, error: Some(JsonRpcError { code: -32601, message: e.to_string(), data: None }) },
+                        Err(e @ ToolError::InvalidArguments(_)) => JsonRpcResponse { jsonrpc: "2.0", id: req.id, result: None, error: Some(JsonRpcError { code: -32602, message: e.to_string(), data: None }) },
+                        Err(ToolError::Failed(e)) => match e.downcast_ref::<LLMError>().and_then(llm_error) {
+                            Some(error) => JsonRpcResponse { jsonrpc: "2.0", id: req.id, result: None, error: Some(error) },
+                            None => JsonRpcResponse {
+                                jsonrpc: "2.0",
+                                id: req.id,
+                                result: Some(json!({
+                                    "content": [ { "type": "text", "text": format!("tool error: {}", e) } ],
+                                    "isError": true
+                                })),
+                                error: None,
+                            },
+                        },
                     }
                 }
                 Err(e) => JsonRpcResponse { jsonrpc: "2.0", id: req.id, result: None, error: Some(JsonRpcError { code: -32602, message: format!("invalid params: {}", e), data: None }) },
             }
         }
 
+        "prompts/list" => {
+            let prompts: Vec<Value> = state.prompts.list().map(|template| json!({
+                "name": template.name,
+                "description": template.description,
+                "arguments": template.arguments
+            })).collect();
+            JsonRpcResponse { jsonrpc: "2.0", id: req.id, result: Some(json!({ "prompts": prompts })), error: None }
+        }
+
+        "prompts/get" => {
+            let params: Result<PromptGetParams, _> = serde_json::from_value(req.params);
+            match params {
+                Ok(p) => match state.prompts.render(&p.name, &p.arguments) {
+                    Ok((template, text)) => JsonRpcResponse {
+                        jsonrpc: "2.0",
+                        id: req.id,
+                        result: Some(json!({
+                            "description": template.description,
+                            "messages": [ { "role": "user", "content": { "type": "text", "text": text } } ]
+                        })),
+                        error: None,
+                    },
+                    Err(e) => JsonRpcResponse { jsonrpc: "2.0", id: req.id, result: None, error: Some(JsonRpcError { code: -32602, message: e.to_string(), data: None }) },
+                },
+                Err(e) => JsonRpcResponse { jsonrpc: "2.0", id: req.id, result: None, error: Some(JsonRpcError { code: -32602, message: format!("invalid params: {}", e), data: None }) },
+            }
+        }
+
+        "resources/templates/list" => {
+            JsonRpcResponse { jsonrpc: "2.0", id: req.id, result: Some(json!({ "resourceTemplates": resources::templates() })), error: None }
+        }
+
+        "resources/read" => {
+            let params: Result<ResourceParams, _> = serde_json::from_value(req.params);
+            match params {
+                Ok(p) => match resources::read(&state.history, owner, &p.uri) {
+                    Ok(contents) => JsonRpcResponse { jsonrpc: "2.0", id: req.id, result: Some(json!({ "contents": contents })), error: None },
+                    Err(e) => JsonRpcResponse { jsonrpc: "2.0", id: req.id, result: None, error: Some(JsonRpcError { code: -32602, message: e.to_string(), data: None }) },
+                },
+                Err(e) => JsonRpcResponse { jsonrpc: "2.0", id: req.id, result: None, error: Some(JsonRpcError { code: -32602, message: format!("invalid params: {}", e), data: None }) },
+            }
+        }
+
         "ping" => JsonRpcResponse { jsonrpc: "2.0", id: req.id, result: Some(json!({"message": "pong"})), error: None },
 
         "shutdown" => JsonRpcResponse { jsonrpc: "2.0", id: req.id, result: Some(json!({"ok": true})), error: None },
@@ -211,32 +655,77 @@ async fn handle_request(usecase: &EnhancePrompt, req: JsonRpcRequest) -> JsonRpc
     }
 }
 
+/// `resources/list`, `resources/subscribe`, `resources/unsubscribe` and
+/// `logging/setLevel`, which act on the connection rather than only the shared
+/// state; a connection that lists resources is told when the list changes
+fn handle_session_request(state: &ServerState, req: JsonRpcRequest, reply: &Reply) -> JsonRpcResponse {
+    let invalid_params = |message: String| JsonRpcError { code: -32602, message, data: None };
+    let owner = reply.owner.as_ref();
+    let outcome = match req.method.as_str() {
+        "resources/list" => {
+            state.subscriptions.listen(&reply.session, owner);
+            Ok(json!({ "resources": resources::list(&state.history, owner) }))
+        }
+        "logging/setLevel" => serde_json::from_value::<SetLevelParams>(req.params)
+            .map_err(|e| invalid_params(format!("invalid params: {}", e)))
+            .map(|params| state.logs.set_level(&reply.session, reply.session_id.as_deref(), params.level))
+            .map(|()| json!({})),
+        method => match serde_json::from_value::<ResourceParams>(req.params) {
+            Err(e) => Err(invalid_params(format!("invalid params: {}", e))),
+            Ok(params) if method == "resources/unsubscribe" => {
+                state.subscriptions.unsubscribe(&params.uri, &reply.session);
+                Ok(json!({}))
+            }
+            Ok(params) => resources::read(&state.history, owner, &params.uri)
+                .map_err(|e| invalid_params(e.to_string()))
+                .map(|_| state.subscriptions.subscribe(&params.uri, &reply.session, owner))
+                .map(|()| json!({})),
+        },
+    };
+    match outcome {
+        Ok(result) => JsonRpcResponse { jsonrpc: "2.0", id: req.id, result: Some(result), error: None },
+        Err(error) => JsonRpcResponse { jsonrpc: "2.0", id: req.id, result: None, error: Some(error) },
+    }
+}
+
+/// JSON-RPC error for provider failures the client can act on; other failures
+/// are reported as an `isError` tool result
+fn llm_error(error: &LLMError) -> Option<JsonRpcError> {
+    let (code, kind) = match error {
+        LLMError::Unauthorized(_) => (UNAUTHORIZED, "unauthorized"),
+        LLMError::RateLimited { .. } => (RATE_LIMITED, "rate_limited"),
+        LLMError::ContextLengthExceeded(_) => (CONTEXT_LENGTH_EXCEEDED, "context_length_exceeded"),
+        LLMError::ContentFiltered(_) => (CONTENT_FILTERED, "content_filtered"),
+        LLMError::Timeout(_) => (TIMEOUT, "timeout"),
+        LLMError::ModelNotFound(_) => (MODEL_NOT_FOUND, "model_not_found"),
+        _ => return None,
+    };
+
+    let mut data = json!({ "kind": kind, "retryable": error.is_retryable() });
+    if let Some(detail) = error.provider_error() {
+        data["status"] = json!(detail.status);
+        if let Some(ref body) = detail.body {
+            data["providerError"] = body.clone();
+        }
+    }
+    if let LLMError::RateLimited { retry_after: Some(retry_after), .. } = error {
+        data["retryAfterSecs"] = json!(retry_after.as_secs());
+    }
+
+    Some(JsonRpcError { code, message: error.to_string(), data: Some(data) })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::llm::{LLMError, LLMProvider};
-    use crate::domain::models::{EnhancedPrompt, EnhancementOptions, Prompt};
-    use crate::infrastructure::config::{Config, OpenRouterConfig, SequentialThinkingConfig, LoggingConfig};
+    use crate::domain::llm::{LLMError, LLMProvider, ProviderError};
+    use crate::domain::models::{DegradationPolicy, EnhancedPrompt, EnhancementOptions, Prompt, TokenUsage};
+    use crate::infrastructure::config::{create_test_config, Config};
+    use crate::infrastructure::thinking_store::FileThinkingStore;
+    use crate::infrastructure::TempDir;
     use async_trait::async_trait;
     use serde_json::json;
-
-    // Helper function to create test config
-    fn create_test_config() -> Config {
-        Config {
-            openrouter: OpenRouterConfig {
-                api_key: "test-key".to_string(),
-                model: "test-model".to_string(),
-                referer: None,
-                title: None,
-            },
-            sequential_thinking: SequentialThinkingConfig {
-                default_enabled: false, // Disable for tests unless explicitly needed
-            },
-            logging: LoggingConfig {
-                level: "info".to_string(),
-            },
-        }
-    }
+    use std::sync::atomic::Ordering;
 
     struct MockProvider;
 
@@ -247,6 +736,7 @@ mod tests {
                 text: format!("Enhanced: {} - this is a longer text with enough words to pass validation", prompt.text),
                 rationale: Some("Test rationale".to_string()),
                 confidence: None,
+                ..Default::default()
             })
         }
     }
@@ -293,19 +783,6 @@ mod tests {
         assert!(error.data.is_some());
     }
 
-    #[test]
-    fn test_tool_description_creation() {
-        let tool = ToolDescription {
-            name: "enhance_prompt".to_string(),
-            description: "Enhance a prompt".to_string(),
-            input_schema: json!({"type": "object"}),
-        };
-
-        assert_eq!(tool.name, "enhance_prompt");
-        assert_eq!(tool.description, "Enhance a prompt");
-        assert!(tool.input_schema.is_object());
-    }
-
     #[test]
     fn test_tool_call_params_creation() {
         let params = ToolCallParams {
@@ -317,36 +794,11 @@ mod tests {
         assert!(params.arguments.is_object());
     }
 
-    #[test]
-    fn test_enhance_args_creation() {
-        let args = EnhanceArgs {
-            prompt: "Test prompt".to_string(),
-            goal: Some("Test goal".to_string()),
-            style: None,
-            tone: Some("professional".to_string()),
-            level: Some(3),
-            audience: None,
-            language: Some("en".to_string()),
-            enable_sequential_thinking: None,
-            thought_count: None,
-        };
-
-        assert_eq!(args.prompt, "Test prompt");
-        assert_eq!(args.goal.as_deref(), Some("Test goal"));
-        assert!(args.style.is_none());
-        assert_eq!(args.tone.as_deref(), Some("professional"));
-        assert_eq!(args.level, Some(3));
-        assert!(args.audience.is_none());
-        assert_eq!(args.language.as_deref(), Some("en"));
-        assert!(args.enable_sequential_thinking.is_none());
-        assert!(args.thought_count.is_none());
-    }
-
     #[tokio::test]
     async fn test_handle_initialize() {
         let provider = Box::new(MockProvider);
         let config = create_test_config();
-        let usecase = EnhancePrompt::new(provider, config);
+        let state = ServerState::new(EnhancePrompt::new(provider, config));
         let req = JsonRpcRequest {
             jsonrpc: "2.0".to_string(),
             id: Some(json!(1)),
@@ -354,7 +806,7 @@ mod tests {
             params: json!({}),
         };
 
-        let response = handle_request(&usecase, req).await;
+        let response = handle_request(&state, req, &Notifier::default(), None).await;
 
         assert_eq!(response.jsonrpc, "2.0");
         assert_eq!(response.id, Some(json!(1)));
@@ -372,7 +824,7 @@ mod tests {
     async fn test_handle_tools_list() {
         let provider = Box::new(MockProvider);
         let config = create_test_config();
-        let usecase = EnhancePrompt::new(provider, config);
+        let state = ServerState::new(EnhancePrompt::new(provider, config));
         let req = JsonRpcRequest {
             jsonrpc: "2.0".to_string(),
             id: Some(json!(2)),
@@ -380,7 +832,7 @@ mod tests {
             params: json!({}),
         };
 
-        let response = handle_request(&usecase, req).await;
+        let response = handle_request(&state, req, &Notifier::default(), None).await;
 
         assert_eq!(response.jsonrpc, "2.0");
         assert_eq!(response.id, Some(json!(2)));
@@ -390,8 +842,8 @@ mod tests {
         if let Some(result) = response.result {
             if let Some(tools) = result.get("tools") {
                 if let Some(tools_array) = tools.as_array() {
-                    assert_eq!(tools_array.len(), 1);
-                    if let Some(tool) = tools_array.get(0) {
+                    assert_eq!(tools_array.len(), 6);
+                    if let Some(tool) = tools_array.first() {
                         assert_eq!(tool.get("name").unwrap(), "enhance_prompt");
                         assert!(tool.get("description").is_some());
                         assert!(tool.get("inputSchema").is_some());
@@ -405,7 +857,7 @@ mod tests {
     async fn test_handle_tools_call_success() {
         let provider = Box::new(MockProvider);
         let config = create_test_config();
-        let usecase = EnhancePrompt::new(provider, config);
+        let state = ServerState::new(EnhancePrompt::new(provider, config));
         let req = JsonRpcRequest {
             jsonrpc: "2.0".to_string(),
             id: Some(json!(3)),
@@ -419,7 +871,7 @@ mod tests {
             }),
         };
 
-        let response = handle_request(&usecase, req).await;
+        let response = handle_request(&state, req, &Notifier::default(), None).await;
 
         assert_eq!(response.jsonrpc, "2.0");
         assert_eq!(response.id, Some(json!(3)));
@@ -430,7 +882,7 @@ mod tests {
             if let Some(content) = result.get("content") {
                 if let Some(content_array) = content.as_array() {
                     assert!(!content_array.is_empty());
-                    if let Some(first_item) = content_array.get(0) {
+                    if let Some(first_item) = content_array.first() {
                         assert_eq!(first_item.get("type").unwrap(), "text");
                         let text = first_item.get("text").unwrap().as_str().unwrap();
                         assert!(text.contains("Enhanced: test prompt"));
@@ -444,7 +896,7 @@ mod tests {
     async fn test_handle_tools_call_error() {
         let provider = Box::new(FailingProvider);
         let config = create_test_config();
-        let usecase = EnhancePrompt::new(provider, config);
+        let state = ServerState::new(EnhancePrompt::new(provider, config));
         let req = JsonRpcRequest {
             jsonrpc: "2.0".to_string(),
             id: Some(json!(4)),
@@ -457,7 +909,7 @@ mod tests {
             }),
         };
 
-        let response = handle_request(&usecase, req).await;
+        let response = handle_request(&state, req, &Notifier::default(), None).await;
 
         assert_eq!(response.jsonrpc, "2.0");
         assert_eq!(response.id, Some(json!(4)));
@@ -468,7 +920,7 @@ mod tests {
             if let Some(content) = result.get("content") {
                 if let Some(content_array) = content.as_array() {
                     assert!(!content_array.is_empty());
-                    if let Some(first_item) = content_array.get(0) {
+                    if let Some(first_item) = content_array.first() {
                         assert_eq!(first_item.get("type").unwrap(), "text");
                         let text = first_item.get("text").unwrap().as_str().unwrap();
                         assert!(text.contains("tool error"));
@@ -480,11 +932,251 @@ mod tests {
         }
     }
 
+    fn enhance_call(id: i64) -> JsonRpcRequest {
+        JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(id)),
+            method: "tools/call".to_string(),
+            params: json!({
+                "name": "enhance_prompt",
+                "arguments": { "prompt": "test prompt", "enable_sequential_thinking": false }
+            }),
+        }
+    }
+
+    struct RateLimitedProvider;
+
+    #[async_trait]
+    impl LLMProvider for RateLimitedProvider {
+        async fn enhance(&self, _prompt: Prompt, _options: EnhancementOptions) -> Result<EnhancedPrompt, LLMError> {
+            let body = json!({ "error": { "code": 429, "message": "Rate limit exceeded" } });
+            Err(LLMError::RateLimited {
+                retry_after: Some(std::time::Duration::from_secs(30)),
+                error: ProviderError::new(429, "Rate limit exceeded").with_body(Some(body)),
+            })
+        }
+    }
+
+    struct FilteredProvider;
+
+    #[async_trait]
+    impl LLMProvider for FilteredProvider {
+        async fn enhance(&self, _prompt: Prompt, _options: EnhancementOptions) -> Result<EnhancedPrompt, LLMError> {
+            Err(LLMError::ContentFiltered(ProviderError::new(403, "Input was flagged")))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_tools_call_rate_limited_error() {
+        let state = ServerState::new(EnhancePrompt::new(Box::new(RateLimitedProvider), create_test_config()));
+
+        let response = handle_request(&state, enhance_call(12), &Notifier::default(), None).await;
+
+        assert!(response.result.is_none());
+        let error = response.error.unwrap();
+        assert_eq!(error.code, RATE_LIMITED);
+        let data = error.data.unwrap();
+        assert_eq!(data["kind"], "rate_limited");
+        assert_eq!(data["retryable"], true);
+        assert_eq!(data["status"], 429);
+        assert_eq!(data["retryAfterSecs"], 30);
+        assert_eq!(data["providerError"]["error"]["message"], "Rate limit exceeded");
+    }
+
+    #[tokio::test]
+    async fn test_handle_tools_call_content_filtered_error() {
+        let state = ServerState::new(EnhancePrompt::new(Box::new(FilteredProvider), create_test_config()));
+
+        let error = handle_request(&state, enhance_call(13), &Notifier::default(), None).await.error.unwrap();
+
+        assert_eq!(error.code, CONTENT_FILTERED);
+        assert_eq!(error.message, "content filtered: status 403: Input was flagged");
+        let data = error.data.unwrap();
+        assert_eq!(data["kind"], "content_filtered");
+        assert_eq!(data["retryable"], false);
+        assert!(data.get("providerError").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_handle_tools_call_progress_notifications() {
+        let state = ServerState::new(EnhancePrompt::new(Box::new(MockProvider), create_test_config()));
+        let (notifier, mut outbound) = Notifier::channel();
+        let mut req = enhance_call(14);
+        req.params["_meta"] = json!({ "progressToken": "enhance-1" });
+        req.params["arguments"]["enable_sequential_thinking"] = json!(true);
+        req.params["arguments"]["thought_count"] = json!(2);
+
+        let response = handle_request(&state, req, &notifier, None).await;
+
+        let mut notifications = Vec::new();
+        while let Ok(line) = outbound.try_recv() {
+            notifications.push(serde_json::from_str::<Value>(&line).unwrap());
+        }
+        assert_eq!(notifications.len(), 4);
+        for (i, notification) in notifications.iter().enumerate() {
+            assert_eq!(notification["method"], "notifications/progress");
+            assert_eq!(notification["params"]["progressToken"], "enhance-1");
+            assert_eq!(notification["params"]["progress"], i as u64 + 1);
+            assert!(notification.get("id").is_none());
+        }
+        assert!(notifications[0]["params"]["message"].as_str().unwrap().starts_with("Enhanced: test prompt"));
+        assert_eq!(notifications[3]["params"]["message"], "thought 2 of 2 complete");
+
+        // The final result has the same shape as for clients that did not ask for progress
+        let result = response.result.unwrap();
+        assert_eq!(result["content"][0]["type"], "text");
+        assert_eq!(result["_meta"]["degraded"], false);
+    }
+
+    #[tokio::test]
+    async fn test_handle_tools_call_without_progress_token_sends_no_notifications() {
+        let state = ServerState::new(EnhancePrompt::new(Box::new(MockProvider), create_test_config()));
+        let (notifier, mut outbound) = Notifier::channel();
+
+        handle_request(&state, enhance_call(15), &notifier, None).await;
+
+        assert!(outbound.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_handle_tools_call_meta_not_degraded() {
+        let state = ServerState::new(EnhancePrompt::new(Box::new(MockProvider), create_test_config()));
+
+        let result = handle_request(&state, enhance_call(10), &Notifier::default(), None).await.result.unwrap();
+
+        assert_eq!(result["_meta"]["degraded"], false);
+        assert_eq!(result["_meta"]["rationale"], "Test rationale");
+        assert!(result["_meta"].get("degradation").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_handle_tools_call_flags_degraded_result() {
+        let config = Config { degradation: DegradationPolicy::Original, ..create_test_config() };
+        let state = ServerState::new(EnhancePrompt::new(Box::new(FailingProvider), config));
+
+        let result = handle_request(&state, enhance_call(11), &Notifier::default(), None).await.result.unwrap();
+
+        assert!(result.get("isError").is_none());
+        assert_eq!(result["content"][0]["text"], "test prompt");
+        assert_eq!(result["_meta"]["degraded"], true);
+        assert_eq!(result["_meta"]["degradation"]["policy"], "original");
+        assert!(result["_meta"]["degradation"]["reason"].as_str().unwrap().contains("Test failure"));
+    }
+
+    struct MeteredProvider;
+
+    #[async_trait]
+    impl LLMProvider for MeteredProvider {
+        async fn enhance(&self, prompt: Prompt, options: EnhancementOptions) -> Result<EnhancedPrompt, LLMError> {
+            let enhanced = MockProvider.enhance(prompt, options).await?;
+            Ok(EnhancedPrompt { backend: Some("mock:test".into()), usage: Some(TokenUsage { input_tokens: 10, output_tokens: 5 }), ..enhanced })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_tools_list_declares_output_schema() {
+        let state = ServerState::new(EnhancePrompt::new(Box::new(MockProvider), create_test_config()));
+
+        let result = handle_request(&state, request("tools/list", json!({})), &Notifier::default(), None).await.result.unwrap();
+
+        let schema = &result["tools"][0]["outputSchema"];
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["required"], json!(["text", "degraded"]));
+        assert!(schema["properties"]["usage"]["properties"]["totalTokens"].is_object());
+    }
+
+    #[tokio::test]
+    async fn test_handle_analyze_prompt_is_local() {
+        let state = ServerState::new(EnhancePrompt::new(Box::new(FailingProvider), create_test_config()));
+        let call = request("tools/call", json!({ "name": "analyze_prompt", "arguments": { "prompt": "write something about dogs" } }));
+
+        let result = handle_request(&state, call, &Notifier::default(), None).await.result.unwrap();
+
+        assert!(result.get("isError").is_none());
+        assert!(result["content"][0]["text"].as_str().unwrap().starts_with("Score: 0.29"));
+        assert_eq!(result["structuredContent"]["wordCount"], 4);
+        assert_eq!(result["structuredContent"]["checks"][3]["check"], "ambiguity");
+        assert_eq!(result["structuredContent"]["checks"][3]["passed"], false);
+        assert!(state.history.list(None).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_handle_analyze_prompt_rejects_empty_prompt() {
+        let state = ServerState::new(EnhancePrompt::new(Box::new(MockProvider), create_test_config()));
+        let call = request("tools/call", json!({ "name": "analyze_prompt", "arguments": { "prompt": " " } }));
+
+        let result = handle_request(&state, call, &Notifier::default(), None).await.result.unwrap();
+
+        assert_eq!(result["isError"], true);
+        assert_eq!(result["content"][0]["text"], "tool error: prompt is empty");
+    }
+
+    #[tokio::test]
+    async fn test_handle_tools_call_returns_structured_content() {
+        let state = ServerState::new(EnhancePrompt::new(Box::new(MeteredProvider), create_test_config()));
+        let mut req = enhance_call(12);
+        req.params["arguments"] = json!({ "prompt": "test prompt", "enable_sequential_thinking": true, "thought_count": 2 });
+
+        let result = handle_request(&state, req, &Notifier::default(), None).await.result.unwrap();
+
+        let structured = &result["structuredContent"];
+        assert_eq!(structured["text"], result["content"][0]["text"]);
+        assert_eq!(structured["model"], "mock:test");
+        assert_eq!(structured["rationale"], "Test rationale");
+        assert!(structured["confidence"].is_number());
+        // enhance, one critique and one revision
+        assert_eq!(structured["usage"], json!({ "inputTokens": 30, "outputTokens": 15, "totalTokens": 45 }));
+        assert!(structured["issues"].is_array());
+        assert_eq!(structured["thoughts"].as_array().unwrap().len(), 2);
+        assert!(structured["thoughts"][0]["critique"].is_string());
+        assert_eq!(structured["thoughts"][1]["thoughtNumber"], 2);
+        assert!(structured["thoughts"][1]["critique"].is_null());
+        assert_eq!(structured["degraded"], false);
+        assert!(structured["degradation"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_handle_tools_call_renders_thought_trace_on_request() {
+        let state = ServerState::new(EnhancePrompt::new(Box::new(MeteredProvider), create_test_config()));
+        let mut arguments = json!({ "prompt": "test prompt", "enable_sequential_thinking": true, "thought_count": 2 });
+        let mut req = enhance_call(14);
+        req.params["arguments"] = arguments.clone();
+
+        let result = handle_request(&state, req, &Notifier::default(), None).await.result.unwrap();
+        assert_eq!(result["content"].as_array().unwrap().len(), 1);
+        assert_eq!(result["structuredContent"]["branches"], json!({}));
+        assert_eq!(result["structuredContent"]["thoughts"][1]["isRevision"], true);
+
+        let mut req = enhance_call(15);
+        arguments["show_thoughts"] = json!(true);
+        req.params["arguments"] = arguments;
+        let result = handle_request(&state, req, &Notifier::default(), None).await.result.unwrap();
+
+        let trace = result["content"][1]["text"].as_str().unwrap();
+        assert!(trace.starts_with("## Thought trace\n\n### 💭 Thought 1/2\n\n"));
+        assert!(trace.contains("**📝 Critique**"));
+        assert!(trace.contains("### 🔄 Revision 2/2 (revising thought 1)"));
+    }
+
+    #[tokio::test]
+    async fn test_structured_content_of_degraded_result() {
+        let config = Config { degradation: DegradationPolicy::Original, ..create_test_config() };
+        let state = ServerState::new(EnhancePrompt::new(Box::new(FailingProvider), config));
+
+        let result = handle_request(&state, enhance_call(13), &Notifier::default(), None).await.result.unwrap();
+
+        let structured = &result["structuredContent"];
+        assert_eq!(structured["text"], "test prompt");
+        assert!(structured["usage"].is_null());
+        assert_eq!(structured["degraded"], true);
+        assert_eq!(structured["degradation"]["policy"], "original");
+    }
+
     #[tokio::test]
     async fn test_handle_unknown_tool() {
         let provider = Box::new(MockProvider);
         let config = create_test_config();
-        let usecase = EnhancePrompt::new(provider, config);
+        let state = ServerState::new(EnhancePrompt::new(provider, config));
         let req = JsonRpcRequest {
             jsonrpc: "2.0".to_string(),
             id: Some(json!(5)),
@@ -495,7 +1187,7 @@ mod tests {
             }),
         };
 
-        let response = handle_request(&usecase, req).await;
+        let response = handle_request(&state, req, &Notifier::default(), None).await;
 
         assert_eq!(response.jsonrpc, "2.0");
         assert_eq!(response.id, Some(json!(5)));
@@ -512,7 +1204,7 @@ mod tests {
     async fn test_handle_ping() {
         let provider = Box::new(MockProvider);
         let config = create_test_config();
-        let usecase = EnhancePrompt::new(provider, config);
+        let state = ServerState::new(EnhancePrompt::new(provider, config));
         let req = JsonRpcRequest {
             jsonrpc: "2.0".to_string(),
             id: Some(json!(6)),
@@ -520,7 +1212,7 @@ mod tests {
             params: json!({}),
         };
 
-        let response = handle_request(&usecase, req).await;
+        let response = handle_request(&state, req, &Notifier::default(), None).await;
 
         assert_eq!(response.jsonrpc, "2.0");
         assert_eq!(response.id, Some(json!(6)));
@@ -536,7 +1228,7 @@ mod tests {
     async fn test_handle_shutdown() {
         let provider = Box::new(MockProvider);
         let config = create_test_config();
-        let usecase = EnhancePrompt::new(provider, config);
+        let state = ServerState::new(EnhancePrompt::new(provider, config));
         let req = JsonRpcRequest {
             jsonrpc: "2.0".to_string(),
             id: Some(json!(7)),
@@ -544,7 +1236,7 @@ mod tests {
             params: json!({}),
         };
 
-        let response = handle_request(&usecase, req).await;
+        let response = handle_request(&state, req, &Notifier::default(), None).await;
 
         assert_eq!(response.jsonrpc, "2.0");
         assert_eq!(response.id, Some(json!(7)));
@@ -560,7 +1252,7 @@ mod tests {
     async fn test_handle_unknown_method() {
         let provider = Box::new(MockProvider);
         let config = create_test_config();
-        let usecase = EnhancePrompt::new(provider, config);
+        let state = ServerState::new(EnhancePrompt::new(provider, config));
         let req = JsonRpcRequest {
             jsonrpc: "2.0".to_string(),
             id: Some(json!(8)),
@@ -568,7 +1260,7 @@ mod tests {
             params: json!({}),
         };
 
-        let response = handle_request(&usecase, req).await;
+        let response = handle_request(&state, req, &Notifier::default(), None).await;
 
         assert_eq!(response.jsonrpc, "2.0");
         assert_eq!(response.id, Some(json!(8)));
@@ -585,7 +1277,7 @@ mod tests {
     async fn test_handle_mcp_initialize() {
         let provider = Box::new(MockProvider);
         let config = create_test_config();
-        let usecase = EnhancePrompt::new(provider, config);
+        let state = ServerState::new(EnhancePrompt::new(provider, config));
         let req = JsonRpcRequest {
             jsonrpc: "2.0".to_string(),
             id: Some(json!(9)),
@@ -593,7 +1285,7 @@ mod tests {
             params: json!({}),
         };
 
-        let response = handle_request(&usecase, req).await;
+        let response = handle_request(&state, req, &Notifier::default(), None).await;
 
         assert_eq!(response.jsonrpc, "2.0");
         assert_eq!(response.id, Some(json!(9)));
@@ -617,23 +1309,543 @@ mod tests {
         assert!(req.params.is_object());
     }
 
-    #[test]
-    fn test_enhance_args_deserialization() {
-        let json_str = r#"{
-            "prompt": "Test prompt",
-            "goal": "Test goal",
-            "level": 3
-        }"#;
+    /// Sleeps before answering and records how many calls overlapped
+    struct SlowProvider {
+        delay: Duration,
+        running: Arc<std::sync::atomic::AtomicUsize>,
+        peak: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl SlowProvider {
+        fn new(delay: Duration) -> Self {
+            Self { delay, running: Default::default(), peak: Default::default() }
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for SlowProvider {
+        async fn enhance(&self, prompt: Prompt, options: EnhancementOptions) -> Result<EnhancedPrompt, LLMError> {
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(running, Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            MockProvider.enhance(prompt, options).await
+        }
+    }
+
+    /// Client end of an in-memory connection to `serve`
+    struct TestClient {
+        writer: tokio::io::WriteHalf<tokio::io::DuplexStream>,
+        reader: tokio::io::Lines<BufReader<tokio::io::ReadHalf<tokio::io::DuplexStream>>>,
+        server: tokio::task::JoinHandle<anyhow::Result<()>>,
+    }
 
-        let args: EnhanceArgs = serde_json::from_str(json_str).unwrap();
-        assert_eq!(args.prompt, "Test prompt");
-        assert_eq!(args.goal.as_deref(), Some("Test goal"));
-        assert_eq!(args.level, Some(3));
-        assert!(args.style.is_none());
-        assert!(args.tone.is_none());
-        assert!(args.audience.is_none());
-        assert!(args.language.is_none());
-        assert!(args.enable_sequential_thinking.is_none());
-        assert!(args.thought_count.is_none());
+    impl TestClient {
+        /// Connect and complete the initialize handshake
+        async fn start(provider: Box<dyn LLMProvider + Send + Sync>, max_concurrent_requests: usize) -> Self {
+            Self::start_with(Arc::new(ServerState::new(EnhancePrompt::new(provider, create_test_config()))), max_concurrent_requests).await
+        }
+
+        async fn start_with(state: Arc<ServerState>, max_concurrent_requests: usize) -> Self {
+            let mut client = Self::connect_to(state, max_concurrent_requests, Duration::from_secs(1));
+            client.send(json!({ "jsonrpc": "2.0", "id": 0, "method": "initialize", "params": { "protocolVersion": "2024-11-05" } })).await;
+            assert_eq!(client.recv().await["result"]["protocolVersion"], "2024-11-05");
+            client.send(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" })).await;
+            client
+        }
+
+        fn connect(provider: Box<dyn LLMProvider + Send + Sync>, max_concurrent_requests: usize, shutdown_timeout: Duration) -> Self {
+            Self::connect_to(Arc::new(ServerState::new(EnhancePrompt::new(provider, create_test_config()))), max_concurrent_requests, shutdown_timeout)
+        }
+
+        fn connect_to(state: Arc<ServerState>, max_concurrent_requests: usize, shutdown_timeout: Duration) -> Self {
+            let (client, server) = tokio::io::duplex(64 * 1024);
+            let (server_read, server_write) = tokio::io::split(server);
+            let options = ServerOptions { shutdown_timeout, max_concurrent_requests };
+            let server = tokio::spawn(serve(server_read, server_write, state, options));
+
+            let (client_read, writer) = tokio::io::split(client);
+            Self { writer, reader: BufReader::new(client_read).lines(), server }
+        }
+
+        async fn send(&mut self, message: Value) {
+            self.writer.write_all(format!("{}\n", message).as_bytes()).await.unwrap();
+        }
+
+        async fn recv(&mut self) -> Value {
+            let line = tokio::time::timeout(Duration::from_secs(5), self.reader.next_line())
+                .await
+                .expect("timed out waiting for the server")
+                .unwrap()
+                .expect("server closed the connection");
+            serde_json::from_str(&line).unwrap()
+        }
+
+        /// Close the input, wait for the server to finish and return anything it
+        /// wrote that was not received yet
+        async fn close(mut self) -> Vec<Value> {
+            // Dropping one half of a split stream does not close it, so shut it down
+            self.writer.shutdown().await.unwrap();
+            self.server.await.unwrap().unwrap();
+            let mut remaining = Vec::new();
+            while let Some(line) = self.reader.next_line().await.unwrap() {
+                remaining.push(serde_json::from_str(&line).unwrap());
+            }
+            remaining
+        }
+    }
+
+    fn tool_call(id: i64) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "tools/call",
+            "params": { "name": "enhance_prompt", "arguments": { "prompt": "test prompt", "enable_sequential_thinking": false } }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_ping_is_not_blocked_by_slow_tool_call() {
+        let mut client = TestClient::start(Box::new(SlowProvider::new(Duration::from_millis(500))), 4).await;
+
+        client.send(tool_call(1)).await;
+        client.send(json!({ "jsonrpc": "2.0", "id": 2, "method": "ping" })).await;
+
+        let first = client.recv().await;
+        assert_eq!(first["id"], 2);
+        assert_eq!(first["result"]["message"], "pong");
+        let second = client.recv().await;
+        assert_eq!(second["id"], 1);
+        assert!(second["result"]["content"][0]["text"].as_str().unwrap().contains("test prompt"));
+
+        client.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_are_bounded() {
+        let provider = SlowProvider::new(Duration::from_millis(100));
+        let peak = provider.peak.clone();
+        let mut client = TestClient::start(Box::new(provider), 2).await;
+
+        for id in 1..=5 {
+            client.send(tool_call(id)).await;
+        }
+        let mut ids = Vec::new();
+        for _ in 1..=5 {
+            ids.push(client.recv().await["id"].as_i64().unwrap());
+        }
+        ids.sort();
+
+        assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        client.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_pending_responses_are_written_before_exit() {
+        let mut client = TestClient::start(Box::new(SlowProvider::new(Duration::from_millis(100))), 4).await;
+
+        client.send(tool_call(7)).await;
+        client.writer.shutdown().await.unwrap();
+
+        assert_eq!(client.recv().await["id"], 7);
+        client.close().await;
+    }
+
+    /// Never answers; signals when a call starts and records when it is dropped
+    #[derive(Default)]
+    struct HangingProvider {
+        started: Arc<tokio::sync::Notify>,
+        dropped: Arc<std::sync::atomic::AtomicBool>,
+    }
+
+    struct DropFlag(Arc<std::sync::atomic::AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for HangingProvider {
+        async fn enhance(&self, _prompt: Prompt, _options: EnhancementOptions) -> Result<EnhancedPrompt, LLMError> {
+            let _flag = DropFlag(self.dropped.clone());
+            self.started.notify_one();
+            std::future::pending().await
+        }
+    }
+
+    fn cancelled(request_id: Value) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/cancelled",
+            "params": { "requestId": request_id, "reason": "user moved on" }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_tool_call_is_aborted_without_response() {
+        let provider = HangingProvider::default();
+        let (started, dropped) = (provider.started.clone(), provider.dropped.clone());
+        let mut client = TestClient::start(Box::new(provider), 4).await;
+
+        client.send(tool_call(1)).await;
+        started.notified().await;
+        client.send(cancelled(json!(1))).await;
+        client.send(json!({ "jsonrpc": "2.0", "id": 2, "method": "ping" })).await;
+
+        assert_eq!(client.recv().await["id"], 2);
+        // The provider call is dropped right away rather than at shutdown
+        tokio::time::timeout(Duration::from_millis(500), async {
+            while !dropped.load(Ordering::SeqCst) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("provider call was not dropped");
+        assert!(client.close().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_matches_request_id_exactly() {
+        let mut client = TestClient::start(Box::new(SlowProvider::new(Duration::from_millis(100))), 4).await;
+
+        client.send(tool_call(1)).await;
+        // A string id is a different request than the number 1
+        client.send(cancelled(json!("1"))).await;
+        client.send(cancelled(json!(99))).await;
+
+        assert_eq!(client.recv().await["id"], 1);
+        assert!(client.close().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_handle_initialize_negotiates_protocol_version() {
+        let state = ServerState::new(EnhancePrompt::new(Box::new(MockProvider), create_test_config()));
+        let initialize = |version: &str| JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(1)),
+            method: "initialize".to_string(),
+            params: json!({ "protocolVersion": version }),
+        };
+
+        let supported = handle_request(&state, initialize("2024-11-05"), &Notifier::default(), None).await.result.unwrap();
+        let unsupported = handle_request(&state, initialize("2000-01-01"), &Notifier::default(), None).await.result.unwrap();
+
+        assert_eq!(supported["protocolVersion"], "2024-11-05");
+        assert_eq!(unsupported["protocolVersion"], SUPPORTED_PROTOCOL_VERSIONS[0]);
+    }
+
+    #[tokio::test]
+    async fn test_requests_before_initialize_are_rejected() {
+        let mut client = TestClient::connect(Box::new(MockProvider), 4, Duration::from_secs(1));
+
+        client.send(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" })).await;
+        client.send(tool_call(1)).await;
+        client.send(json!({ "jsonrpc": "2.0", "id": 2, "method": "ping" })).await;
+
+        // The notification gets no answer, so the first line is about the tool call
+        let rejected = client.recv().await;
+        assert_eq!(rejected["id"], 1);
+        assert_eq!(rejected["error"]["code"], -32600);
+        assert_eq!(rejected["error"]["message"], "server not initialized");
+        assert_eq!(client.recv().await["result"]["message"], "pong");
+        assert!(client.close().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_notifications_get_no_response() {
+        let mut client = TestClient::start(Box::new(MockProvider), 4).await;
+
+        client.send(json!({ "jsonrpc": "2.0", "method": "notifications/roots/list_changed" })).await;
+        client.send(json!({ "jsonrpc": "2.0", "method": "tools/call", "params": tool_call(0)["params"] })).await;
+
+        assert!(client.close().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_second_initialize_is_rejected() {
+        let mut client = TestClient::start(Box::new(MockProvider), 4).await;
+
+        client.send(json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} })).await;
+
+        assert_eq!(client.recv().await["error"]["message"], "server already initialized");
+        client.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_drains_in_flight_calls_and_stops_reading() {
+        let mut client = TestClient::start(Box::new(SlowProvider::new(Duration::from_millis(100))), 4).await;
+
+        client.send(tool_call(1)).await;
+        client.send(json!({ "jsonrpc": "2.0", "id": 2, "method": "shutdown" })).await;
+        client.send(json!({ "jsonrpc": "2.0", "id": 3, "method": "ping" })).await;
+
+        assert_eq!(client.recv().await["id"], 2);
+        assert_eq!(client.recv().await["id"], 1);
+        // The server is gone, so the ping after shutdown is never answered
+        assert!(client.server.await.unwrap().is_ok());
+        assert!(client.reader.next_line().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_timeout_aborts_stuck_calls() {
+        let provider = HangingProvider::default();
+        let (started, dropped) = (provider.started.clone(), provider.dropped.clone());
+        let mut client = TestClient::connect(Box::new(provider), 4, Duration::from_millis(100));
+        client.send(json!({ "jsonrpc": "2.0", "id": 0, "method": "initialize", "params": {} })).await;
+        client.recv().await;
+
+        client.send(tool_call(1)).await;
+        started.notified().await;
+
+        assert!(client.close().await.is_empty());
+        assert!(dropped.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_batch_runs_requests_in_parallel() {
+        let provider = SlowProvider::new(Duration::from_millis(100));
+        let peak = provider.peak.clone();
+        let mut client = TestClient::start(Box::new(provider), 4).await;
+
+        client.send(json!([
+            tool_call(1),
+            { "jsonrpc": "2.0", "method": "notifications/roots/list_changed" },
+            tool_call(2),
+            { "jsonrpc": "2.0", "id": 3, "method": "ping" }
+        ])).await;
+
+        let batch = client.recv().await;
+        let mut ids: Vec<i64> = batch.as_array().unwrap().iter().map(|resp| resp["id"].as_i64().unwrap()).collect();
+        ids.sort();
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        assert!(client.close().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_batch_reports_invalid_members() {
+        let mut client = TestClient::start(Box::new(MockProvider), 4).await;
+
+        client.send(json!([
+            { "jsonrpc": "2.0", "id": 1, "method": "ping" },
+            { "jsonrpc": "2.0", "id": 2 },
+            { "jsonrpc": "2.0", "id": 3, "method": "initialize", "params": {} }
+        ])).await;
+
+        let batch = client.recv().await;
+        let batch = batch.as_array().unwrap();
+        assert_eq!(batch.len(), 3);
+        let error_for = |id: Value| batch.iter().find(|resp| resp["id"] == id).map(|resp| resp["error"]["code"].clone());
+        assert_eq!(error_for(json!(1)), Some(Value::Null));
+        assert_eq!(error_for(json!(3)), Some(json!(-32600)));
+        // A member that is not a request cannot be matched by id
+        assert_eq!(error_for(Value::Null), Some(json!(-32600)));
+        client.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_empty_batch_is_an_error() {
+        let mut client = TestClient::start(Box::new(MockProvider), 4).await;
+
+        client.send(json!([])).await;
+
+        let resp = client.recv().await;
+        assert!(resp.is_object());
+        assert_eq!(resp["error"]["code"], -32600);
+        assert!(resp["id"].is_null());
+        client.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_batch_of_notifications_gets_no_response() {
+        let mut client = TestClient::start(Box::new(MockProvider), 4).await;
+
+        client.send(json!([
+            { "jsonrpc": "2.0", "method": "notifications/roots/list_changed" },
+            { "jsonrpc": "2.0", "method": "notifications/cancelled", "params": { "requestId": 99 } }
+        ])).await;
+
+        assert!(client.close().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_batch_member_is_left_out() {
+        let provider = HangingProvider::default();
+        let started = provider.started.clone();
+        let mut client = TestClient::start(Box::new(provider), 4).await;
+
+        client.send(json!([tool_call(1), { "jsonrpc": "2.0", "id": 2, "method": "ping" }])).await;
+        started.notified().await;
+        client.send(cancelled(json!(1))).await;
+
+        let batch = client.recv().await;
+        assert_eq!(batch, json!([{ "jsonrpc": "2.0", "id": 2, "result": { "message": "pong" } }]));
+        client.close().await;
+    }
+
+    fn request(method: &str, params: Value) -> JsonRpcRequest {
+        JsonRpcRequest { jsonrpc: "2.0".to_string(), id: Some(json!(1)), method: method.to_string(), params }
+    }
+
+    #[tokio::test]
+    async fn test_handle_prompts_list() {
+        let state = ServerState::new(EnhancePrompt::new(Box::new(MockProvider), create_test_config()));
+
+        let result = handle_request(&state, request("prompts/list", json!({})), &Notifier::default(), None).await.result.unwrap();
+
+        let prompts = result["prompts"].as_array().unwrap();
+        let names: Vec<_> = prompts.iter().map(|p| p["name"].as_str().unwrap()).collect();
+        assert_eq!(names, vec!["bug-report", "code-review-request", "data-analysis"]);
+        let code = &prompts[1]["arguments"][0];
+        assert_eq!(code["name"], "code");
+        assert_eq!(code["required"], true);
+    }
+
+    #[tokio::test]
+    async fn test_handle_prompts_get() {
+        let mut catalog = PromptCatalog::builtin();
+        catalog.extend([crate::domain::templates::PromptTemplate::from_text("greet", "Say hi to {{name}}")]);
+        let state = ServerState::new(EnhancePrompt::new(Box::new(MockProvider), create_test_config())).with_prompts(catalog);
+
+        let params = json!({ "name": "greet", "arguments": { "name": "Ana" } });
+        let result = handle_request(&state, request("prompts/get", params), &Notifier::default(), None).await.result.unwrap();
+
+        assert_eq!(result["messages"][0]["role"], "user");
+        assert_eq!(result["messages"][0]["content"], json!({ "type": "text", "text": "Say hi to Ana" }));
+    }
+
+    #[tokio::test]
+    async fn test_handle_prompts_get_errors() {
+        let state = ServerState::new(EnhancePrompt::new(Box::new(MockProvider), create_test_config()));
+
+        let unknown = handle_request(&state, request("prompts/get", json!({ "name": "nope" })), &Notifier::default(), None).await.error.unwrap();
+        let missing = handle_request(&state, request("prompts/get", json!({ "name": "bug-report" })), &Notifier::default(), None).await.error.unwrap();
+
+        assert_eq!(unknown.code, -32602);
+        assert_eq!(unknown.message, "unknown prompt: nope");
+        assert_eq!(missing.code, -32602);
+        assert_eq!(missing.message, "missing required argument: summary");
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_are_recorded_as_resources() {
+        let state = ServerState::new(EnhancePrompt::new(Box::new(MockProvider), create_test_config()));
+
+        handle_request(&state, enhance_call(1), &Notifier::default(), None).await;
+        let read = handle_request(&state, request("resources/read", json!({ "uri": "anytra://history/1" })), &Notifier::default(), None).await.result.unwrap();
+        let unknown = handle_request(&state, request("resources/read", json!({ "uri": "anytra://history/2" })), &Notifier::default(), None).await.error.unwrap();
+
+        let entry: Value = serde_json::from_str(read["contents"][0]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(entry["id"], 1);
+        assert!(entry["result"]["text"].as_str().unwrap().starts_with("Enhanced:"));
+        assert_eq!(unknown.code, -32602);
+    }
+
+    #[tokio::test]
+    async fn test_history_is_private_to_each_client() {
+        let state = ServerState::new(EnhancePrompt::new(Box::new(MockProvider), create_test_config()));
+        let alice = Identity { client: "alice".into() };
+        let bob = Identity { client: "bob".into() };
+        let read = || request("resources/read", json!({ "uri": "anytra://history/1" }));
+
+        handle_request(&state, enhance_call(1), &Notifier::default(), Some(&alice)).await;
+
+        assert!(handle_request(&state, read(), &Notifier::default(), Some(&alice)).await.result.is_some());
+        assert_eq!(handle_request(&state, read(), &Notifier::default(), Some(&bob)).await.error.unwrap().code, -32602);
+        assert_eq!(handle_request(&state, read(), &Notifier::default(), None).await.error.unwrap().code, -32602);
+    }
+
+    #[tokio::test]
+    async fn test_subscriber_is_told_when_an_entry_is_evicted() {
+        let mut state = ServerState::new(EnhancePrompt::new(Box::new(MockProvider), create_test_config()));
+        state.history = EnhancementHistory::new(1);
+        let (session, mut outbound) = Notifier::channel();
+
+        handle_request(&state, enhance_call(1), &Notifier::default(), None).await;
+        state.subscriptions.subscribe("anytra://history/1", &session, None);
+        handle_request(&state, enhance_call(2), &Notifier::default(), None).await;
+
+        let update: Value = serde_json::from_str(&outbound.recv().await.unwrap()).unwrap();
+        assert_eq!(update["method"], "notifications/resources/updated");
+        assert_eq!(update["params"]["uri"], "anytra://history/1");
+    }
+
+    #[tokio::test]
+    async fn test_listing_client_is_told_when_the_list_changes() {
+        let mut client = TestClient::start(Box::new(MockProvider), 4).await;
+
+        client.send(json!({ "jsonrpc": "2.0", "id": 1, "method": "resources/list" })).await;
+        assert!(!client.recv().await["result"]["resources"].as_array().unwrap().iter().any(|r| r["uri"] == "anytra://history/1"));
+        client.send(tool_call(2)).await;
+
+        assert_eq!(client.recv().await["method"], "notifications/resources/list_changed");
+        assert_eq!(client.recv().await["id"], 2);
+        client.send(json!({ "jsonrpc": "2.0", "id": 3, "method": "resources/list" })).await;
+        assert!(client.recv().await["result"]["resources"].as_array().unwrap().iter().any(|r| r["uri"] == "anytra://history/1"));
+        client.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_subscriber_is_told_about_new_history_entries() {
+        let mut client = TestClient::start(Box::new(MockProvider), 4).await;
+
+        client.send(json!({ "jsonrpc": "2.0", "id": 1, "method": "resources/subscribe", "params": { "uri": "anytra://history" } })).await;
+        assert_eq!(client.recv().await["result"], json!({}));
+        client.send(tool_call(2)).await;
+
+        let update = client.recv().await;
+        assert_eq!(update["method"], "notifications/resources/updated");
+        assert_eq!(update["params"]["uri"], "anytra://history");
+        assert_eq!(client.recv().await["id"], 2);
+
+        client.send(json!({ "jsonrpc": "2.0", "id": 3, "method": "resources/unsubscribe", "params": { "uri": "anytra://history" } })).await;
+        assert_eq!(client.recv().await["id"], 3);
+        client.send(tool_call(4)).await;
+        assert_eq!(client.recv().await["id"], 4);
+        assert!(client.close().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_subscribing_to_unknown_resource_fails() {
+        let mut client = TestClient::start(Box::new(MockProvider), 4).await;
+
+        client.send(json!({ "jsonrpc": "2.0", "id": 1, "method": "resources/subscribe", "params": { "uri": "anytra://nope" } })).await;
+
+        assert_eq!(client.recv().await["error"]["code"], -32602);
+        client.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_set_level_registers_the_session_for_log_messages() {
+        let mut client = TestClient::start(Box::new(MockProvider), 4).await;
+
+        client.send(json!({ "jsonrpc": "2.0", "id": 1, "method": "logging/setLevel", "params": { "level": "debug" } })).await;
+        client.send(json!({ "jsonrpc": "2.0", "id": 2, "method": "logging/setLevel", "params": { "level": "verbose" } })).await;
+
+        let mut responses = [client.recv().await, client.recv().await];
+        responses.sort_by_key(|resp| resp["id"].as_i64());
+        assert_eq!(responses[0]["result"], json!({}));
+        assert_eq!(responses[1]["error"]["code"], -32602);
+        client.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_thinking_sessions_outlive_the_connection() {
+        let dir = TempDir::new();
+        let store = FileThinkingStore::open(dir.path()).unwrap();
+        let state = ServerState::new(EnhancePrompt::new(Box::new(MockProvider), create_test_config())).with_thinking_store(Box::new(store), None);
+        let mut client = TestClient::start_with(Arc::new(state), 4).await;
+        let thought = json!({ "thought": "step 1", "thoughtNumber": 1, "totalThoughts": 2, "nextThoughtNeeded": true });
+
+        client.send(json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": { "name": "sequential_thinking", "arguments": thought } })).await;
+        assert_eq!(client.recv().await["result"]["structuredContent"]["thoughtHistoryLength"], 1);
+        client.close().await;
+
+        let sessions = FileThinkingStore::open(dir.path()).unwrap().list().unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].thinking.get_thought_history()[0].thought, "step 1");
     }
 }
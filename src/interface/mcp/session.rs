@@ -0,0 +1,148 @@
+use thiserror::Error;
+
+/// Protocol revisions this server speaks, newest first
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-03-26", "2024-11-05"];
+
+/// Pick the protocol revision for a session: the client's if we support it,
+/// otherwise our latest so the client can decide whether to disconnect
+pub fn negotiate_protocol_version(requested: Option<&str>) -> &'static str {
+    requested
+        .and_then(|requested| SUPPORTED_PROTOCOL_VERSIONS.iter().find(|v| **v == requested))
+        .unwrap_or(&SUPPORTED_PROTOCOL_VERSIONS[0])
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionState {
+    /// nothing but `initialize` and `ping` is accepted yet
+    AwaitingInitialize,
+    /// `initialize` was received, waiting for `notifications/initialized`
+    Initializing,
+    Ready,
+    /// `shutdown` or `exit` was received; no new work is accepted
+    ShuttingDown,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum LifecycleError {
+    #[error("server not initialized")]
+    NotInitialized,
+    #[error("server already initialized")]
+    AlreadyInitialized,
+    #[error("server is shutting down")]
+    ShuttingDown,
+}
+
+/// Lifecycle of one MCP connection. Messages must be fed in the order they
+/// were received so the transitions match what the client sent.
+#[derive(Debug)]
+pub struct Session {
+    state: SessionState,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self { state: SessionState::AwaitingInitialize }
+    }
+}
+
+impl Session {
+    pub fn state(&self) -> SessionState {
+        self.state
+    }
+
+    /// Check whether a request may run in the current state and apply the
+    /// transition it causes
+    pub fn accept_request(&mut self, method: &str) -> Result<(), LifecycleError> {
+        if self.state == SessionState::ShuttingDown {
+            return Err(LifecycleError::ShuttingDown);
+        }
+        match method {
+            "initialize" | "mcp/initialize" => {
+                if self.state != SessionState::AwaitingInitialize {
+                    return Err(LifecycleError::AlreadyInitialized);
+                }
+                self.state = SessionState::Initializing;
+            }
+            "ping" => {}
+            "shutdown" => self.state = SessionState::ShuttingDown,
+            _ if self.state == SessionState::AwaitingInitialize => return Err(LifecycleError::NotInitialized),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Apply the transition caused by a notification; unknown ones are ignored
+    pub fn accept_notification(&mut self, method: &str) {
+        match method {
+            "notifications/initialized" if self.state == SessionState::Initializing => self.state = SessionState::Ready,
+            "exit" => self.state = SessionState::ShuttingDown,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate_supported_version() {
+        assert_eq!(negotiate_protocol_version(Some("2024-11-05")), "2024-11-05");
+        assert_eq!(negotiate_protocol_version(Some("2025-03-26")), "2025-03-26");
+    }
+
+    #[test]
+    fn test_negotiate_unknown_version_offers_latest() {
+        assert_eq!(negotiate_protocol_version(Some("1999-01-01")), SUPPORTED_PROTOCOL_VERSIONS[0]);
+        assert_eq!(negotiate_protocol_version(None), SUPPORTED_PROTOCOL_VERSIONS[0]);
+    }
+
+    #[test]
+    fn test_requests_before_initialize_are_rejected() {
+        let mut session = Session::default();
+
+        assert_eq!(session.accept_request("tools/list"), Err(LifecycleError::NotInitialized));
+        assert_eq!(session.accept_request("ping"), Ok(()));
+        assert_eq!(session.state(), SessionState::AwaitingInitialize);
+    }
+
+    #[test]
+    fn test_handshake() {
+        let mut session = Session::default();
+
+        assert_eq!(session.accept_request("initialize"), Ok(()));
+        assert_eq!(session.state(), SessionState::Initializing);
+        session.accept_notification("notifications/initialized");
+        assert_eq!(session.state(), SessionState::Ready);
+        assert_eq!(session.accept_request("tools/call"), Ok(()));
+        assert_eq!(session.accept_request("initialize"), Err(LifecycleError::AlreadyInitialized));
+    }
+
+    #[test]
+    fn test_initialized_before_initialize_is_ignored() {
+        let mut session = Session::default();
+
+        session.accept_notification("notifications/initialized");
+
+        assert_eq!(session.state(), SessionState::AwaitingInitialize);
+    }
+
+    #[test]
+    fn test_nothing_is_accepted_after_shutdown() {
+        let mut session = Session::default();
+        session.accept_request("initialize").unwrap();
+
+        assert_eq!(session.accept_request("shutdown"), Ok(()));
+        assert_eq!(session.state(), SessionState::ShuttingDown);
+        assert_eq!(session.accept_request("ping"), Err(LifecycleError::ShuttingDown));
+    }
+
+    #[test]
+    fn test_exit_notification_shuts_down() {
+        let mut session = Session::default();
+
+        session.accept_notification("exit");
+
+        assert_eq!(session.state(), SessionState::ShuttingDown);
+    }
+}
@@ -0,0 +1,97 @@
+use crate::domain::analysis::{analyze, PromptAnalysis};
+use crate::interface::mcp::server::ServerState;
+use crate::interface::mcp::tools::{Tool, ToolAnnotations, ToolContext};
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// Arguments of `analyze_prompt`
+#[derive(Debug, Deserialize, JsonSchema)]
+pub(super) struct AnalyzeArgs {
+    /// The prompt to diagnose
+    prompt: String,
+}
+
+/// Scores a prompt with local heuristics; no provider is called
+pub(super) struct AnalyzePromptTool;
+
+#[async_trait]
+impl Tool for AnalyzePromptTool {
+    type Args = AnalyzeArgs;
+
+    const NAME: &'static str = "analyze_prompt";
+    const DESCRIPTION: &'static str = "Diagnose a prompt without rewriting it: clarity, ambiguity, output format, constraints and context, with a score and suggestions. Runs locally, no LLM call";
+    const ANNOTATIONS: ToolAnnotations = ToolAnnotations { read_only_hint: true, open_world_hint: false, idempotent_hint: true };
+
+    fn output_schema(&self) -> Option<Value> {
+        Some(analysis_output_schema())
+    }
+
+    async fn call(&self, _state: &ServerState, args: AnalyzeArgs, _context: &ToolContext<'_>) -> anyhow::Result<Value> {
+        if args.prompt.trim().is_empty() {
+            anyhow::bail!("prompt is empty");
+        }
+        let analysis = analyze(&args.prompt);
+        Ok(json!({
+            "content": [ { "type": "text", "text": report(&analysis) } ],
+            "structuredContent": analysis
+        }))
+    }
+}
+
+/// Shape of `structuredContent` in `analyze_prompt` results
+fn analysis_output_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["score", "category", "wordCount", "checks"],
+        "properties": {
+            "score": { "type": "number", "minimum": 0, "maximum": 1, "description": "Weighted share of passed checks" },
+            "category": { "type": "string", "enum": ["code", "explanation", "definition", "general"] },
+            "wordCount": { "type": "integer" },
+            "checks": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["check", "passed", "issues"],
+                    "properties": {
+                        "check": { "type": "string", "enum": ["clarity", "consistency", "formatting", "ambiguity", "output_format", "constraints", "context"] },
+                        "passed": { "type": "boolean" },
+                        "issues": { "type": "array", "items": { "type": "string" } },
+                        "suggestion": { "type": "string", "description": "How to fix the prompt, present when the check failed" }
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Plain-text summary for clients that ignore `structuredContent`
+fn report(analysis: &PromptAnalysis) -> String {
+    let passed = analysis.checks.iter().filter(|result| result.passed).count();
+    let mut lines = vec![format!("Score: {:.2} ({} of {} checks passed)", analysis.score, passed, analysis.checks.len())];
+    for result in analysis.failed() {
+        lines.push(format!("- {}: {}", result.check.name(), result.issues.join("; ")));
+        if let Some(suggestion) = result.suggestion {
+            lines.push(format!("  {}", suggestion));
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_lists_failed_checks() {
+        let report = report(&analyze("write something about dogs"));
+
+        let mut lines = report.lines();
+        assert_eq!(lines.next(), Some("Score: 0.29 (3 of 7 checks passed)"));
+        assert_eq!(lines.next(), Some("- ambiguity: Vague terms: something"));
+        assert!(lines.next().unwrap().starts_with("  Replace vague words"));
+        assert!(report.contains("- output_format: No output format requested"));
+        assert!(!report.contains("clarity"));
+    }
+}
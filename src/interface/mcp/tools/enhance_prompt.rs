@@ -0,0 +1,292 @@
+use crate::domain::models::{EnhancedPrompt, EnhancementOptions, Prompt};
+use crate::domain::sequential_thinking::render_markdown;
+use crate::interface::mcp::resources::{history_uri, HISTORY_URI};
+use crate::interface::mcp::server::{Notifier, ServerState};
+use crate::interface::mcp::tools::{Tool, ToolAnnotations, ToolContext};
+use crate::usecases::enhance_prompt::ProgressEvent;
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Arguments of `enhance_prompt`
+#[derive(Debug, Deserialize, JsonSchema)]
+pub(super) struct EnhanceArgs {
+    /// The raw prompt to enhance
+    prompt: String,
+    /// Desired outcome
+    #[serde(default)] goal: Option<String>,
+    /// Writing style (concise, formal, etc.)
+    #[serde(default)] style: Option<String>,
+    /// Tone (neutral, persuasive, etc.)
+    #[serde(default)] tone: Option<String>,
+    /// Enhancement strength 1-5
+    #[serde(default)]
+    #[schemars(range(min = 1, max = 5))]
+    level: Option<u8>,
+    /// Target audience
+    #[serde(default)] audience: Option<String>,
+    /// Output language, e.g., en, id
+    #[serde(default)] language: Option<String>,
+    /// Enable sequential thinking for step-by-step reasoning
+    #[serde(default)] enable_sequential_thinking: Option<bool>,
+    /// Number of thoughts to generate (default: 3, at most 10)
+    #[serde(default)]
+    #[schemars(range(min = 1, max = 10))]
+    thought_count: Option<u32>,
+    /// Alternative revisions to try at each thought, keeping the best one (default: 1, at most 4)
+    #[serde(default)]
+    #[schemars(range(min = 1, max = 4))]
+    branch_count: Option<u32>,
+    /// Add a markdown rendering of the thoughts and branches as a second text block (default: false)
+    #[serde(default)] show_thoughts: Option<bool>,
+}
+
+/// Rewrites a prompt with the configured LLM provider
+pub(super) struct EnhancePromptTool;
+
+#[async_trait]
+impl Tool for EnhancePromptTool {
+    type Args = EnhanceArgs;
+
+    const NAME: &'static str = "enhance_prompt";
+    const DESCRIPTION: &'static str = "Enhance a user prompt for clarity, constraints, and specificity";
+    // Results differ between calls and come from an external provider; the
+    // history entry it adds is the server's own record
+    const ANNOTATIONS: ToolAnnotations = ToolAnnotations { read_only_hint: true, open_world_hint: true, idempotent_hint: false };
+
+    fn output_schema(&self) -> Option<Value> {
+        Some(enhance_output_schema())
+    }
+
+    async fn call(&self, state: &ServerState, a: EnhanceArgs, context: &ToolContext<'_>) -> anyhow::Result<Value> {
+        let opt = EnhancementOptions { goal: a.goal, style: a.style, tone: a.tone, level: a.level, audience: a.audience, language: a.language, enable_sequential_thinking: a.enable_sequential_thinking, thought_count: a.thought_count, branch_count: a.branch_count };
+        let enhanced = match context.progress_token.clone() {
+            Some(token) => {
+                let reporter = ProgressReporter::new(context.notifier.clone(), token);
+                state.usecase.execute_with_progress(Prompt { text: a.prompt.clone() }, opt.clone(), &|event| reporter.report(event)).await?
+            }
+            None => state.usecase.execute(Prompt { text: a.prompt.clone() }, opt.clone()).await?,
+        };
+        let owner = context.owner;
+        let (_, evicted) = state.history.record(owner.map(|identity| identity.client.as_str()), &a.prompt, &opt, &enhanced);
+        state.subscriptions.updated(HISTORY_URI, owner);
+        if let Some(evicted) = evicted {
+            state.subscriptions.updated(&history_uri(evicted.id), owner);
+        }
+        state.subscriptions.list_changed(owner);
+        let mut content = vec![json!({ "type": "text", "text": enhanced.text })];
+        if a.show_thoughts.unwrap_or(false) && !enhanced.thoughts.is_empty() {
+            content.push(json!({ "type": "text", "text": format!("## Thought trace\n\n{}", render_markdown(&enhanced.thoughts, &enhanced.branches)) }));
+        }
+        Ok(json!({
+            "content": content,
+            "structuredContent": structured_content(&enhanced),
+            "_meta": result_meta(&enhanced)
+        }))
+    }
+}
+
+/// Turns enhancement progress into `notifications/progress` for one request
+struct ProgressReporter {
+    notifier: Notifier,
+    token: Value,
+    progress: AtomicU64,
+}
+
+impl ProgressReporter {
+    fn new(notifier: Notifier, token: Value) -> Self {
+        Self { notifier, token, progress: AtomicU64::new(0) }
+    }
+
+    fn report(&self, event: ProgressEvent) {
+        // Progress must increase with every notification, so count events of both kinds
+        let progress = self.progress.fetch_add(1, Ordering::SeqCst) + 1;
+        let message = match event {
+            ProgressEvent::Token(text) => text,
+            ProgressEvent::Thought { number, total } => format!("thought {} of {} complete", number, total),
+        };
+        self.notifier.notify("notifications/progress", json!({
+            "progressToken": self.token,
+            "progress": progress,
+            "message": message
+        }));
+    }
+}
+
+/// Shape of `structuredContent` in `enhance_prompt` results
+fn enhance_output_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["text", "degraded"],
+        "properties": {
+            "text": { "type": "string", "description": "The enhanced prompt" },
+            "rationale": { "type": ["string", "null"], "description": "Why the prompt was changed" },
+            "confidence": { "type": ["number", "null"], "minimum": 0, "maximum": 1, "description": "Heuristic quality score of the result" },
+            "model": { "type": ["string", "null"], "description": "Backend that produced the result, e.g. openrouter:openai/gpt-4o-mini" },
+            "usage": {
+                "type": ["object", "null"],
+                "description": "Tokens of every provider call behind the result, when the provider reports them",
+                "properties": {
+                    "inputTokens": { "type": "integer" },
+                    "outputTokens": { "type": "integer" },
+                    "totalTokens": { "type": "integer" }
+                }
+            },
+            "issues": { "type": "array", "items": { "type": "string" }, "description": "Clarity, consistency and formatting findings on the result" },
+            "thoughts": {
+                "type": "array",
+                "description": "Drafts produced by sequential thinking, in order; each one revises the previous draft against its critique",
+                "items": thought_schema()
+            },
+            "branches": {
+                "type": "object",
+                "description": "Alternative revisions tried when branch_count > 1, by branch id; the selected one of each fork is also the next entry of thoughts",
+                "additionalProperties": { "type": "array", "items": thought_schema() }
+            },
+            "degraded": { "type": "boolean", "description": "Whether the result is a fallback rather than an LLM enhancement" },
+            "degradation": {
+                "type": ["object", "null"],
+                "properties": {
+                    "policy": { "type": "string", "enum": ["fail", "original", "local"] },
+                    "reason": { "type": "string" }
+                }
+            }
+        }
+    })
+}
+
+/// A thought as serialized by `ThoughtData`; optional fields are left out when unset
+fn thought_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["thought", "thoughtNumber", "totalThoughts", "nextThoughtNeeded"],
+        "properties": {
+            "thought": { "type": "string" },
+            "thoughtNumber": { "type": "integer" },
+            "totalThoughts": { "type": "integer" },
+            "nextThoughtNeeded": { "type": "boolean" },
+            "isRevision": { "type": "boolean" },
+            "revisesThought": { "type": "integer" },
+            "branchFromThought": { "type": "integer" },
+            "branchId": { "type": "string" },
+            "critique": { "type": "string", "description": "Review of this draft; \"NO ISSUES\" ends the thinking early" },
+            "score": { "type": "number", "description": "Local analysis score of a branch" },
+            "selected": { "type": "boolean", "description": "Whether a branch won its fork" }
+        }
+    })
+}
+
+/// `structuredContent` of a successful `enhance_prompt` call, see `enhance_output_schema`
+fn structured_content(enhanced: &EnhancedPrompt) -> Value {
+    let usage = enhanced.usage.map(|usage| json!({
+        "inputTokens": usage.input_tokens,
+        "outputTokens": usage.output_tokens,
+        "totalTokens": usage.total()
+    }));
+    json!({
+        "text": enhanced.text,
+        "rationale": enhanced.rationale,
+        "confidence": enhanced.confidence,
+        "model": enhanced.backend,
+        "usage": usage,
+        "issues": enhanced.issues,
+        "thoughts": enhanced.thoughts,
+        "branches": enhanced.branches,
+        "degraded": enhanced.degraded.is_some(),
+        "degradation": enhanced.degraded
+    })
+}
+
+/// Result metadata for a tool call; `degraded` is always present so agents
+/// can tell a fallback result from a real enhancement without guessing
+fn result_meta(enhanced: &EnhancedPrompt) -> Value {
+    let mut meta = json!({ "degraded": enhanced.degraded.is_some() });
+    if let Some(ref degradation) = enhanced.degraded {
+        meta["degradation"] = json!(degradation);
+    }
+    if let Some(ref rationale) = enhanced.rationale {
+        meta["rationale"] = json!(rationale);
+    }
+    if let Some(confidence) = enhanced.confidence {
+        meta["confidence"] = json!(confidence);
+    }
+    if let Some(ref backend) = enhanced.backend {
+        meta["backend"] = json!(backend);
+    }
+    meta
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::mcp::tools::ToolRegistry;
+
+    #[test]
+    fn test_enhance_args_creation() {
+        let args = EnhanceArgs {
+            prompt: "Test prompt".to_string(),
+            goal: Some("Test goal".to_string()),
+            style: None,
+            tone: Some("professional".to_string()),
+            level: Some(3),
+            audience: None,
+            language: Some("en".to_string()),
+            enable_sequential_thinking: None,
+            thought_count: None,
+            branch_count: None,
+            show_thoughts: None,
+        };
+
+        assert_eq!(args.prompt, "Test prompt");
+        assert_eq!(args.goal.as_deref(), Some("Test goal"));
+        assert!(args.style.is_none());
+        assert_eq!(args.tone.as_deref(), Some("professional"));
+        assert_eq!(args.level, Some(3));
+        assert!(args.audience.is_none());
+        assert_eq!(args.language.as_deref(), Some("en"));
+        assert!(args.enable_sequential_thinking.is_none());
+        assert!(args.thought_count.is_none());
+    }
+
+    #[test]
+    fn test_enhance_args_deserialization() {
+        let json_str = r#"{
+            "prompt": "Test prompt",
+            "goal": "Test goal",
+            "level": 3
+        }"#;
+
+        let args: EnhanceArgs = serde_json::from_str(json_str).unwrap();
+        assert_eq!(args.prompt, "Test prompt");
+        assert_eq!(args.goal.as_deref(), Some("Test goal"));
+        assert_eq!(args.level, Some(3));
+        assert!(args.style.is_none());
+        assert!(args.tone.is_none());
+        assert!(args.audience.is_none());
+        assert!(args.language.is_none());
+        assert!(args.enable_sequential_thinking.is_none());
+        assert!(args.thought_count.is_none());
+    }
+
+    #[test]
+    fn test_input_schema_matches_args() {
+        let tools = serde_json::to_value(ToolRegistry::builtin().list()).unwrap();
+        let tool = &tools[0];
+
+        assert_eq!(tool["name"], "enhance_prompt");
+        assert_eq!(tool["annotations"], json!({ "readOnlyHint": true, "openWorldHint": true, "idempotentHint": false }));
+        let schema = &tool["inputSchema"];
+        assert_eq!(schema["$schema"], "http://json-schema.org/draft-07/schema#");
+        assert_eq!(schema["required"], json!(["prompt"]));
+        assert_eq!(schema["properties"]["prompt"], json!({ "type": "string", "description": "The raw prompt to enhance" }));
+        assert_eq!(schema["properties"]["goal"]["type"], json!(["string", "null"]));
+        assert_eq!(schema["properties"]["level"]["minimum"], 1);
+        assert_eq!(schema["properties"]["level"]["maximum"], 5);
+        let names: Vec<_> = schema["properties"].as_object().unwrap().keys().cloned().collect();
+        assert_eq!(schema["properties"]["thought_count"]["maximum"], crate::usecases::enhance_prompt::MAX_THOUGHTS);
+        assert_eq!(schema["properties"]["branch_count"]["maximum"], 4);
+        assert_eq!(names.len(), 11);
+    }
+}
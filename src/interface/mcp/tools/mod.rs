@@ -0,0 +1,281 @@
+mod analyze_prompt;
+mod enhance_prompt;
+mod sequential_thinking;
+
+pub(super) use crate::interface::mcp::tools::sequential_thinking::ThinkingSessions;
+
+use crate::interface::mcp::auth::Identity;
+use crate::interface::mcp::server::{Notifier, ServerState};
+use crate::interface::mcp::tools::analyze_prompt::AnalyzePromptTool;
+use crate::interface::mcp::tools::enhance_prompt::EnhancePromptTool;
+use crate::interface::mcp::tools::sequential_thinking::{ExportThinkingTool, ListSessionsTool, ResetThinkingTool, SequentialThinkingTool};
+use async_trait::async_trait;
+use schemars::generate::SchemaSettings;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+/// Hints about a tool's behaviour for clients deciding whether to ask the user
+/// before calling it. They describe the tool, they are not enforced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct ToolAnnotations {
+    /// does not change anything outside the server
+    pub read_only_hint: bool,
+    /// talks to systems outside the server, such as an LLM provider
+    pub open_world_hint: bool,
+    /// calling it again with the same arguments has no further effect
+    pub idempotent_hint: bool,
+}
+
+/// What a tool call can use besides its arguments
+pub(super) struct ToolContext<'a> {
+    /// where `notifications/progress` for this request go
+    pub notifier: &'a Notifier,
+    /// `_meta.progressToken` of the request, if the client wants progress
+    pub progress_token: Option<Value>,
+    /// the authenticated client that sent the request; `None` without authentication
+    pub owner: Option<&'a Identity>,
+}
+
+/// A tool served through `tools/list` and `tools/call`
+#[async_trait]
+pub(super) trait Tool: Send + Sync + 'static {
+    /// The `arguments` of a call; the input schema is derived from it
+    type Args: DeserializeOwned + JsonSchema + Send;
+
+    const NAME: &'static str;
+    const DESCRIPTION: &'static str;
+    const ANNOTATIONS: ToolAnnotations;
+
+    /// Shape of `structuredContent` in the result, if the tool returns any
+    fn output_schema(&self) -> Option<Value> {
+        None
+    }
+
+    /// The `tools/call` result. Errors become an `isError` result, or a JSON-RPC
+    /// error for provider failures the client can act on.
+    async fn call(&self, state: &ServerState, args: Self::Args, context: &ToolContext<'_>) -> anyhow::Result<Value>;
+}
+
+#[derive(Debug, Error)]
+pub(super) enum ToolError {
+    #[error("unknown tool: {0}")]
+    Unknown(String),
+    #[error("invalid arguments: {0}")]
+    InvalidArguments(serde_json::Error),
+    #[error(transparent)]
+    Failed(anyhow::Error),
+}
+
+/// `Tool` with the argument type erased, so tools of any kind fit one registry
+#[async_trait]
+trait DynTool: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn description(&self) -> ToolDescription;
+    async fn call(&self, state: &ServerState, arguments: Value, context: &ToolContext<'_>) -> Result<Value, ToolError>;
+}
+
+#[async_trait]
+impl<T: Tool> DynTool for T {
+    fn name(&self) -> &'static str {
+        T::NAME
+    }
+
+    fn description(&self) -> ToolDescription {
+        ToolDescription {
+            name: T::NAME,
+            description: T::DESCRIPTION,
+            input_schema: input_schema::<T::Args>(),
+            output_schema: self.output_schema(),
+            annotations: T::ANNOTATIONS,
+        }
+    }
+
+    async fn call(&self, state: &ServerState, arguments: Value, context: &ToolContext<'_>) -> Result<Value, ToolError> {
+        // Clients may leave out `arguments` of a tool whose arguments are all optional
+        let arguments = if arguments.is_null() { json!({}) } else { arguments };
+        let args = serde_json::from_value(arguments).map_err(ToolError::InvalidArguments)?;
+        Tool::call(self, state, args, context).await.map_err(ToolError::Failed)
+    }
+}
+
+/// An entry of `tools/list`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct ToolDescription {
+    name: &'static str,
+    description: &'static str,
+    input_schema: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output_schema: Option<Value>,
+    annotations: ToolAnnotations,
+}
+
+/// Self-contained JSON schema of a tool's arguments; doc comments on the fields
+/// become their descriptions
+fn input_schema<T: JsonSchema>() -> Value {
+    let mut schema = SchemaSettings::draft07()
+        .with(|settings| settings.inline_subschemas = true)
+        .into_generator()
+        .into_root_schema_for::<T>();
+    // The Rust type name means nothing to clients
+    schema.remove("title");
+    schema.to_value()
+}
+
+/// The tools a server offers, in `tools/list` order
+#[derive(Default)]
+pub(super) struct ToolRegistry {
+    tools: Vec<Box<dyn DynTool>>,
+}
+
+impl ToolRegistry {
+    /// The tools that ship with anytra
+    pub(super) fn builtin() -> Self {
+        let mut registry = Self::default();
+        registry.register(EnhancePromptTool);
+        registry.register(AnalyzePromptTool);
+        registry.register(SequentialThinkingTool);
+        registry.register(ResetThinkingTool);
+        registry.register(ListSessionsTool);
+        registry.register(ExportThinkingTool);
+        registry
+    }
+
+    /// Add a tool, replacing any tool of the same name
+    pub(super) fn register<T: Tool>(&mut self, tool: T) {
+        match self.tools.iter_mut().find(|t| t.name() == T::NAME) {
+            Some(existing) => *existing = Box::new(tool),
+            None => self.tools.push(Box::new(tool)),
+        }
+    }
+
+    pub(super) fn list(&self) -> Vec<ToolDescription> {
+        self.tools.iter().map(|tool| tool.description()).collect()
+    }
+
+    pub(super) async fn call(&self, state: &ServerState, name: &str, arguments: Value, context: &ToolContext<'_>) -> Result<Value, ToolError> {
+        let tool = self.tools.iter().find(|t| t.name() == name).ok_or_else(|| ToolError::Unknown(name.to_string()))?;
+        tool.call(state, arguments, context).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::llm::{LLMError, LLMProvider};
+    use crate::domain::models::{EnhancedPrompt, EnhancementOptions, Prompt};
+    use crate::infrastructure::config::create_test_config;
+    use crate::usecases::enhance_prompt::EnhancePrompt;
+    use serde::Deserialize;
+
+    struct UnusedProvider;
+
+    #[async_trait]
+    impl LLMProvider for UnusedProvider {
+        async fn enhance(&self, _prompt: Prompt, _options: EnhancementOptions) -> Result<EnhancedPrompt, LLMError> {
+            unreachable!("echo never calls the provider")
+        }
+    }
+
+    fn state() -> ServerState {
+        ServerState::new(EnhancePrompt::new(Box::new(UnusedProvider), create_test_config()))
+    }
+
+    #[derive(Deserialize, JsonSchema)]
+    struct EchoArgs {
+        /// Text to send back
+        text: String,
+        #[serde(default)]
+        times: Option<u32>,
+    }
+
+    struct Echo(&'static str);
+
+    #[async_trait]
+    impl Tool for Echo {
+        type Args = EchoArgs;
+
+        const NAME: &'static str = "echo";
+        const DESCRIPTION: &'static str = "Send the text back";
+        const ANNOTATIONS: ToolAnnotations = ToolAnnotations { read_only_hint: true, open_world_hint: false, idempotent_hint: true };
+
+        async fn call(&self, _state: &ServerState, args: EchoArgs, _context: &ToolContext<'_>) -> anyhow::Result<Value> {
+            if args.text.is_empty() {
+                anyhow::bail!("nothing to echo");
+            }
+            let text = format!("{}{}", self.0, args.text.repeat(args.times.unwrap_or(1) as usize));
+            Ok(json!({ "content": [ { "type": "text", "text": text } ] }))
+        }
+    }
+
+    fn context(notifier: &Notifier) -> ToolContext<'_> {
+        ToolContext { notifier, progress_token: None, owner: None }
+    }
+
+    #[test]
+    fn test_description_derives_schema_from_args() {
+        let mut registry = ToolRegistry::default();
+        registry.register(Echo(""));
+
+        let description = serde_json::to_value(&registry.list()[0]).unwrap();
+
+        assert_eq!(description["name"], "echo");
+        assert_eq!(description["annotations"], json!({ "readOnlyHint": true, "openWorldHint": false, "idempotentHint": true }));
+        let schema = &description["inputSchema"];
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["required"], json!(["text"]));
+        assert_eq!(schema["properties"]["text"]["description"], "Text to send back");
+        assert_eq!(schema["properties"]["times"]["type"], json!(["integer", "null"]));
+        assert!(schema.get("title").is_none());
+        assert!(description.get("outputSchema").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_call_dispatches_by_name() {
+        let mut registry = ToolRegistry::builtin();
+        registry.register(Echo(""));
+        let state = state();
+        let notifier = Notifier::default();
+
+        let result = registry.call(&state, "echo", json!({ "text": "ab", "times": 2 }), &context(&notifier)).await.unwrap();
+
+        assert_eq!(result["content"][0]["text"], "abab");
+        let names: Vec<_> = registry.list().iter().map(|t| t.name).collect();
+        assert_eq!(names, vec!["enhance_prompt", "analyze_prompt", "sequential_thinking", "sequential_thinking_reset", "sequential_thinking_sessions", "sequential_thinking_export", "echo"]);
+    }
+
+    #[tokio::test]
+    async fn test_register_replaces_tool_of_same_name() {
+        let mut registry = ToolRegistry::default();
+        registry.register(Echo("first:"));
+        registry.register(Echo("second:"));
+        let state = state();
+        let notifier = Notifier::default();
+
+        let result = registry.call(&state, "echo", json!({ "text": "x" }), &context(&notifier)).await.unwrap();
+
+        assert_eq!(registry.list().len(), 1);
+        assert_eq!(result["content"][0]["text"], "second:x");
+    }
+
+    #[tokio::test]
+    async fn test_call_errors() {
+        let mut registry = ToolRegistry::default();
+        registry.register(Echo(""));
+        let state = state();
+        let notifier = Notifier::default();
+
+        let unknown = registry.call(&state, "nope", json!({}), &context(&notifier)).await.unwrap_err();
+        assert!(matches!(unknown, ToolError::Unknown(name) if name == "nope"));
+        let invalid = registry.call(&state, "echo", json!({ "times": 2 }), &context(&notifier)).await.unwrap_err();
+        assert!(matches!(invalid, ToolError::InvalidArguments(_)));
+        let missing = registry.call(&state, "echo", Value::Null, &context(&notifier)).await.unwrap_err();
+        assert!(matches!(missing, ToolError::InvalidArguments(_)));
+        let failed = registry.call(&state, "echo", json!({ "text": "" }), &context(&notifier)).await.unwrap_err();
+        assert_eq!(failed.to_string(), "nothing to echo");
+    }
+}
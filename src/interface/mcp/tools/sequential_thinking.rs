@@ -0,0 +1,610 @@
+use crate::domain::sequential_thinking::{render_markdown, ThoughtData};
+use crate::domain::thinking_store::{check_session_id, now, session_key, MemoryThinkingStore, ThinkingSession, ThinkingStore};
+use crate::interface::mcp::auth::Identity;
+use crate::interface::mcp::server::ServerState;
+use crate::interface::mcp::tools::{Tool, ToolAnnotations, ToolContext};
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+/// Session a client records into when it does not name one
+const DEFAULT_SESSION: &str = "default";
+
+/// The thoughts of every session, by the client they belong to and the name it
+/// chose, so clients never see each other's history. Without authentication
+/// there is one client and every connection shares its sessions.
+pub(in crate::interface::mcp) struct ThinkingSessions {
+    store: SessionStore,
+    /// one lock per session in use, held from loading it to saving it so
+    /// concurrent thoughts are not lost
+    locks: Mutex<HashMap<String, Arc<AsyncMutex<()>>>>,
+}
+
+impl Default for ThinkingSessions {
+    fn default() -> Self {
+        Self::new(Box::new(MemoryThinkingStore::default()), None)
+    }
+}
+
+impl ThinkingSessions {
+    pub(in crate::interface::mcp) fn new(store: Box<dyn ThinkingStore>, ttl: Option<Duration>) -> Self {
+        Self { store: SessionStore { store: store.into(), ttl }, locks: Mutex::default() }
+    }
+
+    /// Run `f` on the session `name` of `owner`, starting an empty one if there
+    /// is none, and save it if `f` succeeds
+    async fn with<R>(&self, owner: Option<&Identity>, name: Option<&str>, f: impl FnOnce(&mut ThinkingSession) -> anyhow::Result<R>) -> anyhow::Result<R> {
+        let name = session_name(name)?;
+        let id = session_key(client(owner), name);
+        let _guard = self.lock(&id).await;
+        let loaded = self.blocking(move |store| store.load(&id)).await?;
+        let mut session = loaded.unwrap_or_else(|| ThinkingSession::new(client(owner), name));
+        let result = f(&mut session)?;
+        session.touch();
+        self.blocking(move |store| store.store.save(&session)).await?;
+        Ok(result)
+    }
+
+    /// The session `name` of `owner`, if it has one that has not expired
+    pub(in crate::interface::mcp) async fn get(&self, owner: Option<&Identity>, name: Option<&str>) -> anyhow::Result<Option<ThinkingSession>> {
+        let id = session_key(client(owner), session_name(name)?);
+        self.blocking(move |store| store.load(&id)).await
+    }
+
+    /// Drop the session `name` of `owner`, returning how many thoughts it had
+    pub(in crate::interface::mcp) async fn reset(&self, owner: Option<&Identity>, name: Option<&str>) -> anyhow::Result<usize> {
+        let id = session_key(client(owner), session_name(name)?);
+        let _guard = self.lock(&id).await;
+        let removed = self.blocking(move |store| store.store.remove(&id)).await?;
+        Ok(removed.map_or(0, |session| session.thinking.get_thought_history().len()))
+    }
+
+    /// Sessions of `owner` that have not expired, most recently updated first
+    pub(in crate::interface::mcp) async fn list(&self, owner: Option<&Identity>) -> anyhow::Result<Vec<ThinkingSession>> {
+        let (sessions, _) = self.blocking(SessionStore::sweep).await?;
+        let mut sessions: Vec<_> = sessions.into_iter().filter(|session| session.owner.as_deref() == client(owner)).collect();
+        sessions.sort_by(|a, b| b.updated_at.cmp(&a.updated_at).then_with(|| a.name.cmp(&b.name)));
+        Ok(sessions)
+    }
+
+    /// Remove every expired session, returning how many there were. Meant for
+    /// startup, before any tool call, so it uses the store directly.
+    pub(in crate::interface::mcp) fn expire(&self) -> anyhow::Result<usize> {
+        Ok(self.store.sweep()?.1)
+    }
+
+    /// Wait until no other call is working on session `id`
+    async fn lock(&self, id: &str) -> OwnedMutexGuard<()> {
+        let lock = {
+            let mut locks = self.locks.lock().unwrap();
+            // Locks of sessions nobody is working on are not needed any more
+            locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+            locks.entry(id.to_string()).or_default().clone()
+        };
+        lock.lock_owned().await
+    }
+
+    /// Run `f` on the blocking thread pool, since stores may do file I/O
+    async fn blocking<R: Send + 'static>(&self, f: impl FnOnce(&SessionStore) -> anyhow::Result<R> + Send + 'static) -> anyhow::Result<R> {
+        let store = self.store.clone();
+        tokio::task::spawn_blocking(move || f(&store)).await?
+    }
+}
+
+/// The store and how long its sessions last, cheap to clone into a blocking task
+#[derive(Clone)]
+struct SessionStore {
+    store: Arc<dyn ThinkingStore>,
+    /// sessions untouched for longer than this are dropped; `None` keeps them
+    ttl: Option<Duration>,
+}
+
+impl SessionStore {
+    /// Remove the expired sessions and return the others, with the number removed
+    fn sweep(&self) -> anyhow::Result<(Vec<ThinkingSession>, usize)> {
+        let (expired, live): (Vec<_>, Vec<_>) = self.store.list()?.into_iter().partition(|session| self.expired(session));
+        for session in &expired {
+            self.store.remove(&session.id)?;
+        }
+        Ok((live, expired.len()))
+    }
+
+    fn load(&self, id: &str) -> anyhow::Result<Option<ThinkingSession>> {
+        match self.store.load(id)? {
+            Some(session) if self.expired(&session) => {
+                self.store.remove(id)?;
+                Ok(None)
+            }
+            session => Ok(session),
+        }
+    }
+
+    fn expired(&self, session: &ThinkingSession) -> bool {
+        match self.ttl {
+            Some(ttl) => now().saturating_sub(session.updated_at) > ttl.as_secs(),
+            None => false,
+        }
+    }
+}
+
+/// The session a call names, else the default one
+fn session_name(name: Option<&str>) -> anyhow::Result<&str> {
+    let name = name.unwrap_or(DEFAULT_SESSION);
+    check_session_id(name)?;
+    Ok(name)
+}
+
+/// Whose sessions to use
+fn client(owner: Option<&Identity>) -> Option<&str> {
+    owner.map(|identity| identity.client.as_str())
+}
+
+/// Arguments of `sequential_thinking`, the input of the reference sequential-thinking server
+/// plus an optional `sessionId`
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct ThoughtArgs {
+    /// Your current thinking step
+    thought: String,
+    /// Whether another thought step is needed
+    next_thought_needed: bool,
+    /// Current thought number
+    #[schemars(range(min = 1))]
+    thought_number: u32,
+    /// Estimated total thoughts needed; may be adjusted up or down as you go
+    #[schemars(range(min = 1))]
+    total_thoughts: u32,
+    /// Whether this revises previous thinking
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    is_revision: Option<bool>,
+    /// Which thought is being reconsidered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(range(min = 1))]
+    revises_thought: Option<u32>,
+    /// Branching point thought number
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(range(min = 1))]
+    branch_from_thought: Option<u32>,
+    /// Branch identifier
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    branch_id: Option<String>,
+    /// Set when reaching the end but realizing more thoughts are needed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    needs_more_thoughts: Option<bool>,
+    /// Session to record into or resume: up to 64 letters, digits, '-' or '_'.
+    /// Defaults to "default"; sessions are kept per client and outlive the connection
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    session_id: Option<String>,
+}
+
+/// Records thoughts sent by the client, one history per session; no provider is called
+pub(super) struct SequentialThinkingTool;
+
+#[async_trait]
+impl Tool for SequentialThinkingTool {
+    type Args = ThoughtArgs;
+
+    const NAME: &'static str = "sequential_thinking";
+    const DESCRIPTION: &'static str = "Think through a problem step by step. Each call records one thought; thoughts can revise earlier ones or branch off into alternatives, and the total can be adjusted as understanding deepens. Compatible with the reference sequential-thinking server; history is kept per session until sequential_thinking_reset, and a sessionId picks the session to record into or resume";
+    const ANNOTATIONS: ToolAnnotations = ToolAnnotations { read_only_hint: false, open_world_hint: false, idempotent_hint: false };
+
+    fn output_schema(&self) -> Option<Value> {
+        Some(json!({
+            "type": "object",
+            "required": ["thoughtNumber", "totalThoughts", "nextThoughtNeeded", "branches", "thoughtHistoryLength"],
+            "properties": {
+                "thoughtNumber": { "type": "integer" },
+                "totalThoughts": { "type": "integer", "description": "Raised to thoughtNumber when a thought goes past the estimate" },
+                "nextThoughtNeeded": { "type": "boolean" },
+                "branches": { "type": "array", "items": { "type": "string" }, "description": "Ids of the branches so far" },
+                "thoughtHistoryLength": { "type": "integer" }
+            }
+        }))
+    }
+
+    async fn call(&self, state: &ServerState, mut args: ThoughtArgs, context: &ToolContext<'_>) -> anyhow::Result<Value> {
+        // Taken out first: the rest is exactly the reference server's input
+        let session_id = args.session_id.take();
+        let input = serde_json::to_value(args)?;
+        let status = state.thinking.with(context.owner, session_id.as_deref(), |session| session.thinking.process_thought(input)).await?;
+        Ok(json!({
+            "content": [ { "type": "text", "text": serde_json::to_string_pretty(&status)? } ],
+            "structuredContent": status
+        }))
+    }
+}
+
+/// Arguments of the tools that work on one whole session
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct SessionArgs {
+    /// Session to work on; defaults to "default"
+    #[serde(default)]
+    session_id: Option<String>,
+}
+
+/// Clears the `sequential_thinking` history of a session
+pub(super) struct ResetThinkingTool;
+
+#[async_trait]
+impl Tool for ResetThinkingTool {
+    type Args = SessionArgs;
+
+    const NAME: &'static str = "sequential_thinking_reset";
+    const DESCRIPTION: &'static str = "Clear the thoughts and branches recorded by sequential_thinking in a session, to start thinking about a new problem";
+    const ANNOTATIONS: ToolAnnotations = ToolAnnotations { read_only_hint: false, open_world_hint: false, idempotent_hint: true };
+
+    async fn call(&self, state: &ServerState, args: SessionArgs, context: &ToolContext<'_>) -> anyhow::Result<Value> {
+        let cleared = state.thinking.reset(context.owner, args.session_id.as_deref()).await?;
+        Ok(json!({
+            "content": [ { "type": "text", "text": format!("Cleared {} thoughts", cleared) } ],
+            "structuredContent": { "cleared": cleared }
+        }))
+    }
+}
+
+/// `sequential_thinking_sessions` takes no arguments
+#[derive(Debug, Deserialize, JsonSchema)]
+pub(super) struct ListSessionsArgs {}
+
+/// Lists the sessions that can be resumed or exported
+pub(super) struct ListSessionsTool;
+
+#[async_trait]
+impl Tool for ListSessionsTool {
+    type Args = ListSessionsArgs;
+
+    const NAME: &'static str = "sequential_thinking_sessions";
+    const DESCRIPTION: &'static str = "List your sequential_thinking sessions, most recently updated first, with their thought counts and branches. Pass a sessionId to sequential_thinking to resume one";
+    const ANNOTATIONS: ToolAnnotations = ToolAnnotations { read_only_hint: true, open_world_hint: false, idempotent_hint: true };
+
+    fn output_schema(&self) -> Option<Value> {
+        Some(json!({
+            "type": "object",
+            "required": ["sessions"],
+            "properties": {
+                "sessions": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["sessionId", "updatedAt", "thoughtCount", "branches"],
+                        "properties": {
+                            "sessionId": { "type": "string" },
+                            "updatedAt": { "type": "integer", "description": "Seconds since the Unix epoch of the last thought" },
+                            "thoughtCount": { "type": "integer" },
+                            "branches": { "type": "array", "items": { "type": "string" } }
+                        }
+                    }
+                }
+            }
+        }))
+    }
+
+    async fn call(&self, state: &ServerState, _args: ListSessionsArgs, context: &ToolContext<'_>) -> anyhow::Result<Value> {
+        let sessions = state.thinking.list(context.owner).await?;
+        let mut lines = vec![format!("{} sessions", sessions.len())];
+        let mut summaries = Vec::new();
+        for session in &sessions {
+            let thought_count = session.thinking.get_thought_history().len();
+            let branches = branches(session);
+            lines.push(format!("- {}: {} thoughts, {} branches", session.name, thought_count, branches.len()));
+            summaries.push(json!({
+                "sessionId": session.name,
+                "updatedAt": session.updated_at,
+                "thoughtCount": thought_count,
+                "branches": branches.keys().collect::<Vec<_>>()
+            }));
+        }
+        Ok(json!({
+            "content": [ { "type": "text", "text": lines.join("\n") } ],
+            "structuredContent": { "sessions": summaries }
+        }))
+    }
+}
+
+/// Returns the whole history of a session
+pub(super) struct ExportThinkingTool;
+
+#[async_trait]
+impl Tool for ExportThinkingTool {
+    type Args = SessionArgs;
+
+    const NAME: &'static str = "sequential_thinking_export";
+    const DESCRIPTION: &'static str = "Export every thought and branch recorded by sequential_thinking in a session, as a markdown trace and as structured data";
+    const ANNOTATIONS: ToolAnnotations = ToolAnnotations { read_only_hint: true, open_world_hint: false, idempotent_hint: true };
+
+    fn output_schema(&self) -> Option<Value> {
+        Some(json!({
+            "type": "object",
+            "required": ["sessionId", "updatedAt", "thoughts", "branches"],
+            "properties": {
+                "sessionId": { "type": "string" },
+                "updatedAt": { "type": "integer", "description": "Seconds since the Unix epoch of the last thought" },
+                "thoughts": { "type": "array", "items": { "type": "object" }, "description": "Thoughts in the order they were recorded, with the reference server's field names" },
+                "branches": { "type": "object", "additionalProperties": { "type": "array", "items": { "type": "object" } }, "description": "Thoughts of each branch, by branch id" }
+            }
+        }))
+    }
+
+    async fn call(&self, state: &ServerState, args: SessionArgs, context: &ToolContext<'_>) -> anyhow::Result<Value> {
+        let Some(session) = state.thinking.get(context.owner, args.session_id.as_deref()).await? else {
+            anyhow::bail!("no thoughts recorded in session {}", args.session_id.as_deref().unwrap_or(DEFAULT_SESSION));
+        };
+        let branches = branches(&session);
+        let trace = render_markdown(session.thinking.get_thought_history(), &branches);
+        Ok(json!({
+            "content": [ { "type": "text", "text": format!("## Session {}\n\n{}", session.name, trace) } ],
+            "structuredContent": {
+                "sessionId": session.name,
+                "updatedAt": session.updated_at,
+                "thoughts": session.thinking.get_thought_history(),
+                "branches": branches
+            }
+        }))
+    }
+}
+
+/// Branches of a session in id order
+fn branches(session: &ThinkingSession) -> BTreeMap<String, Vec<ThoughtData>> {
+    session.thinking.get_branches().iter().map(|(id, thoughts)| (id.clone(), thoughts.clone())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::llm::{LLMError, LLMProvider};
+    use crate::domain::models::{EnhancedPrompt, EnhancementOptions, Prompt};
+    use crate::infrastructure::config::create_test_config;
+    use crate::interface::mcp::server::Notifier;
+    use crate::interface::mcp::tools::{ToolError, ToolRegistry};
+    use crate::infrastructure::thinking_store::FileThinkingStore;
+    use crate::infrastructure::TempDir;
+    use crate::usecases::enhance_prompt::EnhancePrompt;
+
+    struct UnusedProvider;
+
+    #[async_trait]
+    impl LLMProvider for UnusedProvider {
+        async fn enhance(&self, _prompt: Prompt, _options: EnhancementOptions) -> Result<EnhancedPrompt, LLMError> {
+            unreachable!("sequential thinking never calls the provider")
+        }
+    }
+
+    fn state() -> ServerState {
+        ServerState::new(EnhancePrompt::new(Box::new(UnusedProvider), create_test_config()))
+    }
+
+    /// Call a tool as the authenticated `client`, or without authentication
+    async fn call(state: &ServerState, client: Option<&str>, name: &str, arguments: Value) -> Result<Value, ToolError> {
+        let notifier = Notifier::default();
+        let owner = client.map(|client| Identity { client: client.to_string() });
+        let context = ToolContext { notifier: &notifier, progress_token: None, owner: owner.as_ref() };
+        ToolRegistry::builtin().call(state, name, arguments, &context).await
+    }
+
+    fn named(number: u32, total: u32, session_id: &str) -> Value {
+        let mut thought = thought(number, total);
+        thought["sessionId"] = json!(session_id);
+        thought
+    }
+
+    /// A server keeping its sessions in `dir`
+    fn file_state(dir: &TempDir, ttl: Option<Duration>) -> ServerState {
+        state().with_thinking_store(Box::new(FileThinkingStore::open(dir.path()).unwrap()), ttl)
+    }
+
+    fn thought(number: u32, total: u32) -> Value {
+        json!({ "thought": format!("step {}", number), "thoughtNumber": number, "totalThoughts": total, "nextThoughtNeeded": true })
+    }
+
+    #[tokio::test]
+    async fn test_thoughts_accumulate_per_session() {
+        let state = state();
+
+        call(&state, Some("alice"), "sequential_thinking", thought(1, 3)).await.unwrap();
+        let result = call(&state, Some("alice"), "sequential_thinking", thought(2, 3)).await.unwrap();
+        let other = call(&state, Some("bob"), "sequential_thinking", thought(1, 3)).await.unwrap();
+
+        assert_eq!(result["structuredContent"]["thoughtHistoryLength"], 2);
+        assert_eq!(other["structuredContent"]["thoughtHistoryLength"], 1);
+        let text: Value = serde_json::from_str(result["content"][0]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(text, result["structuredContent"]);
+    }
+
+    #[tokio::test]
+    async fn test_revisions_branches_and_growing_total() {
+        let state = state();
+        call(&state, None, "sequential_thinking", thought(1, 2)).await.unwrap();
+        let mut revision = thought(2, 2);
+        revision["isRevision"] = json!(true);
+        revision["revisesThought"] = json!(1);
+        call(&state, None, "sequential_thinking", revision).await.unwrap();
+        let mut branch = thought(3, 2);
+        branch["branchFromThought"] = json!(1);
+        branch["branchId"] = json!("alt");
+        branch["needsMoreThoughts"] = json!(true);
+
+        let result = call(&state, None, "sequential_thinking", branch).await.unwrap();
+
+        let status = &result["structuredContent"];
+        assert_eq!(status["totalThoughts"], 3);
+        assert_eq!(status["branches"], json!(["alt"]));
+        let session = state.thinking.get(None, None).await.unwrap().unwrap();
+        let history = session.thinking.get_thought_history();
+        assert_eq!(history[1].revises_thought, Some(1));
+        assert_eq!(history[2].needs_more_thoughts, Some(true));
+    }
+
+    #[tokio::test]
+    async fn test_reset_clears_only_the_calling_session() {
+        let state = state();
+        call(&state, Some("alice"), "sequential_thinking", thought(1, 3)).await.unwrap();
+        call(&state, Some("alice"), "sequential_thinking", thought(2, 3)).await.unwrap();
+        call(&state, Some("bob"), "sequential_thinking", thought(1, 3)).await.unwrap();
+
+        let result = call(&state, Some("alice"), "sequential_thinking_reset", Value::Null).await.unwrap();
+
+        assert_eq!(result["structuredContent"]["cleared"], 2);
+        let alice = call(&state, Some("alice"), "sequential_thinking", thought(1, 3)).await.unwrap();
+        assert_eq!(alice["structuredContent"]["thoughtHistoryLength"], 1);
+        let bob = call(&state, Some("bob"), "sequential_thinking", thought(2, 3)).await.unwrap();
+        assert_eq!(bob["structuredContent"]["thoughtHistoryLength"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_thought_is_rejected() {
+        let state = state();
+
+        let error = call(&state, None, "sequential_thinking", json!({ "thought": "x", "thoughtNumber": "one" })).await.unwrap_err();
+
+        assert!(matches!(error, ToolError::InvalidArguments(_)));
+        assert_eq!(state.thinking.reset(None, None).await.unwrap(), 0);
+    }
+
+    #[test]
+    fn test_input_schema_uses_reference_names() {
+        let tools = serde_json::to_value(ToolRegistry::builtin().list()).unwrap();
+        let tool = tools.as_array().unwrap().iter().find(|tool| tool["name"] == "sequential_thinking").unwrap();
+
+        assert_eq!(tool["annotations"], json!({ "readOnlyHint": false, "openWorldHint": false, "idempotentHint": false }));
+        let schema = &tool["inputSchema"];
+        assert_eq!(schema["required"], json!(["thought", "nextThoughtNeeded", "thoughtNumber", "totalThoughts"]));
+        assert_eq!(schema["properties"]["thoughtNumber"]["minimum"], 1);
+        assert!(schema["properties"]["needsMoreThoughts"].is_object());
+    }
+
+    #[tokio::test]
+    async fn test_named_sessions_are_kept_per_client() {
+        let state = state();
+        call(&state, Some("alice"), "sequential_thinking", named(1, 2, "plan")).await.unwrap();
+
+        let resumed = call(&state, Some("alice"), "sequential_thinking", named(2, 2, "plan")).await.unwrap();
+        let other = call(&state, Some("bob"), "sequential_thinking", named(1, 2, "plan")).await.unwrap();
+
+        assert_eq!(resumed["structuredContent"]["thoughtHistoryLength"], 2);
+        assert_eq!(other["structuredContent"]["thoughtHistoryLength"], 1);
+        assert!(state.thinking.get(None, Some("plan")).await.unwrap().is_none());
+        let alice = Identity { client: "alice".to_string() };
+        let history = state.thinking.get(Some(&alice), Some("plan")).await.unwrap().unwrap();
+        // The session id is not part of the recorded thought
+        assert_eq!(serde_json::to_value(&history.thinking.get_thought_history()[1]).unwrap().get("sessionId"), None);
+    }
+
+    #[tokio::test]
+    async fn test_other_clients_sessions_are_out_of_reach() {
+        let state = state();
+        call(&state, Some("alice"), "sequential_thinking", named(1, 2, "plan")).await.unwrap();
+
+        let list = call(&state, Some("bob"), "sequential_thinking_sessions", Value::Null).await.unwrap();
+        let export = call(&state, Some("bob"), "sequential_thinking_export", json!({ "sessionId": "plan" })).await;
+        let reset = call(&state, Some("bob"), "sequential_thinking_reset", json!({ "sessionId": "plan" })).await.unwrap();
+
+        assert_eq!(list["structuredContent"]["sessions"], json!([]));
+        assert!(export.is_err());
+        assert_eq!(reset["structuredContent"]["cleared"], 0);
+        let own = call(&state, Some("alice"), "sequential_thinking_sessions", Value::Null).await.unwrap();
+        assert_eq!(own["structuredContent"]["sessions"][0]["sessionId"], "plan");
+    }
+
+    #[tokio::test]
+    async fn test_sessions_survive_a_restart() {
+        let dir = TempDir::new();
+        let before = file_state(&dir, None);
+        call(&before, None, "sequential_thinking", named(1, 3, "plan")).await.unwrap();
+        call(&before, None, "sequential_thinking", named(2, 3, "plan")).await.unwrap();
+        drop(before);
+
+        let after = file_state(&dir, None);
+        let result = call(&after, None, "sequential_thinking", named(3, 3, "plan")).await.unwrap();
+
+        assert_eq!(result["structuredContent"]["thoughtHistoryLength"], 3);
+        let reset = call(&after, None, "sequential_thinking_reset", json!({ "sessionId": "plan" })).await.unwrap();
+        assert_eq!(reset["structuredContent"]["cleared"], 3);
+        assert!(std::fs::read_dir(dir.path()).unwrap().next().is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_concurrent_thoughts_are_all_recorded() {
+        let dir = TempDir::new();
+        let state = Arc::new(file_state(&dir, None));
+        let mut calls = tokio::task::JoinSet::new();
+
+        for number in 1..=8 {
+            let state = state.clone();
+            calls.spawn(async move { call(&state, None, "sequential_thinking", thought(number, 8)).await.unwrap() });
+        }
+        while let Some(joined) = calls.join_next().await {
+            joined.unwrap();
+        }
+
+        let session = state.thinking.get(None, None).await.unwrap().unwrap();
+        assert_eq!(session.thinking.get_thought_history().len(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_list_and_export_sessions() {
+        let state = state();
+        call(&state, None, "sequential_thinking", named(1, 2, "plan")).await.unwrap();
+        let mut branch = named(2, 2, "plan");
+        branch["branchFromThought"] = json!(1);
+        branch["branchId"] = json!("alt");
+        call(&state, None, "sequential_thinking", branch).await.unwrap();
+        call(&state, None, "sequential_thinking", thought(1, 1)).await.unwrap();
+
+        let list = call(&state, None, "sequential_thinking_sessions", Value::Null).await.unwrap();
+        let export = call(&state, None, "sequential_thinking_export", json!({ "sessionId": "plan" })).await.unwrap();
+
+        let mut sessions = list["structuredContent"]["sessions"].as_array().unwrap().clone();
+        sessions.sort_by_key(|session| session["sessionId"].as_str().unwrap().to_string());
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[1]["sessionId"], "plan");
+        assert_eq!(sessions[1]["thoughtCount"], 2);
+        assert_eq!(sessions[1]["branches"], json!(["alt"]));
+        let exported = &export["structuredContent"];
+        assert_eq!(exported["thoughts"][0]["thought"], "step 1");
+        assert_eq!(exported["branches"]["alt"][0]["branchId"], "alt");
+        let text = export["content"][0]["text"].as_str().unwrap();
+        assert!(text.starts_with("## Session plan\n\n### 💭 Thought 1/2"));
+        assert!(text.contains("#### 🌿 Branch 2/2 (from thought 1, ID: alt)"));
+    }
+
+    #[tokio::test]
+    async fn test_export_of_unknown_session_fails() {
+        let state = state();
+
+        let error = call(&state, None, "sequential_thinking_export", json!({ "sessionId": "nothing" })).await.unwrap_err();
+
+        assert_eq!(error.to_string(), "no thoughts recorded in session nothing");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_session_id_is_rejected() {
+        let dir = TempDir::new();
+        let state = file_state(&dir, None);
+
+        let error = call(&state, None, "sequential_thinking", named(1, 1, "../escape")).await.unwrap_err();
+
+        assert!(error.to_string().contains("session id may only contain"));
+        assert!(std::fs::read_dir(dir.path()).unwrap().next().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_expired_sessions_are_dropped() {
+        let store = MemoryThinkingStore::default();
+        let stale = ThinkingSession { updated_at: now() - 120, ..ThinkingSession::new(None, "stale") };
+        store.save(&stale).unwrap();
+        store.save(&ThinkingSession::new(None, "fresh")).unwrap();
+
+        let sessions = ThinkingSessions::new(Box::new(store), Some(Duration::from_secs(60)));
+
+        assert!(sessions.get(None, Some("stale")).await.unwrap().is_none());
+        let names: Vec<_> = sessions.list(None).await.unwrap().into_iter().map(|session| session.name).collect();
+        assert_eq!(names, vec!["fresh"]);
+        assert_eq!(sessions.expire().unwrap(), 0);
+    }
+}
@@ -3,10 +3,21 @@ mod usecases;
 mod infrastructure;
 mod interface;
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use infrastructure::config::Config;
+use infrastructure::providers::build_provider;
 use infrastructure::logger::init_tracing;
-use interface::mcp::server::run_stdio_server;
+use interface::mcp::auth::TokenStore;
+use interface::mcp::http::{run_http_server, HttpOptions};
+use interface::mcp::logging::ClientLogs;
+use interface::mcp::server::{run_stdio_server, ServerOptions, ServerState};
+use domain::templates::PromptCatalog;
+use infrastructure::templates::load_templates;
+use domain::thinking_store::{MemoryThinkingStore, ThinkingStore};
+use infrastructure::thinking_store::FileThinkingStore;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 use tracing::info;
 
@@ -17,9 +28,53 @@ struct Cli {
     #[arg(long, default_value = "info")]
     log_level: String,
 
-    /// Optional: graceful shutdown timeout in seconds
+    /// Optional: how long in-flight requests may run after shutdown, in seconds
     #[arg(long, default_value_t = 5)]
     shutdown_timeout: u64,
+
+    /// Maximum number of tool calls processed concurrently
+    #[arg(long, default_value_t = 8)]
+    max_concurrent_requests: usize,
+
+    /// Transport to serve MCP over
+    #[arg(long, value_enum, default_value_t = Transport::Stdio)]
+    transport: Transport,
+
+    /// Address the HTTP transport listens on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    http_bind: SocketAddr,
+
+    /// JSON file of client names and SHA-256 token digests; enables bearer auth on the HTTP transport
+    #[arg(long)]
+    auth_tokens: Option<PathBuf>,
+
+    /// Close HTTP sessions that send no request for this many seconds; 0 keeps them until DELETE
+    #[arg(long, default_value_t = 60 * 60)]
+    http_session_idle_timeout: u64,
+
+    /// Comma-separated browser origins allowed to call the HTTP transport besides loopback ones
+    #[arg(long, value_delimiter = ',')]
+    allowed_origins: Vec<String>,
+
+    /// Directory of extra prompt templates (.json, .md or .txt) served through MCP prompts
+    #[arg(long)]
+    prompt_templates: Option<PathBuf>,
+
+    /// Directory where sequential_thinking sessions are saved, so they survive restarts; kept in memory when unset
+    #[arg(long)]
+    thinking_sessions: Option<PathBuf>,
+
+    /// Drop sequential_thinking sessions untouched for this many seconds; 0 keeps them forever
+    #[arg(long, default_value_t = 7 * 24 * 60 * 60)]
+    thinking_session_ttl: u64,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Transport {
+    /// newline-delimited JSON-RPC on stdin/stdout, one client per process
+    Stdio,
+    /// MCP streamable HTTP on `/mcp`, shared by many clients
+    Http,
 }
 
 #[tokio::main]
@@ -35,23 +90,72 @@ async fn main() -> anyhow::Result<()> {
         }
     };
 
-    // Initialize logging with config
-    init_tracing(&config.logging.level);
+    // Initialize logging with config; clients can also receive it over MCP
+    let logs = Arc::new(ClientLogs::default());
+    init_tracing(&config.logging.level, logs.clone());
 
     info!("starting anytra");
 
     // Create provider with configuration
-    let openrouter_config = config.openrouter.clone();
-    let provider = match infrastructure::providers::openrouter::OpenRouterClient::new(openrouter_config) {
-        Ok(c) => Box::new(c) as Box<dyn domain::llm::LLMProvider + Send + Sync>,
+    let provider = match build_provider(&config) {
+        Ok(p) => p,
         Err(e) => {
-            eprintln!("Failed to create OpenRouter client: {}", e);
+            eprintln!("Failed to create LLM provider: {}", e);
             std::process::exit(1);
         }
     };
 
+    let mut prompts = PromptCatalog::builtin();
+    if let Some(ref dir) = cli.prompt_templates {
+        match load_templates(dir) {
+            Ok(templates) => prompts.extend(templates),
+            Err(e) => {
+                eprintln!("Failed to load prompt templates: {:#}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let thinking_store: Box<dyn ThinkingStore> = match cli.thinking_sessions {
+        Some(ref dir) => match FileThinkingStore::open(dir) {
+            Ok(store) => Box::new(store),
+            Err(e) => {
+                eprintln!("Failed to open thinking session store: {:#}", e);
+                std::process::exit(1);
+            }
+        },
+        None => Box::new(MemoryThinkingStore::default()),
+    };
+    let thinking_ttl = (cli.thinking_session_ttl > 0).then(|| Duration::from_secs(cli.thinking_session_ttl));
+
     let usecase = usecases::enhance_prompt::EnhancePrompt::new(provider, config);
+    let state = Arc::new(
+        ServerState::new(usecase)
+            .with_prompts(prompts)
+            .with_logs(logs)
+            .with_thinking_store(thinking_store, thinking_ttl),
+    );
 
-    let timeout = Duration::from_secs(cli.shutdown_timeout);
-    run_stdio_server(usecase, timeout).await
+    let options = ServerOptions {
+        shutdown_timeout: Duration::from_secs(cli.shutdown_timeout),
+        max_concurrent_requests: cli.max_concurrent_requests,
+    };
+    match cli.transport {
+        Transport::Stdio => run_stdio_server(state, options).await,
+        Transport::Http => {
+            let tokens = match cli.auth_tokens {
+                Some(path) => match TokenStore::load(&path) {
+                    Ok(tokens) => Some(tokens),
+                    Err(e) => {
+                        eprintln!("Failed to load auth tokens: {:#}", e);
+                        std::process::exit(1);
+                    }
+                },
+                None => None,
+            };
+            let session_idle_timeout = (cli.http_session_idle_timeout > 0).then(|| Duration::from_secs(cli.http_session_idle_timeout));
+            run_http_server(state, options, HttpOptions { bind: cli.http_bind, tokens, session_idle_timeout, allowed_origins: cli.allowed_origins }).await
+        }
+    }
 }
+
@@ -1,9 +1,28 @@
-use crate::domain::llm::LLMProvider;
-use crate::domain::models::{EnhancedPrompt, EnhancementOptions, Prompt};
-use crate::domain::sequential_thinking::SequentialThinking;
+use crate::domain::llm::{LLMError, LLMProvider};
+use crate::domain::local_enhancer::enhance_locally;
+use crate::domain::models::{Degradation, DegradationPolicy, EnhancedPrompt, EnhancementOptions, Prompt, TokenUsage};
+use crate::domain::analysis::analyze;
+use crate::domain::prompting::{critique_is_clean, critique_request, revision_request, CompletionRequest, BRANCH_TEMPERATURES};
+use crate::domain::sequential_thinking::{SequentialThinking, ThoughtData};
 use crate::infrastructure::config::Config;
 use anyhow::Result;
-use serde_json::json;
+use std::collections::BTreeMap;
+use tracing::warn;
+
+/// Upper bound on `thought_count`, each thought costs up to two provider calls
+pub const MAX_THOUGHTS: u32 = 10;
+
+/// Progress reported while an enhancement is running
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressEvent {
+    /// a chunk of text generated by the provider
+    Token(String),
+    /// a sequential thinking step was processed
+    Thought { number: u32, total: u32 },
+}
+
+/// Receives the progress events of a running enhancement
+pub type ProgressSink<'a> = &'a (dyn Fn(ProgressEvent) + Send + Sync);
 
 pub struct EnhancePrompt {
     provider: Box<dyn LLMProvider + Send + Sync>,
@@ -16,44 +35,206 @@ impl EnhancePrompt {
     }
 
     pub async fn execute(&self, prompt: Prompt, options: EnhancementOptions) -> Result<EnhancedPrompt> {
-        let mut enhanced = self.provider.enhance(prompt.clone(), options.clone()).await?;
+        self.run(prompt, options, None).await
+    }
+
+    /// Same as `execute`, streaming provider output and thought steps to `progress`
+    pub async fn execute_with_progress(&self, prompt: Prompt, options: EnhancementOptions, progress: ProgressSink<'_>) -> Result<EnhancedPrompt> {
+        self.run(prompt, options, Some(progress)).await
+    }
+
+    async fn run(&self, prompt: Prompt, options: EnhancementOptions, progress: Option<ProgressSink<'_>>) -> Result<EnhancedPrompt> {
+        let mut enhanced = match self.enhance(prompt.clone(), options.clone(), progress).await {
+            Ok(enhanced) => enhanced,
+            Err(e) => return self.degrade(&prompt, &options, e),
+        };
         crate::domain::validation::validate_enhanced_prompt(&enhanced)?;
-        let confidence = crate::domain::validation::compute_confidence(&enhanced);
-        enhanced.confidence = Some(confidence);
+        let mut usage = enhanced.usage;
+        let mut thoughts = Vec::new();
+        let mut branches = BTreeMap::new();
 
-        // Handle sequential thinking if enabled
+        // Sequential thinking: each thought critiques the current draft, and the
+        // next thought is a revision that addresses that critique
         if options.enable_sequential_thinking.unwrap_or_else(|| self.config.sequential_thinking_enabled()) {
             let mut sequential_thinker = SequentialThinking::new();
-            let thought_count = options.thought_count.unwrap_or(3);
+            let thought_count = options.thought_count.unwrap_or(3).clamp(1, MAX_THOUGHTS);
+            let branch_count = options.branch_count.unwrap_or(1).clamp(1, BRANCH_TEMPERATURES.len() as u32);
 
             for i in 1..=thought_count {
-                let is_last_thought = i == thought_count;
-                let thought_input = json!({
-                    "thought": enhanced.text,
-                    "thoughtNumber": i,
-                    "totalThoughts": thought_count,
-                    "nextThoughtNeeded": !is_last_thought
-                });
-
-                match sequential_thinker.process_thought(thought_input) {
-                    Ok(_) => {
-                        if !is_last_thought {
-                            // Generate next thought based on current enhanced text
-                            let next_options = EnhancementOptions {
-                                enable_sequential_thinking: Some(false), // Disable for intermediate steps
-                                ..options.clone()
-                            };
-                            enhanced = self.provider.enhance(Prompt { text: enhanced.text.clone() }, next_options).await?;
+                // The last draft is returned as is, so a critique of it would go unused
+                let mut stopped = false;
+                let critique = if i < thought_count {
+                    match self.provider.complete(critique_request(&prompt, &options, &enhanced.text)).await {
+                        Ok(critique) => {
+                            usage = TokenUsage::combine(usage, critique.usage);
+                            Some(critique.text.trim().to_string())
+                        }
+                        Err(e) => {
+                            // The draft is still recorded as this thought, just without a critique
+                            self.stop_thinking(&mut enhanced, i, e)?;
+                            stopped = true;
+                            None
+                        }
+                    }
+                } else {
+                    None
+                };
+                let done = stopped || match &critique {
+                    Some(critique) => critique_is_clean(critique),
+                    None => true,
+                };
+
+                let mut thought = ThoughtData::new(enhanced.text.clone(), i, thought_count, !done);
+                if i > 1 {
+                    thought = thought.with_revision(i - 1);
+                }
+                if let Some(critique) = &critique {
+                    thought = thought.with_critique(critique.clone());
+                }
+                sequential_thinker.record_thought(thought);
+                if let Some(progress) = progress {
+                    progress(ProgressEvent::Thought { number: i, total: thought_count });
+                }
+                let Some(critique) = critique.filter(|_| !done) else { break };
+
+                let request = revision_request(&prompt, &options, &enhanced.text, &critique);
+                let revised = if branch_count > 1 {
+                    self.branch(&mut sequential_thinker, request, i, thought_count, branch_count).await
+                } else {
+                    self.complete(request, progress).await
+                };
+                match revised {
+                    Ok(revised) => {
+                        usage = TokenUsage::combine(usage, revised.usage);
+                        // A revision that would not pass as a first draft does not replace one
+                        if let Err(e) = crate::domain::validation::validate_enhanced_prompt(&revised) {
+                            self.stop_thinking(&mut enhanced, i, LLMError::UnexpectedResponse(format!("revision rejected: {}", e)))?;
+                            break;
                         }
+                        // The rationale of the first draft still explains the result
+                        enhanced.text = revised.text;
+                        enhanced.backend = revised.backend.or(enhanced.backend);
                     }
                     Err(e) => {
-                        eprintln!("Sequential thinking error: {}", e);
+                        self.stop_thinking(&mut enhanced, i, e)?;
                         break;
                     }
                 }
             }
+            // Branch entries are kept apart so `thoughts` stays the line of drafts that led to the result
+            thoughts = sequential_thinker.get_thought_history().iter().filter(|t| t.branch_id.is_none()).cloned().collect();
+            branches = sequential_thinker.get_branches().iter().map(|(id, thoughts)| (id.clone(), thoughts.clone())).collect();
+        }
+
+        // Score the draft that is returned, not the first one
+        enhanced.confidence = Some(crate::domain::validation::compute_confidence(&enhanced));
+        enhanced.issues = crate::domain::validation::find_issues(&enhanced.text);
+        enhanced.usage = usage;
+        enhanced.thoughts = thoughts;
+        enhanced.branches = branches;
+        Ok(enhanced)
+    }
+
+    /// One provider call, streamed when someone is listening for progress
+    async fn enhance(&self, prompt: Prompt, options: EnhancementOptions, progress: Option<ProgressSink<'_>>) -> Result<EnhancedPrompt, LLMError> {
+        match progress {
+            Some(progress) => {
+                let on_token = |token: &str| progress(ProgressEvent::Token(token.to_string()));
+                self.provider.enhance_streaming(prompt, options, &on_token).await
+            }
+            None => self.provider.enhance(prompt, options).await,
+        }
+    }
+
+    /// `enhance` for any other request, such as a revision
+    async fn complete(&self, request: CompletionRequest, progress: Option<ProgressSink<'_>>) -> Result<EnhancedPrompt, LLMError> {
+        match progress {
+            Some(progress) => {
+                let on_token = |token: &str| progress(ProgressEvent::Token(token.to_string()));
+                self.provider.complete_streaming(request, &on_token).await
+            }
+            None => self.provider.complete(request).await,
+        }
+    }
+
+    /// Fork `count` alternative revisions of thought `thought` at different
+    /// temperatures, record each as a branch with its local analysis score, and
+    /// return the best one with the usage of all of them. Branches are not
+    /// streamed; a branch that fails is left out unless all of them fail.
+    async fn branch(&self, thinker: &mut SequentialThinking, request: CompletionRequest, thought: u32, total: u32, count: u32) -> Result<EnhancedPrompt, LLMError> {
+        let mut candidates = Vec::new();
+        let mut usage = None;
+        let mut last_error = None;
+        for (index, temperature) in BRANCH_TEMPERATURES.iter().take(count as usize).enumerate() {
+            match self.provider.complete(CompletionRequest { temperature: *temperature, ..request.clone() }).await {
+                Ok(candidate) => {
+                    usage = TokenUsage::combine(usage, candidate.usage);
+                    let score = analyze(&candidate.text).score;
+                    candidates.push((index, score, candidate));
+                }
+                Err(e) => {
+                    warn!(thought, branch = index, error = %e, "branch failed, leaving it out");
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        if candidates.is_empty() {
+            return Err(last_error.unwrap_or_else(|| LLMError::UnexpectedResponse("no branches were tried".to_string())));
+        }
+        // The first of equally scored branches wins, it was generated closest to the plain revision
+        let mut winner = 0;
+        for (position, (_, score, _)) in candidates.iter().enumerate() {
+            if *score > candidates[winner].1 {
+                winner = position;
+            }
+        }
+
+        let mut selected = None;
+        for (position, (index, score, candidate)) in candidates.into_iter().enumerate() {
+            let id = format!("{}{}", thought, (b'a' + index as u8) as char);
+            let branch = ThoughtData::new(candidate.text.clone(), thought + 1, total, thought + 1 < total)
+                .with_branch(thought, id)
+                .with_score(score, position == winner);
+            thinker.record_thought(branch);
+            if position == winner {
+                selected = Some(candidate);
+            }
         }
+        let mut selected = selected.expect("winner is one of the candidates");
+        selected.usage = usage;
+        Ok(selected)
+    }
 
+    /// Handle a provider failure after thought `thought`: keep the last good draft
+    /// instead of discarding the work done so far, unless the policy is to fail
+    fn stop_thinking(&self, enhanced: &mut EnhancedPrompt, thought: u32, error: LLMError) -> Result<()> {
+        if self.config.degradation == DegradationPolicy::Fail {
+            return Err(error.into());
+        }
+        warn!(thought, error = %error, "provider failed during sequential thinking, returning last draft");
+        enhanced.degraded = Some(Degradation {
+            policy: self.config.degradation,
+            reason: format!("sequential thinking stopped after thought {}: {}", thought, error),
+        });
+        Ok(())
+    }
+
+    /// Apply the configured degradation policy to a provider failure
+    fn degrade(&self, prompt: &Prompt, options: &EnhancementOptions, error: LLMError) -> Result<EnhancedPrompt> {
+        let policy = self.config.degradation;
+        let mut enhanced = match policy {
+            DegradationPolicy::Fail => return Err(error.into()),
+            DegradationPolicy::Original => EnhancedPrompt { text: prompt.text.clone(), ..Default::default() },
+            DegradationPolicy::Local => {
+                let mut local = enhance_locally(prompt, options);
+                local.confidence = Some(crate::domain::validation::compute_confidence(&local));
+                local
+            }
+        };
+
+        warn!(?policy, error = %error, "provider failed, returning degraded result");
+        enhanced.degraded = Some(Degradation { policy, reason: error.to_string() });
         Ok(enhanced)
     }
 }
@@ -62,33 +243,15 @@ impl EnhancePrompt {
 mod tests {
     use super::*;
     use crate::domain::llm::{LLMError, LLMProvider};
-    use crate::infrastructure::config::{Config, OpenRouterConfig, SequentialThinkingConfig, LoggingConfig};
+    use crate::infrastructure::config::create_test_config;
     use async_trait::async_trait;
 
-    // Helper function to create test config
-    fn create_test_config() -> Config {
-        Config {
-            openrouter: OpenRouterConfig {
-                api_key: "test-key".to_string(),
-                model: "test-model".to_string(),
-                referer: None,
-                title: None,
-            },
-            sequential_thinking: SequentialThinkingConfig {
-                default_enabled: false, // Disable for tests unless explicitly needed
-            },
-            logging: LoggingConfig {
-                level: "info".to_string(),
-            },
-        }
-    }
-
     struct MockProvider;
 
     #[async_trait]
     impl LLMProvider for MockProvider {
         async fn enhance(&self, prompt: Prompt, _options: EnhancementOptions) -> Result<EnhancedPrompt, LLMError> {
-            Ok(EnhancedPrompt { text: format!("ENH: {} - this is a longer text with enough words to pass the validation check", prompt.text), rationale: None, confidence: None })
+            Ok(EnhancedPrompt { text: format!("ENH: {} - this is a longer text with enough words to pass the validation check", prompt.text), rationale: None, confidence: None, ..Default::default() })
         }
     }
 
@@ -101,6 +264,7 @@ mod tests {
                 text: format!("ENHANCED: {} - this is a longer text with enough words to pass validation", prompt.text),
                 rationale: Some("Made it clearer and more specific".to_string()),
                 confidence: None,
+                ..Default::default()
             })
         }
     }
@@ -141,6 +305,7 @@ mod tests {
             language: Some("en".to_string()),
             enable_sequential_thinking: Some(false),
             thought_count: Some(1),
+            branch_count: None,
         };
 
         let res = usecase
@@ -194,6 +359,7 @@ mod tests {
             language: Some("en".to_string()),
             enable_sequential_thinking: Some(false),
             thought_count: Some(1),
+            branch_count: None,
         };
 
         let res = usecase
@@ -230,6 +396,177 @@ mod tests {
         assert!(res.text.len() > "test prompt".len());
     }
 
+    /// Critiques find an issue until `clean_after` critiques have been made,
+    /// revisions are numbered; every call uses 100 input and 40 output tokens
+    struct ReviewingProvider {
+        clean_after: usize,
+        critiques: std::sync::atomic::AtomicUsize,
+        revisions: std::sync::atomic::AtomicUsize,
+    }
+
+    impl ReviewingProvider {
+        fn new(clean_after: usize) -> Self {
+            Self { clean_after, critiques: Default::default(), revisions: Default::default() }
+        }
+    }
+
+    const USAGE: Option<TokenUsage> = Some(TokenUsage { input_tokens: 100, output_tokens: 40 });
+
+    #[async_trait]
+    impl LLMProvider for ReviewingProvider {
+        async fn enhance(&self, prompt: Prompt, options: EnhancementOptions) -> Result<EnhancedPrompt, LLMError> {
+            let enhanced = MockProviderWithRationale.enhance(prompt, options).await?;
+            Ok(EnhancedPrompt { usage: USAGE, ..enhanced })
+        }
+
+        async fn complete(&self, request: CompletionRequest) -> Result<EnhancedPrompt, LLMError> {
+            let text = if request.system == crate::domain::prompting::CRITIQUE_SYSTEM_PROMPT {
+                let critique = self.critiques.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+                if critique >= self.clean_after { "NO ISSUES".to_string() } else { format!("- Issue {}", critique) }
+            } else {
+                assert!(request.user.ends_with(&format!("Review to address:\n- Issue {}", self.revisions.load(std::sync::atomic::Ordering::SeqCst) + 1)));
+                let revision = self.revisions.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+                format!("Revision {} of the prompt with enough words to pass validation", revision)
+            };
+            Ok(EnhancedPrompt { text, usage: USAGE, ..Default::default() })
+        }
+    }
+
+    fn thinking(thought_count: u32) -> EnhancementOptions {
+        EnhancementOptions { enable_sequential_thinking: Some(true), thought_count: Some(thought_count), ..Default::default() }
+    }
+
+    #[tokio::test]
+    async fn test_sequential_thinking_reports_thoughts_and_total_usage() {
+        let usecase = EnhancePrompt::new(Box::new(ReviewingProvider::new(usize::MAX)), create_test_config());
+
+        let res = usecase.execute(Prompt { text: "test prompt".into() }, thinking(3)).await.unwrap();
+
+        // enhance, then critique and revision for thoughts 1 and 2
+        assert_eq!(res.usage, Some(TokenUsage { input_tokens: 500, output_tokens: 200 }));
+        let numbers: Vec<_> = res.thoughts.iter().map(|t| t.thought_number).collect();
+        assert_eq!(numbers, vec![1, 2, 3]);
+        assert!(res.thoughts[0].thought.starts_with("ENHANCED: test prompt"));
+        assert!(res.thoughts[2].thought.starts_with("Revision 2 of the prompt"));
+        assert_eq!(res.text, res.thoughts[2].thought);
+        assert_eq!(res.rationale.as_deref(), Some("Made it clearer and more specific"));
+        assert_eq!(res.confidence, Some(crate::domain::validation::compute_confidence(&res)));
+    }
+
+    #[tokio::test]
+    async fn test_thoughts_store_critiques_and_revise_the_previous_thought() {
+        let usecase = EnhancePrompt::new(Box::new(ReviewingProvider::new(usize::MAX)), create_test_config());
+
+        let res = usecase.execute(Prompt { text: "test prompt".into() }, thinking(3)).await.unwrap();
+
+        let critiques: Vec<_> = res.thoughts.iter().map(|t| t.critique.as_deref()).collect();
+        assert_eq!(critiques, vec![Some("- Issue 1"), Some("- Issue 2"), None]);
+        assert_eq!(res.thoughts[0].is_revision, None);
+        assert_eq!(res.thoughts[1].revises_thought, Some(1));
+        assert_eq!(res.thoughts[2].revises_thought, Some(2));
+        assert!(!res.thoughts[2].next_thought_needed);
+    }
+
+    #[tokio::test]
+    async fn test_sequential_thinking_stops_when_critique_finds_no_issues() {
+        let usecase = EnhancePrompt::new(Box::new(ReviewingProvider::new(2)), create_test_config());
+
+        let res = usecase.execute(Prompt { text: "test prompt".into() }, thinking(5)).await.unwrap();
+
+        assert_eq!(res.thoughts.len(), 2);
+        assert_eq!(res.thoughts[1].critique.as_deref(), Some("NO ISSUES"));
+        assert!(!res.thoughts[1].next_thought_needed);
+        assert_eq!(res.text, "Revision 1 of the prompt with enough words to pass validation");
+        // enhance, two critiques and one revision
+        assert_eq!(res.usage, Some(TokenUsage { input_tokens: 400, output_tokens: 160 }));
+    }
+
+    /// Always finds an issue; the revision at temperature 0.5 is the one that
+    /// scores best, the one at `failing_temperature` fails
+    struct BranchingProvider {
+        failing_temperature: Option<f32>,
+    }
+
+    #[async_trait]
+    impl LLMProvider for BranchingProvider {
+        async fn enhance(&self, prompt: Prompt, options: EnhancementOptions) -> Result<EnhancedPrompt, LLMError> {
+            let enhanced = MockProvider.enhance(prompt, options).await?;
+            Ok(EnhancedPrompt { usage: USAGE, ..enhanced })
+        }
+
+        async fn complete(&self, request: CompletionRequest) -> Result<EnhancedPrompt, LLMError> {
+            let text = if request.system == crate::domain::prompting::CRITIQUE_SYSTEM_PROMPT {
+                "- Too vague".to_string()
+            } else if Some(request.temperature) == self.failing_temperature {
+                return Err(LLMError::RequestFailed("Provider error".to_string()));
+            } else if request.temperature == 0.5 {
+                "Summarize the incident report for our on-call engineers as a bulleted list of at most five items.".to_string()
+            } else {
+                format!("Revision at temperature {} with enough words to pass validation", request.temperature)
+            };
+            Ok(EnhancedPrompt { text, usage: USAGE, ..Default::default() })
+        }
+    }
+
+    fn branching(branch_count: u32) -> EnhancementOptions {
+        EnhancementOptions { branch_count: Some(branch_count), ..thinking(2) }
+    }
+
+    #[tokio::test]
+    async fn test_branching_keeps_the_best_scoring_revision() {
+        let usecase = EnhancePrompt::new(Box::new(BranchingProvider { failing_temperature: None }), create_test_config());
+
+        let res = usecase.execute(Prompt { text: "test prompt".into() }, branching(3)).await.unwrap();
+
+        assert!(res.text.starts_with("Summarize the incident report"));
+        assert_eq!(res.thoughts.len(), 2);
+        assert_eq!(res.thoughts[1].thought, res.text);
+        assert_eq!(res.thoughts[1].revises_thought, Some(1));
+        let ids: Vec<_> = res.branches.keys().map(String::as_str).collect();
+        assert_eq!(ids, vec!["1a", "1b", "1c"]);
+        let selected: Vec<_> = res.branches.values().map(|branch| branch[0].selected).collect();
+        assert_eq!(selected, vec![Some(false), Some(true), Some(false)]);
+        let best = &res.branches["1b"][0];
+        assert_eq!(best.branch_from_thought, Some(1));
+        assert!(best.score.unwrap() > res.branches["1a"][0].score.unwrap());
+        // enhance, critique and three branches
+        assert_eq!(res.usage, Some(TokenUsage { input_tokens: 500, output_tokens: 200 }));
+    }
+
+    #[tokio::test]
+    async fn test_failed_branches_are_left_out() {
+        let usecase = EnhancePrompt::new(Box::new(BranchingProvider { failing_temperature: Some(0.2) }), create_test_config());
+
+        let res = usecase.execute(Prompt { text: "test prompt".into() }, branching(2)).await.unwrap();
+
+        assert!(res.degraded.is_none());
+        assert_eq!(res.branches.keys().collect::<Vec<_>>(), vec!["1b"]);
+        assert!(res.text.starts_with("Summarize the incident report"));
+
+        // Without branching the same failure stops sequential thinking
+        assert!(usecase.execute(Prompt { text: "test prompt".into() }, thinking(2)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_without_branching_there_are_no_branches() {
+        let usecase = EnhancePrompt::new(Box::new(BranchingProvider { failing_temperature: None }), create_test_config());
+
+        let res = usecase.execute(Prompt { text: "test prompt".into() }, branching(1)).await.unwrap();
+
+        assert!(res.branches.is_empty());
+        assert!(res.text.starts_with("Revision at temperature 0.2"));
+    }
+
+    #[tokio::test]
+    async fn test_without_sequential_thinking_there_is_no_trace() {
+        let usecase = EnhancePrompt::new(Box::new(MockProvider), create_test_config());
+
+        let res = usecase.execute(Prompt { text: "test prompt".into() }, EnhancementOptions::default()).await.unwrap();
+
+        assert!(res.thoughts.is_empty());
+        assert_eq!(res.usage, None);
+    }
+
     #[tokio::test]
     async fn test_usecase_default_sequential_thinking_enabled() {
         let provider = MockProviderWithRationale;
@@ -254,4 +591,134 @@ mod tests {
         assert!(res.text.contains("ENHANCED"));
         assert!(res.text.len() > "test prompt".len());
     }
+
+    fn config_with_policy(degradation: DegradationPolicy) -> Config {
+        Config { degradation, ..create_test_config() }
+    }
+
+    fn no_thinking() -> EnhancementOptions {
+        EnhancementOptions { enable_sequential_thinking: Some(false), ..Default::default() }
+    }
+
+    #[tokio::test]
+    async fn test_degradation_original_returns_prompt_unchanged() {
+        let usecase = EnhancePrompt::new(Box::new(FailingProvider), config_with_policy(DegradationPolicy::Original));
+        let res = usecase.execute(Prompt { text: "test".into() }, no_thinking()).await.unwrap();
+
+        assert_eq!(res.text, "test");
+        let degraded = res.degraded.unwrap();
+        assert_eq!(degraded.policy, DegradationPolicy::Original);
+        assert!(degraded.reason.contains("Provider error"));
+    }
+
+    #[tokio::test]
+    async fn test_degradation_local_returns_rule_based_enhancement() {
+        let usecase = EnhancePrompt::new(Box::new(FailingProvider), config_with_policy(DegradationPolicy::Local));
+        let res = usecase.execute(Prompt { text: "write code to sort a list".into() }, no_thinking()).await.unwrap();
+
+        assert!(res.text.starts_with("Task: Write code to sort a list."));
+        assert!(res.confidence.is_some());
+        assert_eq!(res.degraded.unwrap().policy, DegradationPolicy::Local);
+    }
+
+    /// Succeeds on the first call and fails on every call after it
+    struct FlakyProvider {
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LLMProvider for FlakyProvider {
+        async fn enhance(&self, prompt: Prompt, options: EnhancementOptions) -> Result<EnhancedPrompt, LLMError> {
+            if self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                MockProvider.enhance(prompt, options).await
+            } else {
+                Err(LLMError::RequestFailed("Provider error".to_string()))
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_degradation_during_sequential_thinking_keeps_last_draft() {
+        let options = thinking(3);
+
+        let provider = FlakyProvider { calls: Default::default() };
+        let usecase = EnhancePrompt::new(Box::new(provider), config_with_policy(DegradationPolicy::Original));
+        let res = usecase.execute(Prompt { text: "hello".into() }, options.clone()).await.unwrap();
+        assert!(res.text.starts_with("ENH: hello"));
+        assert!(res.degraded.unwrap().reason.contains("thought 1"));
+        assert_eq!(res.thoughts.len(), 1);
+        assert!(res.thoughts[0].critique.is_none());
+
+        let provider = FlakyProvider { calls: Default::default() };
+        let usecase = EnhancePrompt::new(Box::new(provider), create_test_config());
+        assert!(usecase.execute(Prompt { text: "hello".into() }, options).await.is_err());
+    }
+
+    /// Reviews like `ReviewingProvider` but every revision comes back empty
+    struct EmptyRevisionProvider(ReviewingProvider);
+
+    #[async_trait]
+    impl LLMProvider for EmptyRevisionProvider {
+        async fn enhance(&self, prompt: Prompt, options: EnhancementOptions) -> Result<EnhancedPrompt, LLMError> {
+            self.0.enhance(prompt, options).await
+        }
+
+        async fn complete(&self, request: CompletionRequest) -> Result<EnhancedPrompt, LLMError> {
+            let reply = self.0.complete(request.clone()).await?;
+            if request.system == crate::domain::prompting::CRITIQUE_SYSTEM_PROMPT {
+                Ok(reply)
+            } else {
+                Ok(EnhancedPrompt { text: String::new(), ..reply })
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_thought_count_is_capped() {
+        let usecase = EnhancePrompt::new(Box::new(ReviewingProvider::new(usize::MAX)), create_test_config());
+
+        let res = usecase.execute(Prompt { text: "test prompt".into() }, thinking(u32::MAX)).await.unwrap();
+
+        assert_eq!(res.thoughts.len(), MAX_THOUGHTS as usize);
+        assert_eq!(res.thoughts[0].total_thoughts, MAX_THOUGHTS);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_revision_keeps_previous_draft() {
+        let options = thinking(3);
+
+        let provider = EmptyRevisionProvider(ReviewingProvider::new(usize::MAX));
+        let usecase = EnhancePrompt::new(Box::new(provider), config_with_policy(DegradationPolicy::Original));
+        let res = usecase.execute(Prompt { text: "test prompt".into() }, options.clone()).await.unwrap();
+        assert!(res.text.starts_with("ENHANCED: test prompt"));
+        let reason = res.degraded.unwrap().reason;
+        assert!(reason.contains("thought 1"));
+        assert!(reason.contains("revision rejected"));
+        assert_eq!(res.thoughts.len(), 1);
+
+        let provider = EmptyRevisionProvider(ReviewingProvider::new(usize::MAX));
+        let usecase = EnhancePrompt::new(Box::new(provider), create_test_config());
+        assert!(usecase.execute(Prompt { text: "test prompt".into() }, options).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_progress_reports_tokens_and_thoughts() {
+        let usecase = EnhancePrompt::new(Box::new(MockProvider), create_test_config());
+        let options = EnhancementOptions { enable_sequential_thinking: Some(true), thought_count: Some(2), ..Default::default() };
+
+        let events = std::sync::Mutex::new(Vec::new());
+        let progress = |event: ProgressEvent| events.lock().unwrap().push(event);
+        let res = usecase.execute_with_progress(Prompt { text: "hello".into() }, options.clone(), &progress).await.unwrap();
+
+        let events = events.into_inner().unwrap();
+        assert!(matches!(&events[0], ProgressEvent::Token(text) if text.starts_with("ENH: hello")));
+        assert_eq!(events[1], ProgressEvent::Thought { number: 1, total: 2 });
+        assert!(matches!(events[2], ProgressEvent::Token(_)));
+        assert_eq!(events[3], ProgressEvent::Thought { number: 2, total: 2 });
+        assert_eq!(events.len(), 4);
+
+        // The final result does not depend on whether progress was requested
+        let plain = usecase.execute(Prompt { text: "hello".into() }, options).await.unwrap();
+        assert_eq!(res.text, plain.text);
+    }
 }